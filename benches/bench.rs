@@ -206,7 +206,7 @@ fn parse_nal(c: &mut Criterion) {
     let nal = RefNal::new(&sps[..], &[], true);
     let mut group = c.benchmark_group("parse_nal");
     group.bench_function("rbsp_sps", |b| {
-        b.iter(|| SeqParameterSet::from_bits(rbsp::BitReader::new(&*rbsp)).unwrap())
+        b.iter(|| SeqParameterSet::from_bits(rbsp::BitReader::from_rbsp(&rbsp)).unwrap())
     });
     group.bench_function("nal_sps", |b| {
         b.iter(|| SeqParameterSet::from_bits(nal.rbsp_bits()).unwrap())
@@ -1,10 +1,15 @@
 //! A reader for the NAL Unit framing format defined in _ITU-T Recommendation H.264 - Annex B_,
 //! as used when H264 data is embedded in an MPEG2 Transport Stream
 
+#[cfg(feature = "log")]
 use log::*;
 use memchr;
 
+use crate::nal::Nal;
 use crate::push::{AccumulatedNalHandler, NalAccumulator, NalFragmentHandler};
+use crate::writer::START_CODE;
+use std::io::{BufRead, Write};
+use std::ops::Range;
 
 /// The current state, named for the most recently examined byte.
 #[derive(Debug)]
@@ -81,6 +86,14 @@ struct InUnitState {
 pub struct AnnexBReader<H: NalFragmentHandler> {
     state: ParseState,
     inner: H,
+
+    /// The total number of bytes pushed before the current `push` call.
+    stream_offset: u64,
+
+    /// In `StartTwoZero` state, the number of consecutive `0x00` bytes seen.
+    zeros: u64,
+
+    last_nal_start_offset: Option<u64>,
 }
 impl<H: AccumulatedNalHandler> AnnexBReader<NalAccumulator<H>> {
     /// Constructs an `AnnexBReader` with a `NalAccumulator`.
@@ -109,9 +122,30 @@ impl<H: NalFragmentHandler> AnnexBReader<H> {
         AnnexBReader {
             state: ParseState::Start,
             inner,
+            stream_offset: 0,
+            zeros: 0,
+            last_nal_start_offset: None,
         }
     }
 
+    /// Returns the total number of bytes pushed so far.
+    pub fn stream_offset(&self) -> u64 {
+        self.stream_offset
+    }
+
+    /// Returns the offset within the stream of the start code of the most recently started
+    /// NAL, if any.
+    ///
+    /// The start code is taken to be the `0x00 0x00 0x01` sequence, plus one preceding `0x00`
+    /// byte if present. Any further `0x00` bytes are considered to be leading or trailing zero
+    /// bytes rather than part of the start code.
+    ///
+    /// To find the offset of every NAL, implement [`NalFragmentHandler::nal_start`] (or
+    /// [`AccumulatedNalHandler::nal_start`]), which is called with the same value.
+    pub fn last_nal_start_offset(&self) -> Option<u64> {
+        self.last_nal_start_offset
+    }
+
     /// Gets a reference to the underlying [NalFragmentHandler].
     pub fn fragment_handler_ref(&self) -> &H {
         &self.inner
@@ -143,13 +177,17 @@ impl<H: NalFragmentHandler> AnnexBReader<H> {
                     _ => self.err(b),
                 },
                 ParseState::StartOneZero => match b {
-                    0x00 => self.to(ParseState::StartTwoZero),
+                    0x00 => {
+                        self.zeros = 2;
+                        self.to(ParseState::StartTwoZero)
+                    }
                     _ => self.err(b),
                 },
                 ParseState::StartTwoZero => {
                     match b {
-                        0x00 => (), // keep ignoring further 0x00 bytes
+                        0x00 => self.zeros += 1, // keep ignoring further 0x00 bytes
                         0x01 => {
+                            self.start_nal(i, std::cmp::min(self.zeros, 3));
                             fake_and_start = Some((0, i + 1));
                             self.to(ParseState::InUnit);
                         }
@@ -177,10 +215,12 @@ impl<H: NalFragmentHandler> AnnexBReader<H> {
                     0x00 => {
                         self.maybe_emit(buf, fake_and_start, i, 2, true);
                         fake_and_start = None;
+                        self.zeros = 3;
                         self.to(ParseState::StartTwoZero);
                     }
                     0x01 => {
                         self.maybe_emit(buf, fake_and_start, i, 2, true);
+                        self.start_nal(i, 2);
                         fake_and_start = Some((0, i + 1));
                         self.to(ParseState::InUnit);
                     }
@@ -198,6 +238,7 @@ impl<H: NalFragmentHandler> AnnexBReader<H> {
                 false,
             );
         }
+        self.stream_offset += buf.len() as u64;
     }
 
     /// To be invoked when calling code knows that the end of a sequence of NAL Unit data has been
@@ -221,6 +262,14 @@ impl<H: NalFragmentHandler> AnnexBReader<H> {
         self.to(ParseState::Start);
     }
 
+    /// Notes the start of a NAL, given the index in the current buffer of the `0x01` byte ending
+    /// the start code and the number of `0x00` bytes in the start code preceding it.
+    fn start_nal(&mut self, i: usize, zeros: u64) {
+        let offset = self.stream_offset + i as u64 - zeros;
+        self.last_nal_start_offset = Some(offset);
+        self.inner.nal_start(offset);
+    }
+
     fn to(&mut self, new_state: ParseState) {
         self.state = new_state;
     }
@@ -259,17 +308,131 @@ impl<H: NalFragmentHandler> AnnexBReader<H> {
     }
 }
 
+/// Finds the NAL units within a complete Annex B byte stream held in memory, returning the
+/// range of `data` occupied by each, excluding start codes.
+///
+/// Both three- and four-byte start codes are accepted. Any data preceding the first start code
+/// is ignored, as are empty NAL units. As with [`AnnexBReader`], a NAL unit also ends at a
+/// `0x00 0x00 0x00` sequence; zero bytes at the end of `data` are taken to be
+/// `trailing_zero_8bits` rather than part of the final NAL unit.
+///
+/// ```
+/// use h264_reader::annexb::find_nal_units;
+///
+/// let data = b"\x00\x00\x00\x01\x09\xf0\x00\x00\x01\x06\x01\x01\x80\x00";
+/// assert_eq!(find_nal_units(&data[..]), [4..6, 9..13]);
+/// ```
+pub fn find_nal_units(data: &[u8]) -> Vec<Range<usize>> {
+    let mut nals = Vec::new();
+    let mut start = None;
+    let mut i = 0;
+    while let Some(pos) = memchr::memchr(0x00, &data[i..]) {
+        let zero = i + pos;
+        i = zero + 1;
+        match data.get(zero + 1..zero + 3) {
+            Some([0x00, 0x00]) => push_nal(&mut nals, start.take(), zero),
+            Some([0x00, 0x01]) => {
+                push_nal(&mut nals, start.take(), zero);
+                i = zero + 3;
+                start = Some(i);
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = start {
+        let mut end = data.len();
+        while end > start && data[end - 1] == 0x00 {
+            end -= 1;
+        }
+        push_nal(&mut nals, Some(start), end);
+    }
+    nals
+}
+
+/// Writes the given NAL units to `w` as an Annex B byte stream, each preceded by a four-byte start
+/// code (`00 00 00 01`).
+///
+/// The NAL bytes are copied from [`Nal::reader`], so must already include any emulation
+/// prevention bytes. As with [`crate::writer::NalWriter::write_annexb`], returns an error of kind
+/// [`std::io::ErrorKind::InvalidInput`] on reaching a NAL which is incomplete, empty or ends with
+/// a zero byte, having written the preceding NALs.
+///
+/// ```
+/// use h264_reader::annexb::write_nals;
+/// use h264_reader::nal::RefNal;
+///
+/// let tail = [&b"\x05\x00\x80"[..]];
+/// let nals = [
+///     RefNal::new(&b"\x09\xf0"[..], &[], true),
+///     RefNal::new(&b"\x06"[..], &tail, true),
+/// ];
+/// let mut out = Vec::new();
+/// write_nals(nals, &mut out).unwrap();
+/// assert_eq!(out, b"\x00\x00\x00\x01\x09\xf0\x00\x00\x00\x01\x06\x05\x00\x80");
+/// ```
+pub fn write_nals<N: Nal, W: Write>(
+    nals: impl IntoIterator<Item = N>,
+    w: &mut W,
+) -> std::io::Result<()> {
+    let invalid_input = |msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+    for nal in nals {
+        if !nal.is_complete() {
+            return Err(invalid_input("NAL is incomplete"));
+        }
+        // Find the last byte without copying, so that nothing of an invalid NAL is written.
+        let mut last = None;
+        let mut r = nal.reader();
+        loop {
+            let chunk = r.fill_buf()?;
+            let Some(&b) = chunk.last() else { break };
+            last = Some(b);
+            let len = chunk.len();
+            r.consume(len);
+        }
+        match last {
+            None => return Err(invalid_input("empty NAL")),
+            Some(0x00) => return Err(invalid_input("NAL ends with a zero byte")),
+            Some(_) => {}
+        }
+        w.write_all(&START_CODE)?;
+        let mut r = nal.reader();
+        loop {
+            let chunk = r.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+            w.write_all(chunk)?;
+            let len = chunk.len();
+            r.consume(len);
+        }
+    }
+    Ok(())
+}
+
+fn push_nal(nals: &mut Vec<Range<usize>>, start: Option<usize>, end: usize) {
+    match start {
+        Some(start) if start < end => nals.push(start..end),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nal::RefNal;
     use hex_literal::*;
 
     #[derive(Default)]
     struct MockFragmentHandler {
         ended: u32,
         data: Vec<u8>,
+        starts: Vec<u64>,
     }
     impl NalFragmentHandler for MockFragmentHandler {
+        fn nal_start(&mut self, stream_offset: u64) {
+            self.starts.push(stream_offset);
+        }
+
         fn nal_fragment(&mut self, bufs: &[&[u8]], end: bool) {
             assert!(!bufs.is_empty() || end);
             for buf in bufs {
@@ -386,6 +549,30 @@ mod tests {
         assert_eq!(1, mock.ended);
     }
 
+    #[test]
+    fn nal_start_offsets() {
+        let data = [
+            0, 0, 0, 0, 1, // leading_zero_8bits + start-code
+            3, 4, // NAL data
+            0, 0, 1, // start-code
+            5, // NAL data
+            0, 0, 0, 0, 1, // trailing_zero_8bits + start-code
+            6, // NAL data
+        ];
+        for i in 0..data.len() {
+            let mut r = AnnexBReader::for_fragment_handler(MockFragmentHandler::default());
+            let (head, tail) = data.split_at(i);
+            r.push(head);
+            r.push(tail);
+            assert_eq!(r.last_nal_start_offset(), Some(12));
+            assert_eq!(r.stream_offset(), data.len() as u64);
+            r.reset();
+            let mock = r.into_fragment_handler();
+            assert_eq!(&mock.starts[..], &[1, 7, 12][..], "split at {}", i);
+            assert_eq!(&mock.data[..], &[3, 4, 5, 6][..]);
+        }
+    }
+
     #[test]
     fn split_nal() {
         let mock = MockFragmentHandler::default();
@@ -595,4 +782,52 @@ mod tests {
         assert_eq!(3, mock.ended);
         assert_eq!(&mock.data[..], &expected[..]);
     }
+
+    #[test]
+    fn find_nal_units() {
+        // Leading garbage, a 4-byte start code, a NAL ending in 0x00 0x00 0x00, an empty NAL,
+        // a 3-byte start code, and a final NAL without trailing zeros.
+        let data = hex!(
+            "ff 00 00 00 01 09 f0 00 00 00 00 01 00 00 01 06
+             00 01 80 00 00 01 65 88"
+        );
+        assert_eq!(super::find_nal_units(&data), [5..7, 15..19, 22..24]);
+        assert!(super::find_nal_units(&hex!("00 00 01 00 00")).is_empty());
+        assert!(super::find_nal_units(&[]).is_empty());
+    }
+
+    #[test]
+    fn write_nals() {
+        let data = hex!("00 00 00 01 09 f0 00 00 00 01 06 05 00 80");
+        let nals: Vec<_> = super::find_nal_units(&data)
+            .into_iter()
+            .map(|r| RefNal::new(&data[r], &[], true))
+            .collect();
+        let mut out = Vec::new();
+        super::write_nals(nals, &mut out).unwrap();
+        assert_eq!(out, data);
+
+        let mut out = Vec::new();
+        let incomplete = [
+            RefNal::new(&data[4..6], &[], true),
+            RefNal::new(&data[10..12], &[], false),
+        ];
+        let e = super::write_nals(incomplete, &mut out).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(out, &data[..6]);
+
+        // A trailing zero byte would be taken as part of the following start code.
+        let mut out = Vec::new();
+        let trailing_zero = [RefNal::new(&data[10..13], &[], true)];
+        let e = super::write_nals(trailing_zero, &mut out).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(out.is_empty());
+
+        // Likewise when the zero byte ends a later chunk.
+        let tail = [&data[11..13]];
+        let trailing_zero = [RefNal::new(&data[10..11], &tail, true)];
+        let e = super::write_nals(trailing_zero, &mut out).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(out.is_empty());
+    }
 }
@@ -4,6 +4,7 @@
 
 use crate::nal::sps::{ConstraintFlags, Level, ProfileIdc, SeqParameterSet};
 use crate::nal::{pps, sps, Nal, NalHeader, NalHeaderError, RefNal, UnitType};
+use crate::writer::NalWriter;
 use crate::Context;
 use std::convert::TryFrom;
 
@@ -18,6 +19,10 @@ pub enum AvccError {
     ParamSet(ParamSetError),
     Sps(sps::SpsError),
     Pps(pps::PpsError),
+    /// `length_size_minus_one` was `2`, which ISO/IEC 14496-15 doesn't allow.
+    InvalidLengthSizeMinusOne(u8),
+    /// A NAL couldn't be written, e.g. by [`to_annexb`] because it ends with a zero byte.
+    WriteError(std::io::Error),
 }
 
 pub struct AvcDecoderConfigurationRecord<'buf> {
@@ -144,6 +149,77 @@ impl<'buf> AvcDecoderConfigurationRecord<'buf> {
     }
 }
 
+/// Converts a sample of length-prefixed NAL units, framed as described by `config`, into
+/// _Annex B_ byte stream format, with each NAL preceded by a four-byte start code.
+///
+/// If `insert_params` is true (typically for samples containing an IDR picture), the sequence and
+/// picture parameter sets of `config` are emitted ahead of the sample's own NAL units.
+///
+/// NALs are written with [`crate::writer::NalWriter::write_annexb`], so a NAL ending with a zero
+/// byte is rejected with [`AvccError::WriteError`].
+///
+/// ```
+/// # use h264_reader::avcc::{to_annexb, AvcDecoderConfigurationRecord};
+/// # use std::convert::TryFrom;
+/// let config = [
+///     0x01, 0x42, 0xc0, 0x1e, 0xfd, // length_size_minus_one = 1
+///     0xe0, // no SPS
+///     0x00, // no PPS
+/// ];
+/// let config = AvcDecoderConfigurationRecord::try_from(&config[..]).unwrap();
+/// let sample = [0x00, 0x02, 0x09, 0xf0];
+/// assert_eq!(
+///     to_annexb(&config, &sample, true).unwrap(),
+///     [0x00, 0x00, 0x00, 0x01, 0x09, 0xf0]
+/// );
+/// ```
+pub fn to_annexb(
+    config: &AvcDecoderConfigurationRecord<'_>,
+    sample: &[u8],
+    insert_params: bool,
+) -> Result<Vec<u8>, AvccError> {
+    let length_size_minus_one = config.length_size_minus_one();
+    if length_size_minus_one == 2 {
+        return Err(AvccError::InvalidLengthSizeMinusOne(length_size_minus_one));
+    }
+    let mut out = NalWriter::new(Vec::with_capacity(sample.len() + 16));
+    if insert_params {
+        for param_set in config
+            .sequence_parameter_sets()
+            .chain(config.picture_parameter_sets())
+        {
+            out.write_annexb(param_set.map_err(AvccError::ParamSet)?)
+                .map_err(AvccError::WriteError)?;
+        }
+    }
+    let length_size = usize::from(length_size_minus_one) + 1;
+    let mut pos = 0;
+    while pos < sample.len() {
+        if sample.len() < pos + length_size {
+            return Err(AvccError::NotEnoughData {
+                expected: pos + length_size,
+                actual: sample.len(),
+            });
+        }
+        let len = sample[pos..pos + length_size]
+            .iter()
+            .fold(0usize, |acc, &b| acc << 8 | usize::from(b));
+        pos += length_size;
+        if sample.len() - pos < len {
+            return Err(AvccError::NotEnoughData {
+                expected: pos + len,
+                actual: sample.len(),
+            });
+        }
+        if len > 0 {
+            out.write_annexb(&sample[pos..pos + len])
+                .map_err(AvccError::WriteError)?;
+        }
+        pos += len;
+    }
+    Ok(out.into_inner())
+}
+
 #[derive(Debug)]
 pub enum ParamSetError {
     NalHeader(NalHeaderError),
@@ -227,6 +303,54 @@ mod test {
             .pps_by_id(PicParamSetId::from_u32(0).unwrap())
             .expect("missing pps");
     }
+    #[test]
+    fn to_annexb() {
+        let mut avcc_data = hex!("0142c01e ffe10020 6742c01e b91061ff 78088000 00030080 00001971 3006d600 daf7bdc0 7c2211a8 01000468 de3c80");
+        let avcc = AvcDecoderConfigurationRecord::try_from(&avcc_data[..]).unwrap();
+        let sample = hex!("00000002 6588 00000002 419a");
+        assert_eq!(
+            super::to_annexb(&avcc, &sample, false).unwrap(),
+            hex!("00000001 6588 00000001 419a")
+        );
+        assert_eq!(
+            super::to_annexb(&avcc, &sample, true).unwrap(),
+            hex!(
+                "00000001 6742c01e b91061ff 78088000 00030080 00001971 3006d600 daf7bdc0 7c2211a8
+                 00000001 68de3c80
+                 00000001 6588 00000001 419a"
+            )
+        );
+        assert!(matches!(
+            super::to_annexb(&avcc, &sample[..7], false),
+            Err(AvccError::NotEnoughData {
+                expected: 10,
+                actual: 7
+            })
+        ));
+
+        // A NAL ending in a zero byte would merge into the following start code.
+        assert!(matches!(
+            super::to_annexb(&avcc, &hex!("00000002 6500"), false),
+            Err(AvccError::WriteError(_))
+        ));
+
+        // One-byte lengths.
+        avcc_data[4] = 0xfc;
+        let avcc = AvcDecoderConfigurationRecord::try_from(&avcc_data[..]).unwrap();
+        assert_eq!(
+            super::to_annexb(&avcc, &hex!("02 6588 00 02 419a"), false).unwrap(),
+            hex!("00000001 6588 00000001 419a")
+        );
+
+        // Three-byte lengths aren't allowed.
+        avcc_data[4] = 0xfe;
+        let avcc = AvcDecoderConfigurationRecord::try_from(&avcc_data[..]).unwrap();
+        assert!(matches!(
+            super::to_annexb(&avcc, &hex!("000002 6588"), false),
+            Err(AvccError::InvalidLengthSizeMinusOne(2))
+        ));
+    }
+
     #[test]
     fn sps_with_emulation_protection() {
         // From a Hikvision 2CD2032-I.
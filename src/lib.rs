@@ -3,20 +3,71 @@
 #![forbid(unsafe_code)]
 #![deny(rust_2018_idioms)]
 
+use std::convert::TryFrom;
 use std::fmt::Debug;
 
+// Without the `log` feature, logged messages are discarded (but still type-checked).
+#[cfg(not(feature = "log"))]
+macro_rules! error {
+    ($($arg:tt)+) => {{
+        let _ = format_args!($($arg)+);
+    }};
+}
+#[cfg(not(feature = "log"))]
+macro_rules! warn {
+    ($($arg:tt)+) => {{
+        let _ = format_args!($($arg)+);
+    }};
+}
+
 pub mod annexb;
 pub mod avcc;
+pub mod mpegts;
 pub mod nal;
 pub mod push;
 pub mod rbsp;
+pub mod rtp;
+pub mod writer;
+
+/// Options controlling how strictly some parsers validate their input.
+///
+/// The default is lenient: reserved values are accepted and represented as such (e.g.
+/// [`nal::UnitType::Reserved`]), so that streams using them can still be processed. Strict
+/// parsing is useful when checking a stream's conformance.
+///
+/// Only the parsers which take a `ParseOptions` apply it:
+///
+/// *   [`nal::NalHeader::new_with_options`] checks `nal_unit_type`.
+/// *   [`nal::sps::SeqParameterSet::from_bits_with_options`] checks `reserved_zero_2bits`,
+///     `chroma_format_idc`, `aspect_ratio_idc` and `video_format`.
+/// *   [`nal::sei::SeiReader::with_options`] checks `payload_type`.
+///
+/// Other parsers, including those for PPSs and slice headers, don't take options; they reject
+/// out-of-range values regardless, and have no reserved values to check.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// If set, reserved or invalid values are reported as errors by the parsers listed above.
+    pub strict: bool,
+}
+impl ParseOptions {
+    /// Returns options with [`ParseOptions::strict`] set.
+    pub fn strict() -> Self {
+        ParseOptions { strict: true }
+    }
+}
 
 /// Contextual data that needs to be tracked between evaluations of different portions of H264
 /// syntax.
+///
+/// This holds only the active parameter sets, and isn't generic over any application data. None
+/// of the parsers (including those for SEI messages) take a user context, so application state
+/// should be kept alongside the `Context`, e.g. in the [`push::AccumulatedNalHandler`] which
+/// owns it.
 #[derive(Default, Debug)]
 pub struct Context {
     seq_param_sets: ParamSetMap<nal::sps::SeqParameterSet>,
     pic_param_sets: ParamSetMap<nal::pps::PicParameterSet>,
+    on_replace: ReplaceHook,
 }
 impl Context {
     #[inline]
@@ -34,6 +85,11 @@ impl Context {
     #[inline]
     pub fn put_seq_param_set(&mut self, sps: nal::sps::SeqParameterSet) {
         let i = usize::from(sps.seq_parameter_set_id.id());
+        if let (Some(hook), Some(old)) = (&mut self.on_replace.0, self.seq_param_sets.get(i)) {
+            if *old != sps {
+                hook(ReplacedParamSet::Sps { old, new: &sps });
+            }
+        }
         self.seq_param_sets.put(i, sps);
     }
     #[inline]
@@ -47,8 +103,228 @@ impl Context {
     #[inline]
     pub fn put_pic_param_set(&mut self, pps: nal::pps::PicParameterSet) {
         let i = usize::from(pps.pic_parameter_set_id.id());
+        if let (Some(hook), Some(old)) = (&mut self.on_replace.0, self.pic_param_sets.get(i)) {
+            if *old != pps {
+                hook(ReplacedParamSet::Pps { old, new: &pps });
+            }
+        }
         self.pic_param_sets.put(i, pps);
     }
+
+    /// Sets a callback to be invoked when [`Context::put_seq_param_set`] or
+    /// [`Context::put_pic_param_set`] replaces a stored parameter set with one of the same id but
+    /// different content, e.g. so that a decoder can reinitialize on a mid-stream
+    /// reconfiguration. Repeating an identical parameter set doesn't invoke the callback.
+    ///
+    /// The callback is invoked before the new parameter set is stored.
+    ///
+    /// The callback must be `Send` and `Sync` so that `Context` remains so.
+    pub fn on_replace(&mut self, f: impl FnMut(ReplacedParamSet<'_>) + Send + Sync + 'static) {
+        self.on_replace = ReplaceHook(Some(Box::new(f)));
+    }
+
+    /// Looks up the PPS with the given id, and the SPS it refers to.
+    ///
+    /// Returns `None` if either is missing.
+    pub fn sps_for_pps(
+        &self,
+        pps_id: nal::pps::PicParamSetId,
+    ) -> Option<(&nal::pps::PicParameterSet, &nal::sps::SeqParameterSet)> {
+        let pps = self.pps_by_id(pps_id)?;
+        let sps = self.sps_by_id(pps.seq_parameter_set_id)?;
+        Some((pps, sps))
+    }
+
+    /// Parses all the NALs of an access unit, storing any parameter sets they contain and
+    /// collecting the slice headers and SEI messages. Errors in individual NALs are recorded in
+    /// [`nal::access_unit::AccessUnit::errors`] rather than failing the whole access unit.
+    pub fn parse_access_unit(&mut self, nals: &[nal::RefNal<'_>]) -> nal::access_unit::AccessUnit {
+        nal::access_unit::AccessUnit::parse(self, nals)
+    }
+
+    /// Serializes every stored SPS then every PPS, in order of id, as NALs each preceded by a
+    /// four-byte Annex B start code. This is suitable e.g. for prepending to a keyframe.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidInput`] if a parameter set can't be written; see
+    /// [`nal::sps::SeqParameterSet::write`] and [`nal::pps::PicParameterSet::write`].
+    pub fn to_annexb_params(&self) -> std::io::Result<Vec<u8>> {
+        let sps_nals = self.sps_nals()?;
+        let pps_nals = self.pps_nals()?;
+        let mut w = writer::NalWriter::new(Vec::new());
+        for nal in sps_nals.iter().chain(pps_nals.iter()) {
+            w.write_annexb(nal)?;
+        }
+        Ok(w.into_inner())
+    }
+
+    /// Serializes the stored parameter sets as an `AvcDecoderConfigurationRecord` (the content
+    /// of an MP4 `avcC` box), declaring NAL lengths of `length_size` bytes. The profile,
+    /// compatibility and level fields are taken from the lowest-numbered SPS. This is the
+    /// inverse of [`avcc::AvcDecoderConfigurationRecord::create_context`].
+    ///
+    /// Returns `Ok(None)` if there's no SPS. Fails with [`std::io::ErrorKind::InvalidInput`] if
+    /// `length_size` isn't 1, 2 or 4, or if a parameter set can't be written; see
+    /// [`nal::sps::SeqParameterSet::write`] and [`nal::pps::PicParameterSet::write`].
+    pub fn to_avcc(&self, length_size: u8) -> std::io::Result<Option<Vec<u8>>> {
+        let invalid_input = |msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+        if !matches!(length_size, 1 | 2 | 4) {
+            return Err(invalid_input("length_size must be 1, 2 or 4"));
+        }
+        let first = match self.sps().next() {
+            Some(sps) => sps,
+            None => return Ok(None),
+        };
+        let sps_nals = self.sps_nals()?;
+        let pps_nals = self.pps_nals()?;
+        if sps_nals.len() > 31 {
+            return Err(invalid_input("avcC can hold at most 31 SPSs"));
+        }
+        let mut avcc = vec![
+            1, // configurationVersion
+            first.profile_idc.into(),
+            first.constraint_flags.into(),
+            first.level_idc,
+            0b1111_1100 | (length_size - 1),
+            0b1110_0000 | sps_nals.len() as u8,
+        ];
+        let put_nals = |avcc: &mut Vec<u8>, nals: &[Vec<u8>]| {
+            for nal in nals {
+                let len = u16::try_from(nal.len())
+                    .map_err(|_| invalid_input("parameter set too long for avcC"))?;
+                avcc.extend_from_slice(&len.to_be_bytes());
+                avcc.extend_from_slice(nal);
+            }
+            Ok::<_, std::io::Error>(())
+        };
+        put_nals(&mut avcc, &sps_nals)?;
+        // There are at most 256 PPSs, but the count is a single byte.
+        let num_pps = u8::try_from(pps_nals.len())
+            .map_err(|_| invalid_input("avcC can hold at most 255 PPSs"))?;
+        avcc.push(num_pps);
+        put_nals(&mut avcc, &pps_nals)?;
+        // ISO/IEC 14496-15 adds further fields for profiles other than Baseline, Main and Extended.
+        if !matches!(u8::from(first.profile_idc), 66 | 77 | 88) {
+            let chroma_info = &first.chroma_info;
+            avcc.push(0b1111_1100 | (chroma_info.chroma_format.chroma_format_idc() & 0b11) as u8);
+            avcc.push(0b1111_1000 | chroma_info.bit_depth_luma_minus8);
+            avcc.push(0b1111_1000 | chroma_info.bit_depth_chroma_minus8);
+            avcc.push(0); // numOfSequenceParameterSetExt
+        }
+        Ok(Some(avcc))
+    }
+
+    /// Writes each stored SPS as a NAL, in order of id.
+    fn sps_nals(&self) -> std::io::Result<Vec<Vec<u8>>> {
+        self.sps()
+            .map(|sps| param_set_nal(0x67, |w| sps.write(w)))
+            .collect()
+    }
+
+    /// Writes each stored PPS as a NAL, in order of id.
+    fn pps_nals(&self) -> std::io::Result<Vec<Vec<u8>>> {
+        self.pps()
+            .map(|pps| param_set_nal(0x68, |w| pps.write(w)))
+            .collect()
+    }
+}
+
+fn param_set_nal(
+    header: u8,
+    write: impl FnOnce(&mut rbsp::BitWriter<Vec<u8>>) -> std::io::Result<()>,
+) -> std::io::Result<Vec<u8>> {
+    let mut w = rbsp::BitWriter::new(Vec::new());
+    write(&mut w)?;
+    let header = nal::NalHeader::new(header).unwrap();
+    Ok(rbsp::encode_nal(header, &w.into_writer()))
+}
+
+/// A parameter set being replaced by a different one with the same id, as passed to the
+/// [`Context::on_replace`] callback.
+#[derive(Debug, Clone, Copy)]
+pub enum ReplacedParamSet<'a> {
+    Sps {
+        old: &'a nal::sps::SeqParameterSet,
+        new: &'a nal::sps::SeqParameterSet,
+    },
+    Pps {
+        old: &'a nal::pps::PicParameterSet,
+        new: &'a nal::pps::PicParameterSet,
+    },
+}
+
+type ReplaceFn = dyn FnMut(ReplacedParamSet<'_>) + Send + Sync;
+
+#[derive(Default)]
+struct ReplaceHook(Option<Box<ReplaceFn>>);
+impl Debug for ReplaceHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() { "Some(..)" } else { "None" })
+    }
+}
+
+/// The framing of NAL units within a buffer, as guessed by [`detect_framing`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// NAL units are preceded by start codes, per _ITU-T Recommendation H.264 - Annex B_. See
+    /// [`annexb`].
+    AnnexB,
+    /// NAL units are preceded by big-endian lengths of `length_size` bytes, as in the _ISO Base
+    /// Media File Format_. See [`avcc`].
+    Avcc {
+        length_size: u8,
+    },
+    Unknown,
+}
+
+/// Guesses the framing of the NAL units in `data`.
+///
+/// `data` is taken to be Annex B if it begins with a `00 00 01` or `00 00 00 01` start code.
+/// Otherwise, it's taken to be length-prefixed if, for some length size (tried in the order 4, 2,
+/// 1, the sizes permitted by ISO/IEC 14496-15), the prefixes exactly cover `data` and each
+/// introduces a non-empty NAL with a valid header. Since this is a heuristic, a known framing (e.g. from the container) should be
+/// preferred; notably, a length-prefixed buffer whose first NAL is one byte long is
+/// indistinguishable from Annex B.
+///
+/// ```
+/// use h264_reader::{detect_framing, Framing};
+///
+/// assert_eq!(detect_framing(b"\x00\x00\x00\x01\x09\xf0"), Framing::AnnexB);
+/// assert_eq!(
+///     detect_framing(b"\x00\x02\x09\xf0"),
+///     Framing::Avcc { length_size: 2 }
+/// );
+/// assert_eq!(detect_framing(b"\x00\x05\x09\xf0"), Framing::Unknown);
+/// ```
+pub fn detect_framing(data: &[u8]) -> Framing {
+    if data.starts_with(&[0x00, 0x00, 0x01]) || data.starts_with(&[0x00, 0x00, 0x00, 0x01]) {
+        return Framing::AnnexB;
+    }
+    for length_size in [4, 2, 1] {
+        if is_length_prefixed(data, usize::from(length_size)) {
+            return Framing::Avcc { length_size };
+        }
+    }
+    Framing::Unknown
+}
+
+fn is_length_prefixed(mut data: &[u8], length_size: usize) -> bool {
+    if data.is_empty() {
+        return false;
+    }
+    while !data.is_empty() {
+        if data.len() < length_size {
+            return false;
+        }
+        let (prefix, rest) = data.split_at(length_size);
+        let len = prefix
+            .iter()
+            .fold(0usize, |acc, &b| acc << 8 | usize::from(b));
+        if len == 0 || rest.len() < len || nal::NalHeader::new(rest[0]).is_err() {
+            return false;
+        }
+        data = &rest[len..];
+    }
+    true
 }
 
 /// A map for very small indexes; SPS/PPS IDs must be in `[0, 32)`, and typically only 0 is used.
@@ -87,6 +363,106 @@ impl<T: Debug> Debug for ParamSetMap<T> {
 
 #[cfg(test)]
 mod tests {
+    use super::{detect_framing, Context, Framing, ReplacedParamSet};
+    use crate::avcc::AvcDecoderConfigurationRecord;
+    use crate::nal::pps::{PicParamSetId, PicParameterSet};
+    use crate::nal::sps::SeqParameterSetBuilder;
+    use crate::nal::{Nal, RefNal};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn sps_for_pps() {
+        let mut ctx = Context::new();
+        let pps_id = PicParamSetId::from_u32(0).unwrap();
+        ctx.put_seq_param_set(SeqParameterSetBuilder::default().build());
+        assert!(ctx.sps_for_pps(pps_id).is_none());
+        let pps = RefNal::new(&[0x68, 0xe8, 0x43, 0x8f, 0x13, 0x21, 0x30][..], &[], true);
+        ctx.put_pic_param_set(PicParameterSet::from_bits(&ctx, pps.rbsp_bits()).unwrap());
+        let (pps, sps) = ctx.sps_for_pps(pps_id).unwrap();
+        assert_eq!(pps.pic_parameter_set_id, pps_id);
+        assert_eq!(sps.seq_parameter_set_id, pps.seq_parameter_set_id);
+    }
+
+    #[test]
+    fn context_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Context>();
+    }
+
+    #[test]
+    fn on_replace() {
+        use std::sync::{Arc, Mutex};
+        let mut ctx = Context::new();
+        let replaced = Arc::new(Mutex::new(Vec::new()));
+        let r = replaced.clone();
+        ctx.on_replace(move |p| {
+            r.lock().unwrap().push(match p {
+                ReplacedParamSet::Sps { old, new } => {
+                    assert_ne!(old, new);
+                    ("sps", new.seq_parameter_set_id.id())
+                }
+                ReplacedParamSet::Pps { new, .. } => ("pps", new.pic_parameter_set_id.id()),
+            })
+        });
+        ctx.put_seq_param_set(SeqParameterSetBuilder::default().build());
+        ctx.put_seq_param_set(SeqParameterSetBuilder::default().build());
+        let pps = RefNal::new(&[0x68, 0xe8, 0x43, 0x8f, 0x13, 0x21, 0x30][..], &[], true);
+        let pps = PicParameterSet::from_bits(&ctx, pps.rbsp_bits()).unwrap();
+        ctx.put_pic_param_set(pps.clone());
+        ctx.put_pic_param_set(pps);
+        assert!(replaced.lock().unwrap().is_empty());
+        ctx.put_seq_param_set(SeqParameterSetBuilder::default().dimensions(32, 32).build());
+        assert_eq!(*replaced.lock().unwrap(), [("sps", 0)]);
+    }
+
+    #[test]
+    fn serialize_param_sets() {
+        let mut ctx = Context::new();
+        assert!(ctx.to_avcc(4).unwrap().is_none());
+        assert!(ctx.to_annexb_params().unwrap().is_empty());
+        ctx.put_seq_param_set(SeqParameterSetBuilder::default().build());
+        let pps = RefNal::new(&[0x68, 0xe8, 0x43, 0x8f, 0x13, 0x21, 0x30][..], &[], true);
+        ctx.put_pic_param_set(PicParameterSet::from_bits(&ctx, pps.rbsp_bits()).unwrap());
+
+        let annexb = ctx.to_annexb_params().unwrap();
+        assert!(annexb.starts_with(b"\x00\x00\x00\x01\x67"));
+        assert!(annexb.ends_with(b"\x00\x00\x00\x01\x68\xe8\x43\x8f\x13\x21\x30"));
+
+        assert!(ctx.to_avcc(3).is_err());
+        let avcc = ctx.to_avcc(4).unwrap().unwrap();
+        let avcc = AvcDecoderConfigurationRecord::try_from(&avcc[..]).unwrap();
+        assert_eq!(avcc.length_size_minus_one(), 3);
+        let ctx2 = avcc.create_context().unwrap();
+        assert!(ctx.sps().eq(ctx2.sps()));
+        assert!(ctx.pps().eq(ctx2.pps()));
+    }
+
+    #[test]
+    fn framing() {
+        assert_eq!(detect_framing(b""), Framing::Unknown);
+        assert_eq!(detect_framing(b"\x00\x00\x01\x09\xf0"), Framing::AnnexB);
+        assert_eq!(
+            detect_framing(b"\x00\x00\x00\x02\x09\xf0\x00\x00\x00\x01\x0c"),
+            Framing::Avcc { length_size: 4 }
+        );
+        assert_eq!(
+            detect_framing(b"\x02\x09\xf0\x01\x0c"),
+            Framing::Avcc { length_size: 1 }
+        );
+        // Forbidden zero bit set in the NAL header.
+        assert_eq!(detect_framing(b"\x02\x89\xf0"), Framing::Unknown);
+        // Truncated.
+        assert_eq!(
+            detect_framing(b"\x00\x00\x00\x03\x09\xf0"),
+            Framing::Unknown
+        );
+        // 3-byte lengths aren't permitted.
+        assert_eq!(
+            detect_framing(b"\x00\x00\x02\x09\xf0\x00\x00\x01\x0c"),
+            Framing::Unknown
+        );
+    }
+
     #[test]
     fn map() {
         let mut s = super::ParamSetMap::default();
@@ -0,0 +1,312 @@
+//! Support for H264 carried in an MPEG-2 Transport Stream, where the Annex B byte stream is
+//! split into _PES packets_, as defined in ISO/IEC 13818-1.
+//!
+//! Demultiplexing TS packets (PID filtering, continuity checks etc.) is left to the caller;
+//! [`PesToAnnexB`] takes over from the TS packet payloads of the video PID.
+
+#[cfg(feature = "log")]
+use log::*;
+
+use crate::annexb::AnnexBReader;
+use crate::nal::RefNal;
+use crate::push::{AccumulatedNalHandler, NalAccumulator, NalInterest};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PesError {
+    /// The data supplied as the start of a PES packet didn't begin with `packet_start_code_prefix`.
+    MissingStartCode,
+    /// The PES header wasn't entirely contained in the data supplied as the start of the packet.
+    TruncatedHeader,
+    /// The `stream_id` is of a kind which has no optional PES header and doesn't carry video.
+    UnsupportedStreamId(u8),
+    /// The `PTS_DTS_flags` field had the forbidden value `0b01`.
+    InvalidPtsDtsFlags,
+    /// Data was supplied as the continuation of a PES packet, but no packet had been started.
+    NoPacketStarted,
+}
+
+/// The timestamps from a PES header, in units of a 90kHz clock.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PesTimestamps {
+    /// The 33-bit presentation timestamp, if present.
+    pub pts: Option<u64>,
+    /// The 33-bit decoding timestamp, if present. When absent but `pts` is present, the decoding
+    /// time is equal to the presentation time.
+    pub dts: Option<u64>,
+}
+
+/// [PesToAnnexB] callback which handles partially- or completely-buffered NALs, along with the
+/// timestamps of the PES packet in which each NAL started.
+///
+/// This is the counterpart of [AccumulatedNalHandler]; see there for details of the
+/// `NalInterest` return value.
+pub trait PesNalHandler {
+    fn nal(&mut self, nal: RefNal<'_>, timestamps: PesTimestamps) -> NalInterest;
+}
+impl<F: FnMut(RefNal<'_>, PesTimestamps) -> NalInterest> PesNalHandler for F {
+    fn nal(&mut self, nal: RefNal<'_>, timestamps: PesTimestamps) -> NalInterest {
+        (self)(nal, timestamps)
+    }
+}
+
+/// Tags NALs with the timestamps of the PES packet they started in.
+struct Timestamper<H: PesNalHandler> {
+    inner: H,
+    /// The timestamps of the most recently started PES packet.
+    pes_timestamps: PesTimestamps,
+    /// The timestamps of the PES packet in which the current NAL started.
+    nal_timestamps: PesTimestamps,
+}
+impl<H: PesNalHandler> AccumulatedNalHandler for Timestamper<H> {
+    fn nal(&mut self, nal: RefNal<'_>) -> NalInterest {
+        self.inner.nal(nal, self.nal_timestamps)
+    }
+
+    fn nal_start(&mut self, _stream_offset: u64) {
+        self.nal_timestamps = self.pes_timestamps;
+    }
+}
+
+/// Push parser which strips PES headers from the payloads of MPEG-TS packets carrying H264,
+/// and feeds the resulting Annex B byte stream to an [AnnexBReader].
+///
+/// ```
+/// use h264_reader::mpegts::{PesTimestamps, PesToAnnexB};
+/// use h264_reader::nal::{Nal, RefNal, UnitType};
+/// use h264_reader::push::NalInterest;
+///
+/// let mut calls = Vec::new();
+/// let mut pes = PesToAnnexB::new(|nal: RefNal<'_>, timestamps: PesTimestamps| {
+///     if nal.is_complete() {
+///         calls.push((nal.header().unwrap().nal_unit_type(), timestamps.pts));
+///     }
+///     NalInterest::Buffer
+/// });
+///
+/// // A PES packet with PTS 900, containing an access unit delimiter, split over two TS
+/// // packets.
+/// pes.push(&b"\x00\x00\x01\xe0\x00\x00\x80\x80\x05\x21\x00\x01\x07\x09"[..], true).unwrap();
+/// pes.push(&b"\x00\x00\x00\x01\x09\xf0"[..], false).unwrap();
+/// // The next PES packet has PTS 4500.
+/// pes.push(&b"\x00\x00\x01\xe0\x00\x00\x80\x80\x05\x21\x00\x01\x23\x29"[..], true).unwrap();
+/// pes.push(&b"\x00\x00\x00\x01\x09\xf0"[..], false).unwrap();
+/// pes.reset();
+///
+/// assert_eq!(calls, &[
+///     (UnitType::AccessUnitDelimiter, Some(900)),
+///     (UnitType::AccessUnitDelimiter, Some(4500)),
+/// ]);
+/// ```
+///
+/// Each NAL is reported with the timestamps of the PES packet in which its start code ended.
+/// The timestamps of a PES packet apply to the first access unit which starts within it, so for
+/// a typical stream in which each PES packet contains exactly one access unit, every NAL of the
+/// access unit is reported with that access unit's timestamps.
+pub struct PesToAnnexB<H: PesNalHandler> {
+    reader: AnnexBReader<NalAccumulator<Timestamper<H>>>,
+    in_packet: bool,
+}
+impl<H: PesNalHandler> PesToAnnexB<H> {
+    pub fn new(inner: H) -> Self {
+        PesToAnnexB {
+            reader: AnnexBReader::accumulate(Timestamper {
+                inner,
+                pes_timestamps: PesTimestamps::default(),
+                nal_timestamps: PesTimestamps::default(),
+            }),
+            in_packet: false,
+        }
+    }
+
+    /// Gets a reference to the underlying [PesNalHandler].
+    pub fn nal_handler_ref(&self) -> &H {
+        &self.reader.nal_handler_ref().inner
+    }
+
+    /// Gets a mutable reference to the underlying [PesNalHandler].
+    pub fn nal_handler_mut(&mut self) -> &mut H {
+        &mut self.reader.nal_handler_mut().inner
+    }
+
+    /// Unwraps the `PesToAnnexB<H>`, returning the inner [PesNalHandler].
+    pub fn into_nal_handler(self) -> H {
+        self.reader.into_nal_handler().inner
+    }
+
+    /// Pushes the payload of a TS packet.
+    ///
+    /// `payload_unit_start` should be the TS packet's `payload_unit_start_indicator`. When set,
+    /// the payload must start with a PES header, which must be entirely within this payload.
+    ///
+    /// On error, the payload is discarded, along with any continuations of the same PES packet.
+    pub fn push(&mut self, payload: &[u8], payload_unit_start: bool) -> Result<(), PesError> {
+        if payload_unit_start {
+            self.in_packet = false;
+            let (timestamps, header_len) = parse_pes_header(payload)?;
+            self.reader.nal_handler_mut().pes_timestamps = timestamps;
+            self.in_packet = true;
+            self.reader.push(&payload[header_len..]);
+        } else if self.in_packet {
+            self.reader.push(payload);
+        } else {
+            return Err(PesError::NoPacketStarted);
+        }
+        Ok(())
+    }
+
+    /// To be invoked on discontinuity, or when no further data will be pushed. Ends the current
+    /// NAL (if any), and discards the current PES packet.
+    pub fn reset(&mut self) {
+        self.reader.reset();
+        self.in_packet = false;
+    }
+}
+
+/// Parses a PES header, returning its timestamps and length in bytes.
+fn parse_pes_header(data: &[u8]) -> Result<(PesTimestamps, usize), PesError> {
+    if data.len() < 4 {
+        return Err(PesError::TruncatedHeader);
+    }
+    if data[0..3] != [0x00, 0x00, 0x01] {
+        return Err(PesError::MissingStartCode);
+    }
+    let stream_id = data[3];
+    match stream_id {
+        // program_stream_map, padding_stream, private_stream_2, ECM, EMM,
+        // program_stream_directory, DSMCC_stream, ITU-T H.222.1 type E: no optional header.
+        0xbc | 0xbe | 0xbf | 0xf0 | 0xf1 | 0xff | 0xf2 | 0xf8 => {
+            return Err(PesError::UnsupportedStreamId(stream_id))
+        }
+        _ => {}
+    }
+    if data.len() < 9 {
+        return Err(PesError::TruncatedHeader);
+    }
+    if data[6] & 0b1100_0000 != 0b1000_0000 {
+        warn!("PES header has unexpected marker bits {:#04x}", data[6]);
+    }
+    let pts_dts_flags = data[7] >> 6;
+    let header_len = 9 + usize::from(data[8]);
+    if data.len() < header_len {
+        return Err(PesError::TruncatedHeader);
+    }
+    let fields = &data[9..header_len];
+    let timestamps = match pts_dts_flags {
+        0b00 => PesTimestamps::default(),
+        0b10 => PesTimestamps {
+            pts: Some(read_timestamp(fields, 0)?),
+            dts: None,
+        },
+        0b11 => PesTimestamps {
+            pts: Some(read_timestamp(fields, 0)?),
+            dts: Some(read_timestamp(fields, 5)?),
+        },
+        _ => return Err(PesError::InvalidPtsDtsFlags),
+    };
+    Ok((timestamps, header_len))
+}
+
+/// Reads a 33-bit timestamp, which is encoded in 5 bytes with interspersed marker bits.
+fn read_timestamp(fields: &[u8], offset: usize) -> Result<u64, PesError> {
+    let b = fields
+        .get(offset..offset + 5)
+        .ok_or(PesError::TruncatedHeader)?;
+    if b[0] & 1 == 0 || b[2] & 1 == 0 || b[4] & 1 == 0 {
+        warn!("PES timestamp has unexpected marker bits");
+    }
+    Ok(u64::from(b[0] >> 1 & 0b111) << 30
+        | u64::from(b[1]) << 22
+        | u64::from(b[2] >> 1) << 15
+        | u64::from(b[3]) << 7
+        | u64::from(b[4] >> 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nal::{Nal, UnitType};
+    use hex_literal::hex;
+
+    #[test]
+    fn pts_and_dts() {
+        // PTS = 0x1_2345_6789, DTS = 0x0_0000_0001.
+        let data = hex!(
+            "00 00 01 e0 00 00 80 c0 0a
+             39 8d 15 cf 13
+             11 00 01 00 03
+             00 00 01 09 f0"
+        );
+        let (timestamps, header_len) = parse_pes_header(&data).unwrap();
+        assert_eq!(
+            timestamps,
+            PesTimestamps {
+                pts: Some(0x1_2345_6789),
+                dts: Some(1),
+            }
+        );
+        assert_eq!(header_len, 19);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_pes_header(&hex!("00 00 02 e0 00 00 80 00 00")),
+            Err(PesError::MissingStartCode)
+        );
+        assert_eq!(
+            parse_pes_header(&hex!("00 00 01 be 00 00")),
+            Err(PesError::UnsupportedStreamId(0xbe))
+        );
+        assert_eq!(
+            parse_pes_header(&hex!("00 00 01 e0 00 00 80 80 05 21 00")),
+            Err(PesError::TruncatedHeader)
+        );
+        assert_eq!(
+            parse_pes_header(&hex!("00 00 01 e0 00 00 80 40 00")),
+            Err(PesError::InvalidPtsDtsFlags)
+        );
+    }
+
+    #[test]
+    fn push() {
+        let mut nals = Vec::new();
+        let mut pes = PesToAnnexB::new(|nal: RefNal<'_>, timestamps: PesTimestamps| {
+            if nal.is_complete() {
+                nals.push((nal.header().unwrap().nal_unit_type(), timestamps));
+            }
+            NalInterest::Buffer
+        });
+        assert_eq!(
+            pes.push(&hex!("00 00 00 01 09 f0"), false),
+            Err(PesError::NoPacketStarted)
+        );
+        // No timestamps; an access unit delimiter, and the start of an SEI.
+        pes.push(
+            &hex!("00 00 01 e0 00 00 80 00 00 00 00 01 09 f0 00 00 01 06"),
+            true,
+        )
+        .unwrap();
+        // The SEI continues into the next packet, but is reported with the timestamps of the
+        // packet it started in.
+        pes.push(
+            &hex!("00 00 01 e0 00 00 80 80 05 21 00 01 07 09 05 80"),
+            true,
+        )
+        .unwrap();
+        pes.push(&hex!("00 00 00 01 09 f0"), false).unwrap();
+        pes.reset();
+        drop(pes);
+        let pts_900 = PesTimestamps {
+            pts: Some(900),
+            dts: None,
+        };
+        assert_eq!(
+            nals,
+            &[
+                (UnitType::AccessUnitDelimiter, PesTimestamps::default()),
+                (UnitType::SEI, PesTimestamps::default()),
+                (UnitType::AccessUnitDelimiter, pts_900),
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Parsing of all the NALs making up an _access unit_ (the NALs of a single coded picture, along
+//! with its associated parameter sets and SEI messages) in one step.
+//!
+//! See [`crate::Context::parse_access_unit`].
+
+use crate::nal::pps::{PicParameterSet, PpsError};
+use crate::nal::sei::{OwnedSeiMessage, SeiReader};
+use crate::nal::slice::{SliceHeader, SliceHeaderError};
+use crate::nal::sps::{SeqParameterSet, SpsError};
+use crate::nal::{Nal, NalHeaderError, RefNal, UnitType};
+use crate::rbsp::{BitRead, BitReaderError};
+use crate::Context;
+
+/// An error encountered while parsing one of the NALs of an [`AccessUnit`].
+#[derive(Debug)]
+pub enum AccessUnitError {
+    NalHeader(NalHeaderError),
+    AccessUnitDelimiter(BitReaderError),
+    Sps(SpsError),
+    Pps(PpsError),
+    Sei(BitReaderError),
+    SliceHeader(SliceHeaderError),
+}
+
+/// The parsed contents of an access unit, as returned by [`Context::parse_access_unit`].
+#[derive(Debug, Default)]
+pub struct AccessUnit {
+    /// The `primary_pic_type` of the access unit delimiter, if there was one.
+    pub primary_pic_type: Option<u8>,
+    /// The SPS referenced by the first slice in the access unit.
+    pub sps: Option<SeqParameterSet>,
+    /// The PPS referenced by the first slice in the access unit.
+    pub pps: Option<PicParameterSet>,
+    /// The headers of all slices which were successfully parsed, in order.
+    pub slices: Vec<SliceHeader>,
+    /// The messages of all SEI NALs, in order. If an SEI NAL is malformed, the messages preceding
+    /// the error are included.
+    pub sei_messages: Vec<OwnedSeiMessage>,
+    /// Errors encountered, each along with the index within the access unit of the NAL it
+    /// applies to. The remaining fields contain whatever could be parsed from the other NALs.
+    pub errors: Vec<(usize, AccessUnitError)>,
+}
+
+impl AccessUnit {
+    /// Parses each of the given NALs. Any SPS and PPS NALs are stored in `ctx`, so that they
+    /// apply to later slices of this and subsequent access units.
+    ///
+    /// NAL types other than access unit delimiters, parameter sets, SEI and slices without
+    /// partitioning (or partition A) are ignored.
+    pub fn parse(ctx: &mut Context, nals: &[RefNal<'_>]) -> AccessUnit {
+        let mut au = AccessUnit::default();
+        let mut scratch = Vec::new();
+        for (i, nal) in nals.iter().enumerate() {
+            if let Err(e) = au.parse_nal(ctx, nal, &mut scratch) {
+                au.errors.push((i, e));
+            }
+        }
+        au
+    }
+
+    fn parse_nal(
+        &mut self,
+        ctx: &mut Context,
+        nal: &RefNal<'_>,
+        scratch: &mut Vec<u8>,
+    ) -> Result<(), AccessUnitError> {
+        let header = nal.header().map_err(AccessUnitError::NalHeader)?;
+        match header.nal_unit_type() {
+            UnitType::AccessUnitDelimiter => {
+                let primary_pic_type = nal
+                    .rbsp_bits()
+                    .read_u8(3, "primary_pic_type")
+                    .map_err(AccessUnitError::AccessUnitDelimiter)?;
+                self.primary_pic_type = Some(primary_pic_type);
+            }
+            UnitType::SeqParameterSet => {
+                let sps =
+                    SeqParameterSet::from_bits(nal.rbsp_bits()).map_err(AccessUnitError::Sps)?;
+                ctx.put_seq_param_set(sps);
+            }
+            UnitType::PicParameterSet => {
+                let pps = PicParameterSet::from_bits(ctx, nal.rbsp_bits())
+                    .map_err(AccessUnitError::Pps)?;
+                ctx.put_pic_param_set(pps);
+            }
+            UnitType::SEI => {
+                // Keep the messages preceding a malformed one, rather than dropping the lot.
+                let mut reader = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), scratch);
+                while let Some(msg) = reader.next().map_err(AccessUnitError::Sei)? {
+                    self.sei_messages.push(OwnedSeiMessage::from(msg));
+                }
+            }
+            UnitType::SliceLayerWithoutPartitioningIdr
+            | UnitType::SliceLayerWithoutPartitioningNonIdr
+            | UnitType::SliceDataPartitionALayer => {
+                let (slice, sps, pps) =
+                    SliceHeader::from_nal(ctx, nal).map_err(AccessUnitError::SliceHeader)?;
+                if self.sps.is_none() {
+                    self.sps = Some(sps.clone());
+                    self.pps = Some(pps.clone());
+                }
+                self.slices.push(slice);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nal::sei::HeaderType;
+    use hex_literal::hex;
+
+    #[test]
+    fn parse() {
+        let aud = hex!("09 f0");
+        let sps = hex!(
+            "67 64 00 0A AC 72 84 44 26 84 00 00 03
+            00 04 00 00 03 00 CA 3C 48 96 11 80"
+        );
+        let pps = hex!("68 E8 43 8F 13 21 30");
+        let sei = hex!("06 06 01 84 80");
+        let slice = hex!("65 88 81 00 2a 80");
+        // A slice referencing the undefined PPS 5.
+        let bad_slice = hex!("65 88 37 ff");
+        let nals = [
+            RefNal::new(&aud[..], &[], true),
+            RefNal::new(&sps[..], &[], true),
+            RefNal::new(&pps[..], &[], true),
+            RefNal::new(&sei[..], &[], true),
+            RefNal::new(&bad_slice[..], &[], true),
+            RefNal::new(&slice[..], &[], true),
+        ];
+        let mut ctx = Context::default();
+        let au = ctx.parse_access_unit(&nals);
+        assert_eq!(au.primary_pic_type, Some(7));
+        assert_eq!(au.sps.unwrap().seq_parameter_set_id.id(), 0);
+        assert_eq!(au.pps.unwrap().pic_parameter_set_id.id(), 0);
+        assert_eq!(au.slices.len(), 1);
+        assert_eq!(au.sei_messages.len(), 1);
+        assert_eq!(au.errors.len(), 1);
+        assert!(matches!(
+            au.errors[0],
+            (
+                4,
+                AccessUnitError::SliceHeader(SliceHeaderError::UndefinedPicParamSetId(_))
+            )
+        ));
+        assert!(ctx.sps().next().is_some());
+    }
+
+    #[test]
+    fn malformed_sei_keeps_earlier_messages() {
+        // A complete user_data_unregistered message, then a header whose payload is truncated.
+        let sei = hex!("06 05 01 84 05 10 ff");
+        let nals = [RefNal::new(&sei[..], &[], true)];
+        let au = Context::default().parse_access_unit(&nals);
+        assert_eq!(au.sei_messages.len(), 1);
+        assert_eq!(
+            au.sei_messages[0].payload_type,
+            HeaderType::UserDataUnregistered
+        );
+        assert_eq!(au.sei_messages[0].payload, [0x84]);
+        assert_eq!(au.errors.len(), 1);
+        assert!(matches!(au.errors[0], (0, AccessUnitError::Sei(_))));
+    }
+}
@@ -0,0 +1,136 @@
+//! Support for _filler data_ NAL units (`nal_unit_type` 12), which an encoder may insert to pad
+//! the bitstream, e.g. to achieve a constant bitrate.
+
+use crate::nal::{Nal, NalHeaderError, UnitType};
+use std::io::BufRead;
+
+#[derive(Debug)]
+pub enum FillerDataError {
+    /// Reading the NAL failed, or it ended before the `rbsp_trailing_bits`.
+    ReaderError(std::io::Error),
+    InvalidNalHeader(NalHeaderError),
+    /// The NAL isn't a filler data NAL.
+    UnsupportedNalUnitType(UnitType),
+    /// A byte other than `0xff` was found at the given offset within the RBSP, before the
+    /// `rbsp_trailing_bits`.
+    InvalidByte {
+        offset: usize,
+        value: u8,
+    },
+}
+
+/// `filler_data_rbsp()`, per Rec. ITU-T H.264 (03/2010) section 7.3.2.7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillerData {
+    /// The number of `ff_byte`s, which is the number of bytes of padding, excluding the NAL
+    /// header byte, the `rbsp_trailing_bits` and any emulation prevention bytes.
+    pub ff_byte_count: usize,
+}
+impl FillerData {
+    /// Reads the given NAL, which must be a complete filler data NAL.
+    ///
+    /// ```
+    /// use h264_reader::nal::filler::FillerData;
+    /// use h264_reader::nal::RefNal;
+    ///
+    /// let nal = RefNal::new(&b"\x0c\xff\xff\xff\x80"[..], &[], true);
+    /// assert_eq!(FillerData::from_nal(&nal).unwrap().ff_byte_count, 3);
+    /// ```
+    pub fn from_nal<N: Nal>(nal: &N) -> Result<FillerData, FillerDataError> {
+        let header = nal.header().map_err(FillerDataError::InvalidNalHeader)?;
+        if header.nal_unit_type() != UnitType::FillerData {
+            return Err(FillerDataError::UnsupportedNalUnitType(
+                header.nal_unit_type(),
+            ));
+        }
+        let mut r = nal.rbsp_bytes();
+        let mut offset = 0;
+        let mut ff_byte_count = 0;
+        let mut in_trailing_bits = false;
+        loop {
+            let buf = r.fill_buf().map_err(FillerDataError::ReaderError)?;
+            if buf.is_empty() {
+                break;
+            }
+            for &b in buf {
+                // The rbsp_stop_one_bit and alignment bits are 0x80; anything following can
+                // only be trailing zero bytes.
+                let valid = match (in_trailing_bits, b) {
+                    (false, 0xff) => {
+                        ff_byte_count += 1;
+                        true
+                    }
+                    (false, 0x80) => {
+                        in_trailing_bits = true;
+                        true
+                    }
+                    (true, 0x00) => true,
+                    _ => false,
+                };
+                if !valid {
+                    return Err(FillerDataError::InvalidByte { offset, value: b });
+                }
+                offset += 1;
+            }
+            let len = buf.len();
+            r.consume(len);
+        }
+        if !in_trailing_bits {
+            return Err(FillerDataError::ReaderError(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "filler data ended before rbsp_trailing_bits",
+            )));
+        }
+        Ok(FillerData { ff_byte_count })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nal::RefNal;
+    use hex_literal::hex;
+
+    #[test]
+    fn from_nal() {
+        // Split over several buffers.
+        let tail = [&hex!("ff")[..], &hex!("ff 80")[..]];
+        let nal = RefNal::new(&hex!("0c ff ff")[..], &tail, true);
+        assert_eq!(FillerData::from_nal(&nal).unwrap().ff_byte_count, 4);
+
+        let nal = RefNal::new(&hex!("0c 80")[..], &[], true);
+        assert_eq!(FillerData::from_nal(&nal).unwrap().ff_byte_count, 0);
+
+        let nal = RefNal::new(&hex!("0c ff fe ff 80")[..], &[], true);
+        assert!(matches!(
+            FillerData::from_nal(&nal),
+            Err(FillerDataError::InvalidByte {
+                offset: 1,
+                value: 0xfe
+            })
+        ));
+
+        let nal = RefNal::new(&hex!("0c ff 80 ff")[..], &[], true);
+        assert!(matches!(
+            FillerData::from_nal(&nal),
+            Err(FillerDataError::InvalidByte {
+                offset: 2,
+                value: 0xff
+            })
+        ));
+
+        let nal = RefNal::new(&hex!("0c ff ff")[..], &[], true);
+        assert!(matches!(
+            FillerData::from_nal(&nal),
+            Err(FillerDataError::ReaderError(_))
+        ));
+
+        let nal = RefNal::new(&hex!("09 f0")[..], &[], true);
+        assert!(matches!(
+            FillerData::from_nal(&nal),
+            Err(FillerDataError::UnsupportedNalUnitType(
+                UnitType::AccessUnitDelimiter
+            ))
+        ));
+    }
+}
@@ -4,12 +4,17 @@
 //! [`RbspDecoder`](../rbsp/struct.RbspDecoder.html)), where it has been encoded with
 //! 'emulation prevention bytes'.
 
+pub mod access_unit;
+pub mod filler;
 pub mod pps;
+pub mod prefix;
 pub mod sei;
 pub mod slice;
 pub mod sps;
+pub mod visit;
 
 use crate::rbsp;
+use crate::ParseOptions;
 use hex_slice::AsHex;
 use std::fmt;
 
@@ -100,6 +105,30 @@ impl UnitType {
             UnitType::Reserved(v) => v,
         }
     }
+
+    /// True for the `end_of_seq_rbsp()` NAL, which marks that the next picture in decoding
+    /// order (if any) must be an IDR picture.
+    ///
+    /// This NAL has no body; its RBSP is empty.
+    pub fn is_end_of_sequence(self) -> bool {
+        self == UnitType::EndOfSeq
+    }
+
+    /// True for the `end_of_stream_rbsp()` NAL, which marks that no further pictures follow
+    /// in the bitstream. Access unit assemblers should flush any pending access unit.
+    ///
+    /// This NAL has no body; its RBSP is empty.
+    pub fn is_end_of_stream(self) -> bool {
+        self == UnitType::EndOfStream
+    }
+}
+
+impl std::convert::TryFrom<u8> for UnitType {
+    type Error = UnitTypeError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        UnitType::for_id(id)
+    }
 }
 
 #[derive(Debug)]
@@ -115,6 +144,8 @@ pub struct NalHeader(u8);
 pub enum NalHeaderError {
     /// The most significant bit of the header, called `forbidden_zero_bit`, was set to 1.
     ForbiddenZeroBit,
+    /// The `nal_unit_type` was reserved, when parsing with [`ParseOptions::strict`].
+    ReservedUnitType(u8),
 }
 impl NalHeader {
     pub fn new(header_value: u8) -> Result<NalHeader, NalHeaderError> {
@@ -125,6 +156,41 @@ impl NalHeader {
         }
     }
 
+    /// Like [`NalHeader::new`], but never fails. Returns the header along with whether the
+    /// `forbidden_zero_bit` was set, which some transports use to mark a NAL that may be
+    /// corrupt.
+    ///
+    /// The `forbidden_zero_bit` is cleared in the returned header.
+    ///
+    /// ```
+    /// # use h264_reader::nal::{NalHeader, UnitType};
+    /// let (header, forbidden_zero_bit) = NalHeader::new_lenient(0xe5);
+    /// assert!(forbidden_zero_bit);
+    /// assert_eq!(header.nal_unit_type(), UnitType::SliceLayerWithoutPartitioningIdr);
+    /// assert_eq!(u8::from(header), 0x65);
+    /// ```
+    pub fn new_lenient(header_value: u8) -> (NalHeader, bool) {
+        (
+            NalHeader(header_value & 0b0111_1111),
+            header_value & 0b1000_0000 != 0,
+        )
+    }
+
+    /// Like [`NalHeader::new`], but if [`ParseOptions::strict`] is set, also fails on a reserved
+    /// `nal_unit_type`.
+    pub fn new_with_options(
+        header_value: u8,
+        options: &ParseOptions,
+    ) -> Result<NalHeader, NalHeaderError> {
+        let header = NalHeader::new(header_value)?;
+        if options.strict {
+            if let UnitType::Reserved(id) = header.nal_unit_type() {
+                return Err(NalHeaderError::ReservedUnitType(id));
+            }
+        }
+        Ok(header)
+    }
+
     pub fn nal_ref_idc(self) -> u8 {
         (self.0 & 0b0110_0000) >> 5
     }
@@ -132,6 +198,20 @@ impl NalHeader {
     pub fn nal_unit_type(self) -> UnitType {
         UnitType::for_id(self.0 & 0b0001_1111).unwrap()
     }
+
+    /// Returns true if both headers have the same `nal_unit_type`, regardless of `nal_ref_idc`.
+    ///
+    /// ```
+    /// # use h264_reader::nal::NalHeader;
+    /// let idr = NalHeader::new(0x65).unwrap();
+    /// let non_ref_idr = NalHeader::new(0x05).unwrap();
+    /// assert_ne!(idr, non_ref_idr);
+    /// assert!(idr.same_type(non_ref_idr));
+    /// assert!(!idr.same_type(NalHeader::new(0x61).unwrap()));
+    /// ```
+    pub fn same_type(self, other: NalHeader) -> bool {
+        self.0 & 0b0001_1111 == other.0 & 0b0001_1111
+    }
 }
 impl From<NalHeader> for u8 {
     fn from(v: NalHeader) -> Self {
@@ -251,6 +331,84 @@ impl<'a> RefNal<'a> {
             complete,
         }
     }
+
+    /// Constructs a complete NAL from a single Annex B unit, stripping its leading `00 00 01` or
+    /// `00 00 00 01` start code.
+    ///
+    /// Returns `None` if `data` doesn't start with a start code, or if the start code isn't
+    /// followed by a valid NAL header byte.
+    ///
+    /// ```
+    /// # use h264_reader::nal::{Nal, RefNal, UnitType};
+    /// let nal = RefNal::from_annexb_unit(b"\x00\x00\x00\x01\x09\xf0").unwrap();
+    /// assert_eq!(nal.header().unwrap().nal_unit_type(), UnitType::AccessUnitDelimiter);
+    /// assert_eq!(nal.to_hex_string(), "09 f0");
+    /// assert!(RefNal::from_annexb_unit(b"\x00\x00\x01").is_none());
+    /// assert!(RefNal::from_annexb_unit(b"\x09\xf0").is_none());
+    /// ```
+    pub fn from_annexb_unit(data: &'a [u8]) -> Option<Self> {
+        let nal = data
+            .strip_prefix(&[0x00, 0x00, 0x01][..])
+            .or_else(|| data.strip_prefix(&[0x00, 0x00, 0x00, 0x01][..]))?;
+        NalHeader::new(*nal.first()?).ok()?;
+        Some(Self::new(nal, &[], true))
+    }
+
+    /// Renders the NAL's bytes (including the header byte) as space-separated hex, as in its
+    /// `Debug` output. An incomplete NAL is followed by ` ...`.
+    ///
+    /// ```
+    /// # use h264_reader::nal::RefNal;
+    /// let tail = [&b"\x43"[..]];
+    /// let nal = RefNal::new(&b"\x68\xe8"[..], &tail, false);
+    /// assert_eq!(nal.to_hex_string(), "68 e8 43 ...");
+    /// ```
+    pub fn to_hex_string(&self) -> String {
+        self.to_hex_string_limited(usize::MAX)
+    }
+
+    /// Like [`RefNal::to_hex_string`], but renders at most `max_bytes` bytes, followed by ` ...`
+    /// if the NAL was truncated. Useful for logging large NALs. With a `max_bytes` of 0, just
+    /// `...` is returned.
+    ///
+    /// ```
+    /// # use h264_reader::nal::RefNal;
+    /// let nal = RefNal::new(&b"\x68\xe8\x43\x8f"[..], &[], true);
+    /// assert_eq!(nal.to_hex_string_limited(2), "68 e8 ...");
+    /// assert_eq!(nal.to_hex_string_limited(4), "68 e8 43 8f");
+    /// assert_eq!(nal.to_hex_string_limited(0), "...");
+    /// ```
+    pub fn to_hex_string_limited(&self, max_bytes: usize) -> String {
+        let mut s = String::new();
+        self.reader()
+            .write_hex(&mut s, max_bytes)
+            .expect("writing to a String can't fail");
+        s
+    }
+
+    /// Decodes the NAL's RBSP (skipping the header byte and emulation-prevention-three-bytes)
+    /// into a new buffer, across all chunks. Parsing the same NAL several times via
+    /// `BitReader::new(&rbsp[..])` then avoids repeating the emulation prevention processing of
+    /// [`Nal::rbsp_bits`].
+    ///
+    /// Fails with [`std::io::ErrorKind::WouldBlock`] if the NAL is incomplete.
+    ///
+    /// ```
+    /// # use h264_reader::nal::RefNal;
+    /// # use h264_reader::rbsp::{BitRead, BitReader};
+    /// let tail = [&b"\x03\x01"[..]];
+    /// let nal = RefNal::new(&b"\x68\x12\x00\x00"[..], &tail, true);
+    /// let rbsp = nal.rbsp_to_vec().unwrap();
+    /// assert_eq!(rbsp, b"\x12\x00\x00\x01");
+    /// for _ in 0..2 {
+    ///     assert_eq!(BitReader::new(&rbsp[..]).read_u8(8, "first").unwrap(), 0x12);
+    /// }
+    /// ```
+    pub fn rbsp_to_vec(&self) -> Result<Vec<u8>, std::io::Error> {
+        let mut rbsp = Vec::new();
+        std::io::Read::read_to_end(&mut self.rbsp_bytes(), &mut rbsp)?;
+        Ok(rbsp)
+    }
 }
 impl<'a> Nal for RefNal<'a> {
     type BufRead = RefNalReader<'a>;
@@ -291,6 +449,44 @@ impl<'a> std::fmt::Debug for RefNal<'a> {
     }
 }
 
+/// A completely-buffered NAL which owns its bytes, as queued by [`crate::push::NalQueue`].
+///
+/// Borrow it as a [`RefNal`] with [`NalBuf::as_ref_nal`] to parse it.
+#[derive(Clone, Eq, PartialEq)]
+pub struct NalBuf {
+    data: Vec<u8>,
+}
+impl NalBuf {
+    /// Wraps the bytes of a complete NAL, including the header byte and any emulation prevention
+    /// bytes.
+    ///
+    /// Panics if `data` is empty.
+    pub fn new(data: Vec<u8>) -> Self {
+        assert!(!data.is_empty(), "NalBuf must be non-empty");
+        NalBuf { data }
+    }
+
+    /// Borrows this NAL as a [`RefNal`], which implements [`Nal`].
+    pub fn as_ref_nal(&self) -> RefNal<'_> {
+        RefNal::new(&self.data[..], &[], true)
+    }
+
+    /// Returns the NAL's bytes, including the header byte.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..]
+    }
+
+    /// Unwraps the NAL's bytes, including the header byte.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+impl std::fmt::Debug for NalBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.as_ref_nal(), f)
+    }
+}
+
 /// A reader through the bytes of a partially- or fully-buffered [`RefNal`]
 /// that implements [`std::io::BufRead`].
 ///
@@ -304,6 +500,36 @@ pub struct RefNalReader<'a> {
     complete: bool,
 }
 impl<'a> RefNalReader<'a> {
+    /// Writes up to `max_bytes` of the remaining bytes as hex, followed by ` ...` if any were
+    /// omitted or the NAL is incomplete.
+    fn write_hex<W: fmt::Write>(&self, w: &mut W, max_bytes: usize) -> fmt::Result {
+        let mut remaining = max_bytes;
+        let mut truncated = false;
+        let mut wrote = false;
+        for buf in std::iter::once(&self.cur).chain(self.tail) {
+            if remaining == 0 {
+                truncated = !buf.is_empty();
+                break;
+            }
+            let len = std::cmp::min(remaining, buf.len());
+            if wrote {
+                w.write_char(' ')?;
+            }
+            write!(w, "{:02x}", buf[..len].plain_hex(true))?;
+            wrote = true;
+            if len < buf.len() {
+                truncated = true;
+                break;
+            }
+            remaining -= len;
+        }
+        if truncated || !self.complete {
+            // Only separate the marker from any bytes written before it.
+            w.write_str(if wrote { " ..." } else { "..." })?;
+        }
+        Ok(())
+    }
+
     fn next_chunk(&mut self) {
         match self.tail {
             [first, tail @ ..] => {
@@ -356,14 +582,7 @@ impl<'a> std::io::BufRead for RefNalReader<'a> {
 }
 impl<'a> std::fmt::Debug for RefNalReader<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:02x}", self.cur.plain_hex(true))?;
-        for buf in self.tail {
-            write!(f, " {:02x}", buf.plain_hex(true))?;
-        }
-        if !self.complete {
-            f.write_str(" ...")?;
-        }
-        Ok(())
+        self.write_hex(f, usize::MAX)
     }
 }
 
@@ -378,6 +597,49 @@ mod test {
         let h = NalHeader::new(0b0101_0001).unwrap();
         assert_eq!(0b10, h.nal_ref_idc());
         assert_eq!(UnitType::Reserved(17), h.nal_unit_type());
+        assert!(NalHeader::new_with_options(0b0101_0001, &ParseOptions::default()).is_ok());
+        assert!(matches!(
+            NalHeader::new_with_options(0b0101_0001, &ParseOptions::strict()),
+            Err(NalHeaderError::ReservedUnitType(17))
+        ));
+        assert!(NalHeader::new_with_options(0b0110_0111, &ParseOptions::strict()).is_ok());
+
+        assert!(matches!(
+            NalHeader::new(0b1110_0111),
+            Err(NalHeaderError::ForbiddenZeroBit)
+        ));
+        let (h, forbidden_zero_bit) = NalHeader::new_lenient(0b1110_0111);
+        assert!(forbidden_zero_bit);
+        assert_eq!(0b11, h.nal_ref_idc());
+        assert_eq!(UnitType::SeqParameterSet, h.nal_unit_type());
+        assert_eq!(
+            NalHeader::new_lenient(0b0110_0111),
+            (NalHeader::new(0b0110_0111).unwrap(), false)
+        );
+    }
+
+    #[test]
+    fn unit_type_try_from() {
+        use std::convert::TryFrom;
+        for id in 0..=31 {
+            assert_eq!(UnitType::try_from(id).unwrap().id(), id);
+        }
+        assert!(matches!(
+            UnitType::try_from(32),
+            Err(UnitTypeError::ValueOutOfRange(32))
+        ));
+    }
+
+    #[test]
+    fn end_of_seq_and_stream() {
+        let eos = NalHeader::new(0x0a).unwrap().nal_unit_type();
+        assert!(eos.is_end_of_sequence());
+        assert!(!eos.is_end_of_stream());
+        let eob = NalHeader::new(0x0b).unwrap().nal_unit_type();
+        assert!(!eob.is_end_of_sequence());
+        assert!(eob.is_end_of_stream());
+        assert!(!UnitType::SEI.is_end_of_sequence());
+        assert!(!UnitType::SEI.is_end_of_stream());
     }
 
     #[test]
@@ -461,4 +723,38 @@ mod test {
             "00 01 02 03 ..."
         );
     }
+
+    #[test]
+    fn to_hex_string() {
+        let tail = [&b"\x01"[..], &b"\x02\x03"[..]];
+        let nal = RefNal::new(&b"\x68\x00"[..], &tail, true);
+        assert_eq!(nal.to_hex_string(), "68 00 01 02 03");
+        assert_eq!(nal.to_hex_string_limited(1), "68 ...");
+        assert_eq!(nal.to_hex_string_limited(3), "68 00 01 ...");
+        assert_eq!(nal.to_hex_string_limited(4), "68 00 01 02 ...");
+        assert_eq!(nal.to_hex_string_limited(5), "68 00 01 02 03");
+        assert_eq!(nal.to_hex_string_limited(0), "...");
+    }
+
+    #[test]
+    fn from_annexb_unit() {
+        let nal = RefNal::from_annexb_unit(&[0x00, 0x00, 0x01, 0x68, 0xe8]).unwrap();
+        assert_eq!(nal, RefNal::new(&[0x68, 0xe8], &[], true));
+        assert!(RefNal::from_annexb_unit(&[0x00, 0x00, 0x01, 0xe8]).is_none());
+        assert!(RefNal::from_annexb_unit(&[0x00, 0x01, 0x68]).is_none());
+        assert!(RefNal::from_annexb_unit(&[]).is_none());
+    }
+
+    #[test]
+    fn rbsp_to_vec() {
+        // The emulation prevention byte spans the chunk boundary.
+        let tail = [&b"\x00"[..], &b"\x03\x00\x80"[..]];
+        let nal = RefNal::new(&b"\x68\x00"[..], &tail, true);
+        assert_eq!(nal.rbsp_to_vec().unwrap(), b"\x00\x00\x00\x80");
+        let nal = RefNal::new(&b"\x68\x00"[..], &tail, false);
+        assert_eq!(
+            nal.rbsp_to_vec().unwrap_err().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+    }
 }
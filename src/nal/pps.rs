@@ -1,7 +1,8 @@
 use super::sps;
 use crate::nal::sps::{SeqParamSetId, SeqParamSetIdError};
-use crate::rbsp::BitRead;
+use crate::rbsp::{BitRead, BitWrite};
 use crate::{rbsp, Context};
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug)]
 pub enum PpsError {
@@ -22,7 +23,7 @@ impl From<rbsp::BitReaderError> for PpsError {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SliceGroupChangeType {
     BoxOut,
     RasterScan,
@@ -37,9 +38,16 @@ impl SliceGroupChangeType {
             _ => Err(PpsError::InvalidSliceGroupChangeType(id)),
         }
     }
+    fn id(&self) -> u32 {
+        match self {
+            SliceGroupChangeType::BoxOut => 3,
+            SliceGroupChangeType::RasterScan => 4,
+            SliceGroupChangeType::WipeOut => 5,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SliceRect {
     top_left: u32,
     bottom_right: u32,
@@ -53,7 +61,7 @@ impl SliceRect {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SliceGroup {
     Interleaved {
         run_length_minus1: Vec<u32>,
@@ -62,6 +70,7 @@ pub enum SliceGroup {
         num_slice_groups_minus1: u32,
     },
     ForegroundAndLeftover {
+        /// The rectangle of each slice group except the last, which is the leftover region.
         rectangles: Vec<SliceRect>,
     },
     Changing {
@@ -118,11 +127,12 @@ impl SliceGroup {
         r: &mut R,
         num_slice_groups_minus1: u32,
     ) -> Result<Vec<SliceRect>, PpsError> {
-        let mut run_length_minus1 = Vec::with_capacity(num_slice_groups_minus1 as usize + 1);
-        for _ in 0..num_slice_groups_minus1 + 1 {
-            run_length_minus1.push(SliceRect::read(r)?);
+        // The last slice group is the leftover region, so has no rectangle.
+        let mut rectangles = Vec::with_capacity(num_slice_groups_minus1 as usize);
+        for _ in 0..num_slice_groups_minus1 {
+            rectangles.push(SliceRect::read(r)?);
         }
-        Ok(run_length_minus1)
+        Ok(rectangles)
     }
 
     fn read_group_ids<R: BitRead>(
@@ -138,9 +148,76 @@ impl SliceGroup {
         }
         Ok(run_length_minus1)
     }
+
+    /// Writes `num_slice_groups_minus1` and the fields which follow it.
+    fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        match self {
+            SliceGroup::Interleaved { run_length_minus1 } => {
+                w.write_ue(len_minus1(run_length_minus1.len(), "run_length_minus1")?)?;
+                w.write_ue(0)?;
+                for &run_length in run_length_minus1 {
+                    w.write_ue(run_length)?;
+                }
+            }
+            SliceGroup::Dispersed {
+                num_slice_groups_minus1,
+            } => {
+                w.write_ue(*num_slice_groups_minus1)?;
+                w.write_ue(1)?;
+            }
+            SliceGroup::ForegroundAndLeftover { rectangles } => {
+                // There's a rectangle for each slice group but the leftover one.
+                if rectangles.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "rectangles must have at least one entry",
+                    ));
+                }
+                w.write_ue(rectangles.len() as u32)?;
+                w.write_ue(2)?;
+                for rect in rectangles {
+                    w.write_ue(rect.top_left)?;
+                    w.write_ue(rect.bottom_right)?;
+                }
+            }
+            SliceGroup::Changing {
+                change_type,
+                num_slice_groups_minus1,
+                slice_group_change_direction_flag,
+                slice_group_change_rate_minus1,
+            } => {
+                w.write_ue(*num_slice_groups_minus1)?;
+                w.write_ue(change_type.id())?;
+                w.write_bool(*slice_group_change_direction_flag)?;
+                w.write_ue(*slice_group_change_rate_minus1)?;
+            }
+            SliceGroup::ExplicitAssignment {
+                num_slice_groups_minus1,
+                slice_group_id,
+            } => {
+                w.write_ue(*num_slice_groups_minus1)?;
+                w.write_ue(6)?;
+                w.write_ue(len_minus1(slice_group_id.len(), "slice_group_id")?)?;
+                let size = (1f64 + f64::from(*num_slice_groups_minus1)).log2().ceil() as u32;
+                for &id in slice_group_id {
+                    w.write_u32(size, id)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone)]
+fn len_minus1(len: usize, name: &'static str) -> std::io::Result<u32> {
+    len.checked_sub(1).map(|l| l as u32).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} must have at least one entry", name),
+        )
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PicScalingMatrix {
     // TODO
 }
@@ -183,7 +260,7 @@ impl PicScalingMatrix {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PicParameterSetExtra {
     pub transform_8x8_mode_flag: bool,
     pub pic_scaling_matrix: Option<PicScalingMatrix>,
@@ -205,6 +282,17 @@ impl PicParameterSetExtra {
             None
         })
     }
+    fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_bool(self.transform_8x8_mode_flag)?;
+        if self.pic_scaling_matrix.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "can't write pic_scaling_matrix, as the parser doesn't retain its lists",
+            ));
+        }
+        w.write_bool(false)?; // pic_scaling_matrix_present_flag
+        w.write_se(self.second_chroma_qp_index_offset)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -227,7 +315,7 @@ impl PicParamSetId {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PicParameterSet {
     pub pic_parameter_set_id: PicParamSetId,
     pub seq_parameter_set_id: SeqParamSetId,
@@ -285,6 +373,87 @@ impl PicParameterSet {
         Ok(pps)
     }
 
+    /// Writes this PPS as RBSP, including the `rbsp_trailing_bits`; the inverse of
+    /// [`PicParameterSet::from_bits`]. Use [`crate::rbsp::encode_nal`] to form a NAL from the
+    /// result.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidInput`] if
+    /// [`PicParameterSetExtra::pic_scaling_matrix`] is set, as the parser doesn't retain the
+    /// scaling lists needed to write it.
+    pub fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_ue(u32::from(self.pic_parameter_set_id.id()))?;
+        w.write_ue(u32::from(self.seq_parameter_set_id.id()))?;
+        w.write_bool(self.entropy_coding_mode_flag)?;
+        w.write_bool(self.bottom_field_pic_order_in_frame_present_flag)?;
+        match self.slice_groups {
+            Some(ref slice_groups) => slice_groups.write(w)?,
+            None => w.write_ue(0)?, // num_slice_groups_minus1
+        }
+        w.write_ue(self.num_ref_idx_l0_default_active_minus1)?;
+        w.write_ue(self.num_ref_idx_l1_default_active_minus1)?;
+        w.write_bool(self.weighted_pred_flag)?;
+        w.write_u8(2, self.weighted_bipred_idc)?;
+        w.write_se(self.pic_init_qp_minus26)?;
+        w.write_se(self.pic_init_qs_minus26)?;
+        w.write_se(self.chroma_qp_index_offset)?;
+        w.write_bool(self.deblocking_filter_control_present_flag)?;
+        w.write_bool(self.constrained_intra_pred_flag)?;
+        w.write_bool(self.redundant_pic_cnt_present_flag)?;
+        if let Some(ref extension) = self.extension {
+            extension.write(w)?;
+        }
+        w.write_rbsp_trailing_bits()
+    }
+
+    /// The default number of active entries in reference picture list 0, i.e.
+    /// `num_ref_idx_l0_default_active_minus1 + 1`, in the range `1` to `32` inclusive.
+    ///
+    /// Applies to slices which don't set `num_ref_idx_active_override_flag`; see
+    /// [`crate::nal::slice::SliceHeader::num_ref_idx_active`].
+    pub fn num_ref_idx_l0_default_active(&self) -> u32 {
+        self.num_ref_idx_l0_default_active_minus1 + 1
+    }
+
+    /// The default number of active entries in reference picture list 1, i.e.
+    /// `num_ref_idx_l1_default_active_minus1 + 1`, in the range `1` to `32` inclusive.
+    pub fn num_ref_idx_l1_default_active(&self) -> u32 {
+        self.num_ref_idx_l1_default_active_minus1 + 1
+    }
+
+    /// True if the 8x8 transform may be used, i.e. `transform_8x8_mode_flag` is set. This is
+    /// `false` if the PPS has no [`PicParameterSet::extension`].
+    pub fn transform_8x8_mode(&self) -> bool {
+        matches!(
+            self.extension,
+            Some(PicParameterSetExtra {
+                transform_8x8_mode_flag: true,
+                ..
+            })
+        )
+    }
+
+    /// True if the PPS has a scaling matrix, i.e. `pic_scaling_matrix_present_flag` is set.
+    pub fn has_pic_scaling_matrix(&self) -> bool {
+        matches!(
+            self.extension,
+            Some(PicParameterSetExtra {
+                pic_scaling_matrix: Some(_),
+                ..
+            })
+        )
+    }
+
+    /// Returns a hash of the parsed fields of this PPS, to cheaply detect when the PPS with a
+    /// given id has changed. See [`sps::SeqParameterSet::content_hash`].
+    ///
+    /// As with the SPS, the contents of the scaling lists aren't retained, so only whether a
+    /// [`PicScalingMatrix`] is present contributes to the hash.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
     fn read_slice_groups<R: BitRead>(r: &mut R) -> Result<Option<SliceGroup>, PpsError> {
         let num_slice_groups_minus1 = r.read_ue("num_slice_groups_minus1")?;
         if num_slice_groups_minus1 > 7 {
@@ -331,6 +500,18 @@ mod test {
                 println!("pps: {:#?}", pps);
                 assert_eq!(pps.pic_parameter_set_id.id(), 0);
                 assert_eq!(pps.seq_parameter_set_id.id(), 0);
+                assert_eq!(pps.num_ref_idx_l0_default_active(), 16);
+                assert_eq!(pps.num_ref_idx_l1_default_active(), 1);
+                assert!(pps.transform_8x8_mode());
+                assert!(!pps.has_pic_scaling_matrix());
+                let mut pps2 = pps.clone();
+                assert_eq!(pps.content_hash(), pps2.content_hash());
+                pps2.constrained_intra_pred_flag = !pps2.constrained_intra_pred_flag;
+                assert_ne!(pps.content_hash(), pps2.content_hash());
+
+                let mut w = rbsp::BitWriter::new(Vec::new());
+                pps.write(&mut w).unwrap();
+                assert_eq!(w.into_writer(), data);
             }
         }
     }
@@ -364,6 +545,8 @@ mod test {
                 ..
             })
         ));
+        assert!(pps.transform_8x8_mode());
+        assert!(pps.has_pic_scaling_matrix());
     }
 
     // Earlier versions of h264-reader incorrectly limited pic_parameter_set_id to at most 32,
@@ -381,4 +564,80 @@ mod test {
 
         assert_eq!(pps.pic_parameter_set_id, PicParamSetId(33));
     }
+
+    #[test]
+    fn write_slice_groups() {
+        let mut ctx = Context::default();
+        ctx.put_seq_param_set(sps::SeqParameterSetBuilder::default().build());
+        let data = hex!("E8 43 8F 13 21 30");
+        let pps = PicParameterSet::from_bits(&ctx, rbsp::BitReader::new(&data[..])).unwrap();
+        for slice_groups in [
+            SliceGroup::Interleaved {
+                run_length_minus1: vec![3, 5],
+            },
+            SliceGroup::ForegroundAndLeftover {
+                rectangles: vec![
+                    SliceRect {
+                        top_left: 0,
+                        bottom_right: 4,
+                    },
+                    SliceRect {
+                        top_left: 1,
+                        bottom_right: 2,
+                    },
+                ],
+            },
+            SliceGroup::Changing {
+                change_type: SliceGroupChangeType::WipeOut,
+                num_slice_groups_minus1: 1,
+                slice_group_change_direction_flag: true,
+                slice_group_change_rate_minus1: 7,
+            },
+            SliceGroup::ExplicitAssignment {
+                num_slice_groups_minus1: 2,
+                slice_group_id: vec![0, 2, 1, 1],
+            },
+        ] {
+            let pps = PicParameterSet {
+                slice_groups: Some(slice_groups),
+                ..pps.clone()
+            };
+            let mut w = rbsp::BitWriter::new(Vec::new());
+            pps.write(&mut w).unwrap();
+            let data = w.into_writer();
+            assert_eq!(
+                PicParameterSet::from_bits(&ctx, rbsp::BitReader::new(&data[..])).unwrap(),
+                pps
+            );
+        }
+    }
+
+    #[test]
+    fn foreground_and_leftover_rectangles() {
+        // Two slice groups, the second being the leftover, so only one rectangle is coded:
+        // num_slice_groups_minus1=1 (010), slice_group_map_type=2 (011), top_left=0 (1),
+        // bottom_right=4 (00101), then trailing bits.
+        let group = SliceGroup::ForegroundAndLeftover {
+            rectangles: vec![SliceRect {
+                top_left: 0,
+                bottom_right: 4,
+            }],
+        };
+        let mut w = rbsp::BitWriter::new(Vec::new());
+        group.write(&mut w).unwrap();
+        w.write_rbsp_trailing_bits().unwrap();
+        let data = w.into_writer();
+        assert_eq!(data, [0b0100_1110, 0b0101_1000]);
+        let mut r = rbsp::BitReader::new(&data[..]);
+        assert_eq!(r.read_ue("num_slice_groups_minus1").unwrap(), 1);
+        assert_eq!(SliceGroup::read(&mut r, 1).unwrap(), group);
+        r.finish_rbsp().unwrap();
+
+        let empty = SliceGroup::ForegroundAndLeftover { rectangles: vec![] };
+        let mut w = rbsp::BitWriter::new(Vec::new());
+        assert_eq!(
+            empty.write(&mut w).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
 }
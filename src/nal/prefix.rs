@@ -0,0 +1,276 @@
+//! Support for _prefix NAL units_ (`nal_unit_type` 14), which in SVC and MVC streams carry the
+//! NAL unit header extension applying to the base layer / base view VCL NAL which follows.
+//!
+//! The base layer's own slice NALs (types 1 and 5) have no header extension, so that they remain
+//! decodable by plain H.264 decoders. Use [`PrefixNalTracker`] to associate each one with the
+//! extension from its preceding prefix NAL.
+
+use crate::nal::{Nal, NalHeaderError, UnitType};
+use crate::rbsp::{BitRead, BitReader, BitReaderError, ByteReader};
+use std::io::Read;
+
+#[derive(Debug)]
+pub enum PrefixNalError {
+    /// The NAL was too short to contain the header extension.
+    ReaderError(std::io::Error),
+    RbspError(BitReaderError),
+    InvalidNalHeader(NalHeaderError),
+    /// The NAL isn't of a type which has a header extension.
+    UnsupportedNalUnitType(UnitType),
+}
+impl From<BitReaderError> for PrefixNalError {
+    fn from(e: BitReaderError) -> Self {
+        PrefixNalError::RbspError(e)
+    }
+}
+
+/// `nal_unit_header_svc_extension()`, per Rec. ITU-T H.264 (03/2010) section G.7.3.1.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvcExtension {
+    pub idr_flag: bool,
+    pub priority_id: u8,
+    pub no_inter_layer_pred_flag: bool,
+    pub dependency_id: u8,
+    pub quality_id: u8,
+    pub temporal_id: u8,
+    pub use_ref_base_pic_flag: bool,
+    pub discardable_flag: bool,
+    pub output_flag: bool,
+}
+
+/// `nal_unit_header_mvc_extension()`, per Rec. ITU-T H.264 (03/2010) section H.7.3.1.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MvcExtension {
+    pub non_idr_flag: bool,
+    pub priority_id: u8,
+    pub view_id: u16,
+    pub temporal_id: u8,
+    pub anchor_pic_flag: bool,
+    pub inter_view_flag: bool,
+}
+
+/// The three-byte NAL unit header extension present in NAL units of types 14, 20 and 21.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NalHeaderExtension {
+    Svc(SvcExtension),
+    Mvc(MvcExtension),
+}
+impl NalHeaderExtension {
+    /// Reads the header extension of the given NAL, which must be of type 14, 20 or 21.
+    ///
+    /// Only the header extension itself is read; the rest of the NAL needn't be buffered yet.
+    /// Use [`NalHeaderExtension::parse`] to also read the rest of the NAL.
+    pub fn from_nal<N: Nal>(nal: &N) -> Result<NalHeaderExtension, PrefixNalError> {
+        Self::parse(nal).map(|(extension, _)| extension)
+    }
+
+    /// Reads the header extension of the given NAL, returning it along with a reader of the
+    /// remainder of the NAL in RBSP form.
+    ///
+    /// The NAL must be a prefix NAL unit (type 14), in which case the remainder is the
+    /// `prefix_nal_unit_rbsp()`, or a slice extension (type 20 or 21), in which case the
+    /// remainder starts with the slice header. NALs of these types are the only ones with a
+    /// header extension, so unlike [`Nal::rbsp_bytes`] the returned reader skips all four header
+    /// bytes.
+    ///
+    /// ```
+    /// use h264_reader::nal::prefix::NalHeaderExtension;
+    /// use h264_reader::nal::RefNal;
+    /// use h264_reader::rbsp::{BitRead, BitReader};
+    ///
+    /// // A coded slice extension of view 1.
+    /// let nal = RefNal::new(&b"\x74\x40\x00\x47\x88\x80"[..], &[], true);
+    /// let (extension, rbsp) = NalHeaderExtension::parse(&nal).unwrap();
+    /// let NalHeaderExtension::Mvc(mvc) = extension else {
+    ///     panic!("expected MVC extension");
+    /// };
+    /// assert_eq!(mvc.view_id, 1);
+    /// let mut r = BitReader::new(rbsp);
+    /// assert_eq!(r.read_ue("first_mb_in_slice").unwrap(), 0);
+    /// assert_eq!(r.read_ue("slice_type").unwrap(), 7);
+    /// ```
+    pub fn parse<N: Nal>(
+        nal: &N,
+    ) -> Result<(NalHeaderExtension, ByteReader<N::BufRead>), PrefixNalError> {
+        let header = nal.header().map_err(PrefixNalError::InvalidNalHeader)?;
+        match header.nal_unit_type() {
+            UnitType::PrefixNALUnit
+            | UnitType::SliceExtension
+            | UnitType::SliceExtensionViewComponent => {}
+            other => return Err(PrefixNalError::UnsupportedNalUnitType(other)),
+        }
+        // The header extension isn't subject to emulation prevention, so read it directly rather
+        // than via `rbsp_bytes`.
+        let mut reader = nal.reader();
+        let mut bytes = [0u8; 4];
+        reader
+            .read_exact(&mut bytes)
+            .map_err(PrefixNalError::ReaderError)?;
+        let mut r = BitReader::new(&bytes[1..]);
+        let extension = if r.read_bool("svc_extension_flag")? {
+            NalHeaderExtension::Svc(SvcExtension {
+                idr_flag: r.read_bool("idr_flag")?,
+                priority_id: r.read_u8(6, "priority_id")?,
+                no_inter_layer_pred_flag: r.read_bool("no_inter_layer_pred_flag")?,
+                dependency_id: r.read_u8(3, "dependency_id")?,
+                quality_id: r.read_u8(4, "quality_id")?,
+                temporal_id: r.read_u8(3, "temporal_id")?,
+                use_ref_base_pic_flag: r.read_bool("use_ref_base_pic_flag")?,
+                discardable_flag: r.read_bool("discardable_flag")?,
+                output_flag: r.read_bool("output_flag")?,
+            })
+        } else {
+            NalHeaderExtension::Mvc(MvcExtension {
+                non_idr_flag: r.read_bool("non_idr_flag")?,
+                priority_id: r.read_u8(6, "priority_id")?,
+                view_id: r.read_u16(10, "view_id")?,
+                temporal_id: r.read_u8(3, "temporal_id")?,
+                anchor_pic_flag: r.read_bool("anchor_pic_flag")?,
+                inter_view_flag: r.read_bool("inter_view_flag")?,
+            })
+        };
+        // The remaining bits are `reserved_three_2bits` or `reserved_one_bit`, which are ignored.
+        Ok((extension, ByteReader::without_header(reader)))
+    }
+}
+
+/// Associates prefix NAL units with the base layer / base view slice NALs which follow them.
+///
+/// Pass every NAL of the stream to [`PrefixNalTracker::push`] in order:
+///
+/// ```
+/// use h264_reader::nal::prefix::{NalHeaderExtension, PrefixNalTracker};
+/// use h264_reader::nal::RefNal;
+///
+/// let mut tracker = PrefixNalTracker::default();
+/// // A prefix NAL with an MVC header extension for view 0...
+/// let prefix = RefNal::new(&b"\x6e\x40\x00\x07"[..], &[], true);
+/// assert_eq!(tracker.push(&prefix).unwrap(), None);
+/// // ...applies to the following IDR slice.
+/// let slice = RefNal::new(&b"\x65\x88\x81\x00\x2a\x80"[..], &[], true);
+/// let Some(NalHeaderExtension::Mvc(ext)) = tracker.push(&slice).unwrap() else {
+///     panic!("expected MVC extension");
+/// };
+/// assert_eq!(ext.view_id, 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct PrefixNalTracker {
+    pending: Option<NalHeaderExtension>,
+}
+impl PrefixNalTracker {
+    /// Processes the next NAL of the stream.
+    ///
+    /// For a prefix NAL, returns `Ok(None)` and remembers its header extension. For a
+    /// `SliceLayerWithoutPartitioningNonIdr` or `SliceLayerWithoutPartitioningIdr` NAL, returns
+    /// the header extension of the immediately preceding prefix NAL, if any. For other NALs,
+    /// returns `Ok(None)` and forgets any remembered extension, since a prefix NAL applies only
+    /// to the NAL immediately following it.
+    pub fn push<N: Nal>(&mut self, nal: &N) -> Result<Option<NalHeaderExtension>, PrefixNalError> {
+        let header = nal.header().map_err(PrefixNalError::InvalidNalHeader)?;
+        match header.nal_unit_type() {
+            UnitType::PrefixNALUnit => {
+                // Forget any earlier prefix NAL, even if this one fails to parse.
+                self.pending = None;
+                self.pending = Some(NalHeaderExtension::from_nal(nal)?);
+                Ok(None)
+            }
+            UnitType::SliceLayerWithoutPartitioningNonIdr
+            | UnitType::SliceLayerWithoutPartitioningIdr => Ok(self.pending.take()),
+            _ => {
+                self.pending = None;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Forgets any remembered prefix NAL, e.g. on seeking.
+    pub fn reset(&mut self) {
+        self.pending = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nal::RefNal;
+
+    #[test]
+    fn svc_extension() {
+        // svc_extension_flag=1, idr_flag=1, priority_id=2, no_inter_layer_pred_flag=1,
+        // dependency_id=3, quality_id=4, temporal_id=5, use_ref_base_pic_flag=0,
+        // discardable_flag=1, output_flag=1, reserved_three_2bits.
+        let nal = RefNal::new(&[0x6e, 0b1100_0010, 0b1011_0100, 0b1010_1111], &[], true);
+        assert_eq!(
+            NalHeaderExtension::from_nal(&nal).unwrap(),
+            NalHeaderExtension::Svc(SvcExtension {
+                idr_flag: true,
+                priority_id: 2,
+                no_inter_layer_pred_flag: true,
+                dependency_id: 3,
+                quality_id: 4,
+                temporal_id: 5,
+                use_ref_base_pic_flag: false,
+                discardable_flag: true,
+                output_flag: true,
+            })
+        );
+    }
+
+    #[test]
+    fn mvc_extension() {
+        // svc_extension_flag=0, non_idr_flag=1, priority_id=0, view_id=513, temporal_id=2,
+        // anchor_pic_flag=1, inter_view_flag=0, reserved_one_bit.
+        let nal = RefNal::new(&[0x74, 0b0100_0000, 0b1000_0000, 0b0101_0101], &[], true);
+        assert_eq!(
+            NalHeaderExtension::from_nal(&nal).unwrap(),
+            NalHeaderExtension::Mvc(MvcExtension {
+                non_idr_flag: true,
+                priority_id: 0,
+                view_id: 513,
+                temporal_id: 2,
+                anchor_pic_flag: true,
+                inter_view_flag: false,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_rbsp() {
+        // The header extension bytes look like the start of an emulation prevention sequence,
+        // but aren't subject to it; the RBSP which follows is.
+        let nal = RefNal::new(&[0x74, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x01], &[], true);
+        let (extension, mut rbsp) = NalHeaderExtension::parse(&nal).unwrap();
+        assert!(matches!(extension, NalHeaderExtension::Mvc(_)));
+        let mut bytes = Vec::new();
+        rbsp.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, &[0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn tracker() {
+        let prefix = RefNal::new(&[0x6e, 0x40, 0x00, 0x07], &[], true);
+        let slice = RefNal::new(&[0x41, 0x9a], &[], true);
+        let sei = RefNal::new(&[0x06, 0x05], &[], true);
+        let mut tracker = PrefixNalTracker::default();
+        assert_eq!(tracker.push(&slice).unwrap(), None);
+        assert_eq!(tracker.push(&prefix).unwrap(), None);
+        assert!(tracker.push(&slice).unwrap().is_some());
+        // Only the immediately-following slice is associated.
+        assert_eq!(tracker.push(&slice).unwrap(), None);
+        assert_eq!(tracker.push(&prefix).unwrap(), None);
+        assert_eq!(tracker.push(&sei).unwrap(), None);
+        assert_eq!(tracker.push(&slice).unwrap(), None);
+
+        let truncated = RefNal::new(&[0x6e, 0x40], &[], true);
+        assert!(matches!(
+            tracker.push(&truncated),
+            Err(PrefixNalError::ReaderError(_))
+        ));
+        assert!(matches!(
+            NalHeaderExtension::from_nal(&slice),
+            Err(PrefixNalError::UnsupportedNalUnitType(
+                UnitType::SliceLayerWithoutPartitioningNonIdr
+            ))
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum DepthRepresentationInfoError {
+    RbspError(BitReaderError),
+    /// `num_views_minus1` must be in the range `0` to `1023` inclusive.
+    InvalidNumViewsMinus1(u32),
+    /// The named `da_exponent` field had the reserved value `127`.
+    ReservedExponent(&'static str),
+}
+impl From<BitReaderError> for DepthRepresentationInfoError {
+    fn from(e: BitReaderError) -> Self {
+        DepthRepresentationInfoError::RbspError(e)
+    }
+}
+
+/// A depth or disparity value encoded as sign, exponent and mantissa, per Rec. ITU-T H.264
+/// (06/2019) section J.13.2.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DepthRepresentationElement {
+    pub sign: bool,
+    pub exponent: u8,
+    pub mantissa: u32,
+    /// The number of bits in `mantissa`, from `1` to `32` inclusive.
+    pub mantissa_len: u8,
+}
+impl DepthRepresentationElement {
+    fn read<R: BitRead>(
+        r: &mut R,
+        name: &'static str,
+    ) -> Result<DepthRepresentationElement, DepthRepresentationInfoError> {
+        let sign = r.read_bool(name)?;
+        let exponent = r.read_u8(7, name)?;
+        if exponent == 127 {
+            return Err(DepthRepresentationInfoError::ReservedExponent(name));
+        }
+        let mantissa_len = r.read_u8(5, name)? + 1;
+        let mantissa = r.read_u32(u32::from(mantissa_len), name)?;
+        Ok(DepthRepresentationElement {
+            sign,
+            exponent,
+            mantissa,
+            mantissa_len,
+        })
+    }
+
+    /// Returns the value represented by this element.
+    pub fn value(&self) -> f64 {
+        let magnitude = if self.exponent == 0 {
+            2f64.powi(-30 - i32::from(self.mantissa_len)) * f64::from(self.mantissa)
+        } else {
+            let mantissa = f64::from(self.mantissa) / 2f64.powi(i32::from(self.mantissa_len));
+            2f64.powi(i32::from(self.exponent) - 31) * (1.0 + mantissa)
+        };
+        if self.sign {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+}
+
+/// How the sample values of the depth views map to depth, per `depth_representation_type`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DepthRepresentationType {
+    /// Sample values are uniformly quantized `1/Z`, between `z_far` and `z_near`.
+    UniformInverseZ,
+    /// Sample values are uniformly quantized disparity, between `d_min` and `d_max`.
+    UniformDisparity,
+    /// Sample values are uniformly quantized `Z`, between `z_near` and `z_far`.
+    UniformZ,
+    /// Sample values are disparity mapped by a piecewise-linear function, given by
+    /// `depth_nonlinear_representation_model[1..]`.
+    NonlinearDisparity {
+        depth_nonlinear_representation_model: Vec<u32>,
+    },
+    Reserved(u32),
+}
+
+/// The depth range parameters of a single view.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DepthRepresentationView {
+    pub depth_info_view_id: u32,
+    /// Present if `z_near` or `z_far` is, unless all views share
+    /// [`DepthRepresentationInfo::common_z_axis_reference_view`].
+    pub z_axis_reference_view: Option<u32>,
+    /// Present if `d_min` or `d_max` is.
+    pub disparity_reference_view: Option<u32>,
+    pub z_near: Option<DepthRepresentationElement>,
+    pub z_far: Option<DepthRepresentationElement>,
+    pub d_min: Option<DepthRepresentationElement>,
+    pub d_max: Option<DepthRepresentationElement>,
+}
+
+/// The `depth_representation_info` SEI message, which describes the depth range represented by
+/// the depth views of an MVCD or 3D-AVC bitstream.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DepthRepresentationInfo {
+    /// Set if `z_near` or `z_far` is present and all views share the reference view for the
+    /// Z-axis.
+    pub common_z_axis_reference_view: Option<u32>,
+    pub depth_representation_type: DepthRepresentationType,
+    /// Contains a single entry applying to all views if `all_views_equal_flag` was set,
+    /// otherwise one entry per view.
+    pub views: Vec<DepthRepresentationView>,
+}
+impl DepthRepresentationInfo {
+    pub fn read(
+        msg: &SeiMessage<'_>,
+    ) -> Result<DepthRepresentationInfo, DepthRepresentationInfoError> {
+        assert_eq!(msg.payload_type, HeaderType::DepthRepresentationInfo);
+        let mut r = BitReader::new(msg.payload);
+        let num_views = if r.read_bool("all_views_equal_flag")? {
+            1
+        } else {
+            let num_views_minus1 = r.read_ue("num_views_minus1")?;
+            if num_views_minus1 > 1023 {
+                return Err(DepthRepresentationInfoError::InvalidNumViewsMinus1(
+                    num_views_minus1,
+                ));
+            }
+            num_views_minus1 + 1
+        };
+        let z_near_flag = r.read_bool("z_near_flag")?;
+        let z_far_flag = r.read_bool("z_far_flag")?;
+        let mut z_axis_equal_flag = false;
+        let mut common_z_axis_reference_view = None;
+        if z_near_flag || z_far_flag {
+            z_axis_equal_flag = r.read_bool("z_axis_equal_flag")?;
+            if z_axis_equal_flag {
+                common_z_axis_reference_view = Some(r.read_ue("common_z_axis_reference_view")?);
+            }
+        }
+        let d_min_flag = r.read_bool("d_min_flag")?;
+        let d_max_flag = r.read_bool("d_max_flag")?;
+        let depth_representation_type = r.read_ue("depth_representation_type")?;
+        let mut views = Vec::with_capacity(num_views as usize);
+        for _ in 0..num_views {
+            let depth_info_view_id = r.read_ue("depth_info_view_id")?;
+            let z_axis_reference_view = if (z_near_flag || z_far_flag) && !z_axis_equal_flag {
+                Some(r.read_ue("z_axis_reference_view")?)
+            } else {
+                None
+            };
+            let disparity_reference_view = if d_min_flag || d_max_flag {
+                Some(r.read_ue("disparity_reference_view")?)
+            } else {
+                None
+            };
+            let mut read_element = |flag: bool, name| {
+                if flag {
+                    DepthRepresentationElement::read(&mut r, name).map(Some)
+                } else {
+                    Ok(None)
+                }
+            };
+            views.push(DepthRepresentationView {
+                depth_info_view_id,
+                z_axis_reference_view,
+                disparity_reference_view,
+                z_near: read_element(z_near_flag, "z_near")?,
+                z_far: read_element(z_far_flag, "z_far")?,
+                d_min: read_element(d_min_flag, "d_min")?,
+                d_max: read_element(d_max_flag, "d_max")?,
+            });
+        }
+        let depth_representation_type = match depth_representation_type {
+            0 => DepthRepresentationType::UniformInverseZ,
+            1 => DepthRepresentationType::UniformDisparity,
+            2 => DepthRepresentationType::UniformZ,
+            3 => {
+                let num_minus1 = r.read_ue("depth_nonlinear_representation_num_minus1")?;
+                let depth_nonlinear_representation_model = (0..=num_minus1)
+                    .map(|_| r.read_ue("depth_nonlinear_representation_model"))
+                    .collect::<Result<_, _>>()?;
+                DepthRepresentationType::NonlinearDisparity {
+                    depth_nonlinear_representation_model,
+                }
+            }
+            other => DepthRepresentationType::Reserved(other),
+        };
+        r.finish_sei_payload()?;
+        Ok(DepthRepresentationInfo {
+            common_z_axis_reference_view,
+            depth_representation_type,
+            views,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rbsp::{BitWrite, BitWriter};
+
+    fn write_element(w: &mut BitWriter<Vec<u8>>, sign: bool, exponent: u8, mantissa: (u32, u8)) {
+        w.write_bool(sign).unwrap();
+        w.write_u8(7, exponent).unwrap();
+        w.write_u8(5, mantissa.1 - 1).unwrap();
+        w.write_u32(u32::from(mantissa.1), mantissa.0).unwrap();
+    }
+
+    #[test]
+    fn parse() {
+        let mut w = BitWriter::new(Vec::new());
+        w.write_bool(false).unwrap(); // all_views_equal_flag
+        w.write_ue(1).unwrap(); // num_views_minus1
+        w.write_bool(true).unwrap(); // z_near_flag
+        w.write_bool(true).unwrap(); // z_far_flag
+        w.write_bool(true).unwrap(); // z_axis_equal_flag
+        w.write_ue(0).unwrap(); // common_z_axis_reference_view
+        w.write_bool(false).unwrap(); // d_min_flag
+        w.write_bool(false).unwrap(); // d_max_flag
+        w.write_ue(3).unwrap(); // depth_representation_type
+        for view_id in 0..2 {
+            w.write_ue(view_id).unwrap();
+            write_element(&mut w, false, 31, (1, 1)); // 1.5
+            write_element(&mut w, true, 0, (1, 2)); // -2^-32
+        }
+        w.write_ue(1).unwrap(); // depth_nonlinear_representation_num_minus1
+        w.write_ue(5).unwrap();
+        w.write_ue(7).unwrap();
+        w.write_rbsp_trailing_bits().unwrap();
+        let payload = w.into_writer();
+        let msg = SeiMessage {
+            payload_type: HeaderType::DepthRepresentationInfo,
+            payload: &payload[..],
+        };
+        let info = DepthRepresentationInfo::read(&msg).unwrap();
+        assert_eq!(info.common_z_axis_reference_view, Some(0));
+        assert_eq!(
+            info.depth_representation_type,
+            DepthRepresentationType::NonlinearDisparity {
+                depth_nonlinear_representation_model: vec![5, 7],
+            }
+        );
+        assert_eq!(info.views.len(), 2);
+        let view = &info.views[1];
+        assert_eq!(view.depth_info_view_id, 1);
+        assert_eq!(view.z_axis_reference_view, None);
+        assert_eq!(view.disparity_reference_view, None);
+        assert_eq!(view.z_near.unwrap().value(), 1.5);
+        assert_eq!(view.z_far.unwrap().value(), -(2f64.powi(-32)));
+        assert_eq!(view.d_min, None);
+    }
+
+    #[test]
+    fn reserved_exponent() {
+        let mut w = BitWriter::new(Vec::new());
+        w.write_bool(true).unwrap(); // all_views_equal_flag
+        w.write_bool(false).unwrap(); // z_near_flag
+        w.write_bool(false).unwrap(); // z_far_flag
+        w.write_bool(true).unwrap(); // d_min_flag
+        w.write_bool(false).unwrap(); // d_max_flag
+        w.write_ue(1).unwrap(); // depth_representation_type
+        w.write_ue(0).unwrap(); // depth_info_view_id
+        w.write_ue(0).unwrap(); // disparity_reference_view
+        write_element(&mut w, false, 127, (0, 1));
+        w.write_rbsp_trailing_bits().unwrap();
+        let payload = w.into_writer();
+        let msg = SeiMessage {
+            payload_type: HeaderType::DepthRepresentationInfo,
+            payload: &payload[..],
+        };
+        assert!(matches!(
+            DepthRepresentationInfo::read(&msg),
+            Err(DepthRepresentationInfoError::ReservedExponent("d_min"))
+        ));
+    }
+}
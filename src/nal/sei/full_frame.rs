@@ -0,0 +1,117 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum FullFrameError {
+    RbspError(BitReaderError),
+}
+impl From<BitReaderError> for FullFrameError {
+    fn from(e: BitReaderError) -> Self {
+        FullFrameError::RbspError(e)
+    }
+}
+
+/// The `full_frame_freeze` SEI message, which asks the decoder to keep displaying the current
+/// picture until a [`FullFrameFreezeRelease`] or the repetition period expires.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FullFrameFreeze {
+    /// How long the freeze persists; `0` means until the end of the current picture, `1` until a
+    /// [`FullFrameFreezeRelease`] or the end of the coded video sequence, and larger values until
+    /// a picture with a picture order count that many greater than the current one.
+    pub full_frame_freeze_repetition_period: u32,
+}
+impl FullFrameFreeze {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<FullFrameFreeze, FullFrameError> {
+        assert_eq!(msg.payload_type, HeaderType::FullFrameFreeze);
+        let mut r = BitReader::new(msg.payload);
+        let freeze = FullFrameFreeze {
+            full_frame_freeze_repetition_period: r
+                .read_ue("full_frame_freeze_repetition_period")?,
+        };
+        r.finish_sei_payload()?;
+        Ok(freeze)
+    }
+}
+
+/// The `full_frame_freeze_release` SEI message, which cancels any [`FullFrameFreeze`]. It has no
+/// fields.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FullFrameFreezeRelease;
+impl FullFrameFreezeRelease {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<FullFrameFreezeRelease, FullFrameError> {
+        assert_eq!(msg.payload_type, HeaderType::FullFrameFreezeRelease);
+        BitReader::new(msg.payload).finish_sei_payload()?;
+        Ok(FullFrameFreezeRelease)
+    }
+}
+
+/// The `full_frame_snapshot` SEI message, which marks the current picture as a still-image
+/// snapshot of the video content.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FullFrameSnapshot {
+    pub snapshot_id: u32,
+}
+impl FullFrameSnapshot {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<FullFrameSnapshot, FullFrameError> {
+        assert_eq!(msg.payload_type, HeaderType::FullFrameSnapshot);
+        let mut r = BitReader::new(msg.payload);
+        let snapshot = FullFrameSnapshot {
+            snapshot_id: r.read_ue("snapshot_id")?,
+        };
+        r.finish_sei_payload()?;
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn freeze() {
+        // full_frame_freeze_repetition_period=1 (010), then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::FullFrameFreeze,
+            payload: &[0b0101_0000],
+        };
+        assert_eq!(
+            FullFrameFreeze::read(&msg).unwrap(),
+            FullFrameFreeze {
+                full_frame_freeze_repetition_period: 1,
+            }
+        );
+
+        let msg = SeiMessage {
+            payload_type: HeaderType::FullFrameFreezeRelease,
+            payload: &[],
+        };
+        assert_eq!(
+            FullFrameFreezeRelease::read(&msg).unwrap(),
+            FullFrameFreezeRelease
+        );
+        let msg = SeiMessage {
+            payload_type: HeaderType::FullFrameFreezeRelease,
+            payload: &[0x01],
+        };
+        assert!(matches!(
+            FullFrameFreezeRelease::read(&msg),
+            Err(FullFrameError::RbspError(BitReaderError::RemainingData))
+        ));
+    }
+
+    #[test]
+    fn snapshot() {
+        // snapshot_id=4 (00101), then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::FullFrameSnapshot,
+            payload: &[0b0010_1100],
+        };
+        assert_eq!(
+            FullFrameSnapshot::read(&msg).unwrap(),
+            FullFrameSnapshot { snapshot_id: 4 }
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum LayersNotPresentError {
+    RbspError(BitReaderError),
+    /// `num_layers` must be in the range `0` to `2047` inclusive.
+    InvalidNumLayers(u32),
+}
+impl From<BitReaderError> for LayersNotPresentError {
+    fn from(e: BitReaderError) -> Self {
+        LayersNotPresentError::RbspError(e)
+    }
+}
+
+/// The `layers_not_present` SEI message, which lists the layers of an SVC bitstream (as
+/// identified in the `scalability_info` SEI message) that aren't present.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LayersNotPresent {
+    /// The `layer_id` of each layer which isn't present.
+    pub layer_ids: Vec<u32>,
+}
+impl LayersNotPresent {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<LayersNotPresent, LayersNotPresentError> {
+        assert_eq!(msg.payload_type, HeaderType::LayersNotPresent);
+        let mut r = BitReader::new(msg.payload);
+        let num_layers = r.read_ue("num_layers")?;
+        if num_layers > 2047 {
+            return Err(LayersNotPresentError::InvalidNumLayers(num_layers));
+        }
+        let mut layer_ids = Vec::with_capacity(num_layers as usize);
+        for _ in 0..num_layers {
+            layer_ids.push(r.read_ue("layer_id")?);
+        }
+        r.finish_sei_payload()?;
+        Ok(LayersNotPresent { layer_ids })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // num_layers=2 (011), layer_id = [0 (1), 3 (00100)], then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::LayersNotPresent,
+            payload: &[0b0111_0010, 0b0100_0000],
+        };
+        assert_eq!(
+            LayersNotPresent::read(&msg).unwrap(),
+            LayersNotPresent {
+                layer_ids: vec![0, 3],
+            }
+        );
+    }
+
+    #[test]
+    fn empty() {
+        // num_layers=0 (1), then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::LayersNotPresent,
+            payload: &[0b1100_0000],
+        };
+        assert_eq!(
+            LayersNotPresent::read(&msg).unwrap(),
+            LayersNotPresent { layer_ids: vec![] }
+        );
+    }
+}
@@ -1,8 +1,22 @@
 pub mod buffering_period;
+pub mod depth_representation_info;
+pub mod full_frame;
+pub mod layers_not_present;
+pub mod multiview_acquisition_info;
+pub mod multiview_view_position;
+pub mod operation_points_not_present;
 pub mod pic_timing;
+pub mod post_filter_hint;
+pub mod progressive_refinement_segment;
+pub mod stereo_video_info;
+pub mod sub_seq_info;
+pub mod tl0_dep_rep_index;
+pub mod tl_switching_point;
 pub mod user_data_registered_itu_t_t35;
 
-use crate::rbsp::BitReaderError;
+use crate::nal::{Nal, NalHeaderError, UnitType};
+use crate::rbsp::{BitReaderError, ByteReader};
+use crate::ParseOptions;
 use hex_slice::AsHex;
 use std::convert::TryFrom;
 use std::fmt::{Debug, Formatter};
@@ -68,12 +82,14 @@ pub enum HeaderType {
     GreenMetadata,
     MasteringDisplayColourVolume,
     ColourRemappingInfo,
+    ContentLightLevelInfo,
     AlternativeTransferCharacteristics,
     AlternativeDepthInfo,
     ReservedSeiMessage(u32),
 }
 impl HeaderType {
-    fn from_id(id: u32) -> HeaderType {
+    /// Maps a `payloadType` value to the corresponding message type.
+    pub fn from_id(id: u32) -> HeaderType {
         match id {
             0 => HeaderType::BufferingPeriod,
             1 => HeaderType::PicTiming,
@@ -133,19 +149,179 @@ impl HeaderType {
             56 => HeaderType::GreenMetadata,
             137 => HeaderType::MasteringDisplayColourVolume,
             142 => HeaderType::ColourRemappingInfo,
+            144 => HeaderType::ContentLightLevelInfo,
             147 => HeaderType::AlternativeTransferCharacteristics,
-            188 => HeaderType::AlternativeDepthInfo,
+            181 => HeaderType::AlternativeDepthInfo,
             _ => HeaderType::ReservedSeiMessage(id),
         }
     }
+
+    /// Returns the `payloadType` value which identifies this message type.
+    pub fn id(&self) -> u32 {
+        match *self {
+            HeaderType::BufferingPeriod => 0,
+            HeaderType::PicTiming => 1,
+            HeaderType::PanScanRect => 2,
+            HeaderType::FillerPayload => 3,
+            HeaderType::UserDataRegisteredItuTT35 => 4,
+            HeaderType::UserDataUnregistered => 5,
+            HeaderType::RecoveryPoint => 6,
+            HeaderType::DecRefPicMarkingRepetition => 7,
+            HeaderType::SparePic => 8,
+            HeaderType::SceneInfo => 9,
+            HeaderType::SubSeqInfo => 10,
+            HeaderType::SubSeqLayerCharacteristics => 11,
+            HeaderType::SubSeqCharacteristics => 12,
+            HeaderType::FullFrameFreeze => 13,
+            HeaderType::FullFrameFreezeRelease => 14,
+            HeaderType::FullFrameSnapshot => 15,
+            HeaderType::ProgressiveRefinementSegmentStart => 16,
+            HeaderType::ProgressiveRefinementSegmentEnd => 17,
+            HeaderType::MotionConstrainedSliceGroupSet => 18,
+            HeaderType::FilmGrainCharacteristics => 19,
+            HeaderType::DeblockingFilterDisplayPreference => 20,
+            HeaderType::StereoVideoInfo => 21,
+            HeaderType::PostFilterHint => 22,
+            HeaderType::ToneMappingInfo => 23,
+            HeaderType::ScalabilityInfo => 24,
+            HeaderType::SubPicScalableLayer => 25,
+            HeaderType::NonRequiredLayerRep => 26,
+            HeaderType::PriorityLayerInfo => 27,
+            HeaderType::LayersNotPresent => 28,
+            HeaderType::LayerDependencyChange => 29,
+            HeaderType::ScalableNesting => 30,
+            HeaderType::BaseLayerTemporalHrd => 31,
+            HeaderType::QualityLayerIntegrityCheck => 32,
+            HeaderType::RedundantPicProperty => 33,
+            HeaderType::Tl0DepRepIndex => 34,
+            HeaderType::TlSwitchingPoint => 35,
+            HeaderType::ParallelDecodingInfo => 36,
+            HeaderType::MvcScalableNesting => 37,
+            HeaderType::ViewScalabilityInfo => 38,
+            HeaderType::MultiviewSceneInfo => 39,
+            HeaderType::MultiviewAcquisitionInfo => 40,
+            HeaderType::NonRequiredViewComponent => 41,
+            HeaderType::ViewDependencyChange => 42,
+            HeaderType::OperationPointsNotPresent => 43,
+            HeaderType::BaseViewTemporalHrd => 44,
+            HeaderType::FramePackingArrangement => 45,
+            HeaderType::MultiviewViewPosition => 46,
+            HeaderType::DisplayOrientation => 47,
+            HeaderType::MvcdScalableNesting => 48,
+            HeaderType::MvcdViewScalabilityInfo => 49,
+            HeaderType::DepthRepresentationInfo => 50,
+            HeaderType::ThreeDimensionalReferenceDisplaysInfo => 51,
+            HeaderType::DepthTiming => 52,
+            HeaderType::DepthSamplingInfo => 53,
+            HeaderType::ConstrainedDepthParameterSetIdentifier => 54,
+            HeaderType::GreenMetadata => 56,
+            HeaderType::MasteringDisplayColourVolume => 137,
+            HeaderType::ColourRemappingInfo => 142,
+            HeaderType::ContentLightLevelInfo => 144,
+            HeaderType::AlternativeTransferCharacteristics => 147,
+            HeaderType::AlternativeDepthInfo => 181,
+            HeaderType::ReservedSeiMessage(id) => id,
+        }
+    }
+
+    /// True for messages describing the colour volume or transfer characteristics of HDR
+    /// content: mastering display colour volume, content light level, alternative transfer
+    /// characteristics, tone mapping and colour remapping.
+    pub fn is_hdr_metadata(&self) -> bool {
+        matches!(
+            self,
+            HeaderType::MasteringDisplayColourVolume
+                | HeaderType::ContentLightLevelInfo
+                | HeaderType::AlternativeTransferCharacteristics
+                | HeaderType::ToneMappingInfo
+                | HeaderType::ColourRemappingInfo
+        )
+    }
+
+    /// True for messages specified by the scalable and multiview extensions: SVC (Annex G),
+    /// MVC (Annex H), MVCD (Annex I) and 3D-AVC (Annex J), including the scalable nesting
+    /// messages.
+    pub fn is_scalability(&self) -> bool {
+        matches!(
+            self,
+            HeaderType::ScalabilityInfo
+                | HeaderType::SubPicScalableLayer
+                | HeaderType::NonRequiredLayerRep
+                | HeaderType::PriorityLayerInfo
+                | HeaderType::LayersNotPresent
+                | HeaderType::LayerDependencyChange
+                | HeaderType::ScalableNesting
+                | HeaderType::BaseLayerTemporalHrd
+                | HeaderType::QualityLayerIntegrityCheck
+                | HeaderType::RedundantPicProperty
+                | HeaderType::Tl0DepRepIndex
+                | HeaderType::TlSwitchingPoint
+                | HeaderType::ParallelDecodingInfo
+                | HeaderType::MvcScalableNesting
+                | HeaderType::ViewScalabilityInfo
+                | HeaderType::MultiviewSceneInfo
+                | HeaderType::MultiviewAcquisitionInfo
+                | HeaderType::NonRequiredViewComponent
+                | HeaderType::ViewDependencyChange
+                | HeaderType::OperationPointsNotPresent
+                | HeaderType::BaseViewTemporalHrd
+                | HeaderType::MultiviewViewPosition
+                | HeaderType::MvcdScalableNesting
+                | HeaderType::MvcdViewScalabilityInfo
+                | HeaderType::DepthRepresentationInfo
+                | HeaderType::ThreeDimensionalReferenceDisplaysInfo
+                | HeaderType::DepthTiming
+                | HeaderType::DepthSamplingInfo
+                | HeaderType::ConstrainedDepthParameterSetIdentifier
+                | HeaderType::AlternativeDepthInfo
+        )
+    }
+}
+
+/// Error returned by [`SeiReader::from_nal`].
+#[derive(Debug)]
+pub enum SeiNalError {
+    InvalidNalHeader(NalHeaderError),
+    /// The NAL isn't an SEI NAL.
+    UnsupportedNalUnitType(UnitType),
 }
 
 /// Reader of messages in an SEI NAL.
 pub struct SeiReader<'a, R: BufRead + Clone> {
     reader: R,
     scratch: &'a mut Vec<u8>,
-    payloads_seen: usize,
     done: bool,
+    options: ParseOptions,
+    max_payload_len: usize,
+}
+
+impl<'a, B: BufRead + Clone> SeiReader<'a, ByteReader<B>> {
+    /// Constructs a reader of the messages in the given NAL, which must be an SEI NAL.
+    ///
+    /// This is a shortcut for checking the NAL type and calling
+    /// [`SeiReader::from_rbsp_bytes`] with [`Nal::rbsp_bytes`].
+    ///
+    /// ```
+    /// use h264_reader::nal::sei::{HeaderType, SeiReader};
+    /// use h264_reader::nal::RefNal;
+    ///
+    /// let nal = RefNal::new(&b"\x06\x05\x01\xaa\x80"[..], &[], true);
+    /// let mut scratch = Vec::new();
+    /// let mut reader = SeiReader::from_nal(&nal, &mut scratch).unwrap();
+    /// let msg = reader.next().unwrap().unwrap();
+    /// assert_eq!(msg.payload_type, HeaderType::UserDataUnregistered);
+    /// assert_eq!(msg.payload, &[0xaa]);
+    /// ```
+    pub fn from_nal<N: Nal<BufRead = B>>(
+        nal: &N,
+        scratch: &'a mut Vec<u8>,
+    ) -> Result<Self, SeiNalError> {
+        let header = nal.header().map_err(SeiNalError::InvalidNalHeader)?;
+        if header.nal_unit_type() != UnitType::SEI {
+            return Err(SeiNalError::UnsupportedNalUnitType(header.nal_unit_type()));
+        }
+        Ok(Self::from_rbsp_bytes(nal.rbsp_bytes(), scratch))
+    }
 }
 
 impl<'a, R: BufRead + Clone> SeiReader<'a, R> {
@@ -153,16 +329,67 @@ impl<'a, R: BufRead + Clone> SeiReader<'a, R> {
         Self {
             reader,
             scratch,
-            payloads_seen: 0,
             done: false,
+            options: ParseOptions::default(),
+            max_payload_len: usize::MAX,
+        }
+    }
+
+    /// Like [`SeiReader::from_rbsp_bytes`], but rejects any message whose `payload_len` exceeds
+    /// `max_payload_len` rather than growing `scratch` to hold it.
+    ///
+    /// This bounds the allocation a single (possibly untrusted) SEI header can cause.
+    pub fn with_max_payload_len(
+        reader: R,
+        scratch: &'a mut Vec<u8>,
+        max_payload_len: usize,
+    ) -> Self {
+        Self {
+            max_payload_len,
+            ..Self::from_rbsp_bytes(reader, scratch)
         }
     }
 
+    /// Sets the options used when reading messages. If [`ParseOptions::strict`] is set, a
+    /// reserved `payload_type` is returned as [`BitReaderError::ReservedValue`].
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     /// Returns the next payload.
     ///
     /// This is unfortunately not compatible with `std::iter::Iterator` because
     /// of lifetime constraints.
     pub fn next(&mut self) -> Result<Option<SeiMessage<'_>>, BitReaderError> {
+        let Some((payload_type, payload_len)) = self.next_header()? else {
+            return Ok(None);
+        };
+        self.read_payload(payload_type, payload_len).map(Some)
+    }
+
+    /// Returns the next payload of the given type.
+    ///
+    /// Messages of other types are skipped without being copied into the scratch buffer, but
+    /// are still consumed, so aren't returned by a subsequent call to [`SeiReader::next`].
+    pub fn next_of_type(
+        &mut self,
+        ty: HeaderType,
+    ) -> Result<Option<SeiMessage<'_>>, BitReaderError> {
+        loop {
+            let Some((payload_type, payload_len)) = self.next_header()? else {
+                return Ok(None);
+            };
+            if payload_type == ty {
+                return self.read_payload(payload_type, payload_len).map(Some);
+            }
+            self.skip_payload(payload_len)?;
+        }
+    }
+
+    /// Reads the `payload_type` and `payload_len` of the next message, leaving `self.done` set
+    /// so that the caller must clear it once the payload is successfully read.
+    fn next_header(&mut self) -> Result<Option<(HeaderType, usize)>, BitReaderError> {
         if self.done {
             return Ok(None);
         }
@@ -171,22 +398,36 @@ impl<'a, R: BufRead + Clone> SeiReader<'a, R> {
         // again and return a strange result. (Set done preemptively then clear
         // it on success, rather than adjust each failure path.)
         self.done = true;
-        let payload_type = read_u32(&mut self.reader, "payload_type")?;
 
-        // If this is not the first payload, the byte we just read may actually
-        // be a rbsp_trailing_bits (which is always byte-aligned). Check for EOF.
-        if payload_type == 0x80 && self.payloads_seen > 0 {
-            let buf = self
-                .reader
-                .fill_buf()
-                .map_err(|e| BitReaderError::ReaderErrorFor("payload_type", e))?;
-            if buf.is_empty() {
-                return Ok(None);
-            }
+        // Messages are byte-aligned, so this is equivalent to `more_rbsp_data()`.
+        if at_rbsp_trailing_bits(&self.reader)? {
+            return Ok(None);
         }
+        let payload_type = read_u32(&mut self.reader, "payload_type")?;
         let payload_type = HeaderType::from_id(payload_type);
+        if let HeaderType::ReservedSeiMessage(id) = payload_type {
+            if self.options.strict {
+                return Err(BitReaderError::ReservedValue("payload_type", id));
+            }
+        }
         let payload_len = usize::try_from(read_u32(&mut self.reader, "payload_len")?).unwrap();
+        if payload_len > self.max_payload_len {
+            return Err(BitReaderError::ReaderErrorFor(
+                "payload_len",
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "payload_len exceeds maximum",
+                ),
+            ));
+        }
+        Ok(Some((payload_type, payload_len)))
+    }
 
+    fn read_payload(
+        &mut self,
+        payload_type: HeaderType,
+        payload_len: usize,
+    ) -> Result<SeiMessage<'_>, BitReaderError> {
         // Read into scratch. We could instead directly use reader's buffer if
         // the next chunk is long enough, or pass along a BufRead that uses
         // something like std::io::Take, but it's probably not worth the
@@ -196,16 +437,51 @@ impl<'a, R: BufRead + Clone> SeiReader<'a, R> {
             .read_exact(&mut self.scratch)
             .map_err(|e| BitReaderError::ReaderErrorFor("payload", e))?;
 
-        self.payloads_seen += 1;
         self.done = false;
-        Ok(Some(SeiMessage {
+        Ok(SeiMessage {
             payload_type,
             payload: &self.scratch[..],
-        }))
+        })
+    }
+
+    fn skip_payload(&mut self, mut payload_len: usize) -> Result<(), BitReaderError> {
+        while payload_len > 0 {
+            let available = self
+                .reader
+                .fill_buf()
+                .map_err(|e| BitReaderError::ReaderErrorFor("payload", e))?
+                .len();
+            if available == 0 {
+                return Err(BitReaderError::ReaderErrorFor(
+                    "payload",
+                    std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "payload extends past end of NAL",
+                    ),
+                ));
+            }
+            let n = available.min(payload_len);
+            self.reader.consume(n);
+            payload_len -= n;
+        }
+        self.done = false;
+        Ok(())
+    }
+
+    /// Reads all remaining payloads into owned buffers.
+    ///
+    /// This is convenient when every message in the NAL is needed at once, at the cost of
+    /// copying each payload out of the scratch buffer.
+    pub fn collect_owned(mut self) -> Result<Vec<OwnedSeiMessage>, BitReaderError> {
+        let mut messages = Vec::new();
+        while let Some(msg) = self.next()? {
+            messages.push(OwnedSeiMessage::from(msg));
+        }
+        Ok(messages)
     }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct SeiMessage<'a> {
     pub payload_type: HeaderType,
     pub payload: &'a [u8],
@@ -219,6 +495,61 @@ impl<'a> Debug for SeiMessage<'a> {
             .finish()
     }
 }
+/// An [`SeiMessage`] which owns its payload.
+#[derive(Clone, PartialEq, Eq)]
+pub struct OwnedSeiMessage {
+    pub payload_type: HeaderType,
+    pub payload: Vec<u8>,
+}
+impl<'a> From<SeiMessage<'a>> for OwnedSeiMessage {
+    fn from(msg: SeiMessage<'a>) -> Self {
+        OwnedSeiMessage {
+            payload_type: msg.payload_type,
+            payload: msg.payload.to_vec(),
+        }
+    }
+}
+impl OwnedSeiMessage {
+    /// Borrows this message as an [`SeiMessage`], as expected by the payload parsers.
+    pub fn as_message(&self) -> SeiMessage<'_> {
+        SeiMessage {
+            payload_type: self.payload_type,
+            payload: &self.payload[..],
+        }
+    }
+}
+impl Debug for OwnedSeiMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OwnedSeiMessage")
+            .field("payload_type", &self.payload_type)
+            .field("payload", &format!("{:02x}", self.payload.plain_hex(false)))
+            .finish()
+    }
+}
+
+/// Returns true if the remaining bytes are exactly the `rbsp_trailing_bits`: a `0x80` byte
+/// followed by nothing but zero bytes. Doesn't advance `reader`.
+fn at_rbsp_trailing_bits<R: BufRead + Clone>(reader: &R) -> Result<bool, BitReaderError> {
+    let mut reader = reader.clone();
+    let mut seen_stop_bit = false;
+    loop {
+        let buf = reader
+            .fill_buf()
+            .map_err(|e| BitReaderError::ReaderErrorFor("rbsp_trailing_bits", e))?;
+        if buf.is_empty() {
+            return Ok(seen_stop_bit);
+        }
+        for &b in buf {
+            let expected = if seen_stop_bit { 0x00 } else { 0x80 };
+            if b != expected {
+                return Ok(false);
+            }
+            seen_stop_bit = true;
+        }
+        let len = buf.len();
+        reader.consume(len);
+    }
+}
 
 /// Reads a u32 in the special `sei_message` format used for payload type and size.
 fn read_u32<R: BufRead>(reader: &mut R, name: &'static str) -> Result<u32, BitReaderError> {
@@ -243,7 +574,7 @@ fn read_u32<R: BufRead>(reader: &mut R, name: &'static str) -> Result<u32, BitRe
 
 #[cfg(test)]
 mod test {
-    use crate::nal::{Nal, RefNal};
+    use crate::nal::RefNal;
 
     use super::*;
 
@@ -273,4 +604,187 @@ mod test {
         assert_eq!(r.next().unwrap(), None);
         assert_eq!(r.next().unwrap(), None);
     }
+
+    #[test]
+    fn payload_type_0x80() {
+        let data = [
+            0x06, // SEI
+            0x89, 0x01, 0x01, // AlternativeTransferCharacteristics
+            0x80, 0x01, 0x00, // MasteringDisplayColourVolume (truncated)
+            0x00, 0x01, 0x01, // BufferingPeriod
+            0x80, // rbsp_trailing_bits
+        ];
+        let nal = RefNal::new(&data[..], &[], true);
+        let mut scratch = Vec::new();
+        let msgs = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), &mut scratch)
+            .collect_owned()
+            .unwrap();
+        let types: Vec<_> = msgs.iter().map(|m| m.payload_type.id()).collect();
+        assert_eq!(types, &[137, 128, 0]);
+
+        // The trailing bits may be followed by zero bytes.
+        let data = [0x06, 0x01, 0x01, 0x01, 0x80, 0x00, 0x00];
+        let nal = RefNal::new(&data[..], &[], true);
+        let msgs = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), &mut scratch)
+            .collect_owned()
+            .unwrap();
+        assert_eq!(msgs.len(), 1);
+    }
+
+    #[test]
+    fn header_type_id() {
+        for id in 0..=255 {
+            assert_eq!(HeaderType::from_id(id).id(), id);
+        }
+        assert_eq!(
+            HeaderType::from_id(4),
+            HeaderType::UserDataRegisteredItuTT35
+        );
+        assert_eq!(HeaderType::ReservedSeiMessage(1000).id(), 1000);
+    }
+
+    #[test]
+    fn header_type_categories() {
+        assert!(HeaderType::from_id(144).is_hdr_metadata());
+        assert!(HeaderType::MasteringDisplayColourVolume.is_hdr_metadata());
+        assert!(!HeaderType::MasteringDisplayColourVolume.is_scalability());
+        assert!(HeaderType::ScalableNesting.is_scalability());
+        assert!(HeaderType::MvcScalableNesting.is_scalability());
+        assert!(!HeaderType::FramePackingArrangement.is_scalability());
+        assert!(!HeaderType::PicTiming.is_hdr_metadata());
+        assert!(!HeaderType::ReservedSeiMessage(1000).is_scalability());
+    }
+
+    #[test]
+    fn collect_owned() {
+        let data = [
+            0x06, // SEI
+            0x01, 0x01, 0x01, // PicTiming
+            0x02, 0x02, 0x02, 0x02, // PanScanRect
+            0x80, // rbsp_trailing_bits
+        ];
+        let nal = RefNal::new(&data[..], &[], true);
+        let mut scratch = Vec::new();
+        let msgs = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), &mut scratch)
+            .collect_owned()
+            .unwrap();
+        assert_eq!(
+            msgs,
+            vec![
+                OwnedSeiMessage {
+                    payload_type: HeaderType::PicTiming,
+                    payload: vec![0x01],
+                },
+                OwnedSeiMessage {
+                    payload_type: HeaderType::PanScanRect,
+                    payload: vec![0x02, 0x02],
+                },
+            ]
+        );
+        assert_eq!(msgs[1].as_message().payload, &[0x02, 0x02]);
+    }
+
+    #[test]
+    fn strict() {
+        let data = [
+            0x06, // SEI
+            0xc8, 0x01, 0x01, // payload_type 200, which is reserved
+            0x80, // rbsp_trailing_bits
+        ];
+        let nal = RefNal::new(&data[..], &[], true);
+        let mut scratch = Vec::new();
+        let mut reader = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), &mut scratch);
+        assert_eq!(
+            reader.next().unwrap().unwrap().payload_type,
+            HeaderType::ReservedSeiMessage(200)
+        );
+        let mut reader = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), &mut scratch)
+            .with_options(ParseOptions::strict());
+        assert!(matches!(
+            reader.next(),
+            Err(BitReaderError::ReservedValue("payload_type", 200))
+        ));
+    }
+
+    #[test]
+    fn next_of_type() {
+        let data = [
+            0x06, // SEI
+            0x05, 0x02, 0xaa, 0xbb, // user_data_unregistered, payload_len 2
+            0x01, 0x01, 0xcc, // pic_timing, payload_len 1
+            0x05, 0x01, 0xdd, // user_data_unregistered, payload_len 1
+            0x80, // rbsp_trailing_bits
+        ];
+        let nal = RefNal::new(&data[..], &[], true);
+        let mut scratch = Vec::new();
+        let mut reader = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), &mut scratch);
+        let msg = reader.next_of_type(HeaderType::PicTiming).unwrap().unwrap();
+        assert_eq!(msg.payload, &[0xcc]);
+        let msg = reader.next().unwrap().unwrap();
+        assert_eq!(msg.payload_type, HeaderType::UserDataUnregistered);
+        assert_eq!(msg.payload, &[0xdd]);
+        assert!(reader.next().unwrap().is_none());
+
+        let mut scratch = Vec::new();
+        let mut reader = SeiReader::from_rbsp_bytes(nal.rbsp_bytes(), &mut scratch);
+        assert!(reader
+            .next_of_type(HeaderType::RecoveryPoint)
+            .unwrap()
+            .is_none());
+        assert!(scratch.is_empty());
+
+        let truncated = RefNal::new(&data[..4], &[], true);
+        let mut scratch = Vec::new();
+        let mut reader = SeiReader::from_rbsp_bytes(truncated.rbsp_bytes(), &mut scratch);
+        assert!(matches!(
+            reader.next_of_type(HeaderType::PicTiming),
+            Err(BitReaderError::ReaderErrorFor("payload", _))
+        ));
+    }
+
+    #[test]
+    fn from_nal() {
+        let mut scratch = Vec::new();
+        let nal = RefNal::new(&[0x68, 0xe8, 0x43, 0x8f, 0x13, 0x21, 0x30][..], &[], true);
+        assert!(matches!(
+            SeiReader::from_nal(&nal, &mut scratch),
+            Err(SeiNalError::UnsupportedNalUnitType(
+                UnitType::PicParameterSet
+            ))
+        ));
+        let nal = RefNal::new(&[0x86, 0x80][..], &[], true);
+        assert!(matches!(
+            SeiReader::from_nal(&nal, &mut scratch),
+            Err(SeiNalError::InvalidNalHeader(_))
+        ));
+        let nal = RefNal::new(&[0x06, 0x80][..], &[], true);
+        let mut reader = SeiReader::from_nal(&nal, &mut scratch).unwrap();
+        assert!(reader.next().unwrap().is_none());
+    }
+
+    #[test]
+    fn max_payload_len() {
+        let data = [
+            0x06, // SEI
+            0x05, 0xff, 0xff, 0x02, // payload_type 5, payload_len 512
+        ];
+        let nal = RefNal::new(&data[..], &[], true);
+        let mut scratch = Vec::new();
+        let mut reader = SeiReader::with_max_payload_len(nal.rbsp_bytes(), &mut scratch, 256);
+        assert!(matches!(
+            reader.next(),
+            Err(BitReaderError::ReaderErrorFor("payload_len", _))
+        ));
+        assert!(scratch.is_empty());
+
+        let data = [
+            0x06, // SEI
+            0x05, 0x02, 0xaa, 0xbb, // payload_type 5, payload_len 2
+            0x80, // rbsp_trailing_bits
+        ];
+        let nal = RefNal::new(&data[..], &[], true);
+        let mut reader = SeiReader::with_max_payload_len(nal.rbsp_bytes(), &mut scratch, 2);
+        assert_eq!(reader.next().unwrap().unwrap().payload, &[0xaa, 0xbb]);
+        assert!(reader.next().unwrap().is_none());
+    }
 }
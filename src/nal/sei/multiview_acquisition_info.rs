@@ -0,0 +1,286 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum MultiviewAcquisitionInfoError {
+    RbspError(BitReaderError),
+    /// `num_views_minus1` must be in the range `0` to `1023` inclusive.
+    InvalidNumViewsMinus1(u32),
+    /// The named `prec_*` field must be in the range `0` to `31` inclusive.
+    InvalidPrecision(&'static str, u32),
+    /// The named `exponent_*` field had the reserved value `63`.
+    ReservedExponent(&'static str),
+}
+impl From<BitReaderError> for MultiviewAcquisitionInfoError {
+    fn from(e: BitReaderError) -> Self {
+        MultiviewAcquisitionInfoError::RbspError(e)
+    }
+}
+
+/// A camera parameter encoded as sign, exponent and mantissa, per Rec. ITU-T H.264 (06/2019)
+/// section H.13.2.3.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct CameraParameter {
+    pub sign: bool,
+    pub exponent: u8,
+    pub mantissa: u64,
+    /// The number of bits in `mantissa`, which is derived from `exponent` and the relevant
+    /// `prec_*` field.
+    pub mantissa_len: u32,
+}
+impl CameraParameter {
+    fn read<R: BitRead>(
+        r: &mut R,
+        prec: u32,
+        name: &'static str,
+    ) -> Result<CameraParameter, MultiviewAcquisitionInfoError> {
+        let sign = r.read_bool(name)?;
+        let exponent = r.read_u8(6, name)?;
+        if exponent == 63 {
+            return Err(MultiviewAcquisitionInfoError::ReservedExponent(name));
+        }
+        let mantissa_len = if exponent == 0 {
+            prec.saturating_sub(30)
+        } else {
+            (u32::from(exponent) + prec).saturating_sub(31)
+        };
+        // The mantissa may be up to 62 bits long; read it in two parts if necessary.
+        let mantissa = if mantissa_len > 32 {
+            let high = u64::from(r.read_u32(mantissa_len - 32, name)?);
+            high << 32 | u64::from(r.read_u32(32, name)?)
+        } else if mantissa_len > 0 {
+            u64::from(r.read_u32(mantissa_len, name)?)
+        } else {
+            0
+        };
+        Ok(CameraParameter {
+            sign,
+            exponent,
+            mantissa,
+            mantissa_len,
+        })
+    }
+
+    /// Returns the value represented by this parameter.
+    pub fn value(&self) -> f64 {
+        let mantissa = self.mantissa as f64 / 2f64.powi(self.mantissa_len as i32);
+        let magnitude = if self.exponent == 0 {
+            2f64.powi(-30) * mantissa
+        } else {
+            2f64.powi(i32::from(self.exponent) - 31) * (1.0 + mantissa)
+        };
+        if self.sign {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IntrinsicViewParams {
+    pub focal_length_x: CameraParameter,
+    pub focal_length_y: CameraParameter,
+    pub principal_point_x: CameraParameter,
+    pub principal_point_y: CameraParameter,
+    pub skew_factor: CameraParameter,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IntrinsicParams {
+    pub prec_focal_length: u32,
+    pub prec_principal_point: u32,
+    pub prec_skew_factor: u32,
+    /// Contains a single entry shared by all views if `intrinsic_params_equal_flag` was set,
+    /// otherwise one entry per view.
+    pub views: Vec<IntrinsicViewParams>,
+}
+impl IntrinsicParams {
+    fn read<R: BitRead>(
+        r: &mut R,
+        num_views_minus1: u32,
+    ) -> Result<IntrinsicParams, MultiviewAcquisitionInfoError> {
+        let intrinsic_params_equal_flag = r.read_bool("intrinsic_params_equal_flag")?;
+        let prec_focal_length = read_prec(r, "prec_focal_length")?;
+        let prec_principal_point = read_prec(r, "prec_principal_point")?;
+        let prec_skew_factor = read_prec(r, "prec_skew_factor")?;
+        let num_views = if intrinsic_params_equal_flag {
+            1
+        } else {
+            num_views_minus1 + 1
+        };
+        let mut views = Vec::with_capacity(num_views as usize);
+        for _ in 0..num_views {
+            views.push(IntrinsicViewParams {
+                focal_length_x: CameraParameter::read(r, prec_focal_length, "focal_length_x")?,
+                focal_length_y: CameraParameter::read(r, prec_focal_length, "focal_length_y")?,
+                principal_point_x: CameraParameter::read(
+                    r,
+                    prec_principal_point,
+                    "principal_point_x",
+                )?,
+                principal_point_y: CameraParameter::read(
+                    r,
+                    prec_principal_point,
+                    "principal_point_y",
+                )?,
+                skew_factor: CameraParameter::read(r, prec_skew_factor, "skew_factor")?,
+            });
+        }
+        Ok(IntrinsicParams {
+            prec_focal_length,
+            prec_principal_point,
+            prec_skew_factor,
+            views,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExtrinsicViewParams {
+    /// Rotation matrix, indexed by `[row][column]`.
+    pub rotation: [[CameraParameter; 3]; 3],
+    pub translation: [CameraParameter; 3],
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExtrinsicParams {
+    pub prec_rotation_param: u32,
+    pub prec_translation_param: u32,
+    pub views: Vec<ExtrinsicViewParams>,
+}
+impl ExtrinsicParams {
+    fn read<R: BitRead>(
+        r: &mut R,
+        num_views_minus1: u32,
+    ) -> Result<ExtrinsicParams, MultiviewAcquisitionInfoError> {
+        let prec_rotation_param = read_prec(r, "prec_rotation_param")?;
+        let prec_translation_param = read_prec(r, "prec_translation_param")?;
+        let mut views = Vec::with_capacity(num_views_minus1 as usize + 1);
+        for _ in 0..=num_views_minus1 {
+            let mut rotation = [[CameraParameter::default(); 3]; 3];
+            let mut translation = [CameraParameter::default(); 3];
+            for (row, t) in rotation.iter_mut().zip(translation.iter_mut()) {
+                for r_param in row.iter_mut() {
+                    *r_param = CameraParameter::read(r, prec_rotation_param, "r")?;
+                }
+                *t = CameraParameter::read(r, prec_translation_param, "t")?;
+            }
+            views.push(ExtrinsicViewParams {
+                rotation,
+                translation,
+            });
+        }
+        Ok(ExtrinsicParams {
+            prec_rotation_param,
+            prec_translation_param,
+            views,
+        })
+    }
+}
+
+fn read_prec<R: BitRead>(
+    r: &mut R,
+    name: &'static str,
+) -> Result<u32, MultiviewAcquisitionInfoError> {
+    let prec = r.read_ue(name)?;
+    if prec > 31 {
+        return Err(MultiviewAcquisitionInfoError::InvalidPrecision(name, prec));
+    }
+    Ok(prec)
+}
+
+/// The `multiview_acquisition_info` SEI message, which describes the intrinsic and extrinsic
+/// camera parameters of the views in an MVC bitstream.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MultiviewAcquisitionInfo {
+    pub num_views_minus1: u32,
+    pub intrinsic_params: Option<IntrinsicParams>,
+    pub extrinsic_params: Option<ExtrinsicParams>,
+}
+impl MultiviewAcquisitionInfo {
+    pub fn read(
+        msg: &SeiMessage<'_>,
+    ) -> Result<MultiviewAcquisitionInfo, MultiviewAcquisitionInfoError> {
+        assert_eq!(msg.payload_type, HeaderType::MultiviewAcquisitionInfo);
+        let mut r = BitReader::new(msg.payload);
+        let num_views_minus1 = r.read_ue("num_views_minus1")?;
+        if num_views_minus1 > 1023 {
+            return Err(MultiviewAcquisitionInfoError::InvalidNumViewsMinus1(
+                num_views_minus1,
+            ));
+        }
+        let intrinsic_param_flag = r.read_bool("intrinsic_param_flag")?;
+        let extrinsic_param_flag = r.read_bool("extrinsic_param_flag")?;
+        let intrinsic_params = if intrinsic_param_flag {
+            Some(IntrinsicParams::read(&mut r, num_views_minus1)?)
+        } else {
+            None
+        };
+        let extrinsic_params = if extrinsic_param_flag {
+            Some(ExtrinsicParams::read(&mut r, num_views_minus1)?)
+        } else {
+            None
+        };
+        r.finish_sei_payload()?;
+        Ok(MultiviewAcquisitionInfo {
+            num_views_minus1,
+            intrinsic_params,
+            extrinsic_params,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn parse() {
+        // Two views with shared intrinsic parameters, and extrinsic parameters in which every
+        // element is 1.0.
+        let msg = SeiMessage {
+            payload_type: HeaderType::MultiviewAcquisitionInfo,
+            payload: &hex!(
+                "5c 10 67 e0 00 00 00 60 00 00 00 00 00 00 06 7c
+                 f9 f3 e7 cf 9f 3e 7c f9 f3 e7 cf 9f 3e 7c f9 f3
+                 e7 cf 9f 3f"
+            )[..],
+        };
+        let info = MultiviewAcquisitionInfo::read(&msg).unwrap();
+        assert_eq!(info.num_views_minus1, 1);
+        let intrinsic = info.intrinsic_params.unwrap();
+        assert_eq!(intrinsic.prec_focal_length, 31);
+        assert_eq!(intrinsic.views.len(), 1);
+        assert_eq!(intrinsic.views[0].focal_length_x.value(), 1.5);
+        assert_eq!(intrinsic.views[0].focal_length_y.value(), -2.0);
+        assert_eq!(intrinsic.views[0].principal_point_x.value(), 0.0);
+        let extrinsic = info.extrinsic_params.unwrap();
+        assert_eq!(extrinsic.views.len(), 2);
+        for view in &extrinsic.views {
+            for row in &view.rotation {
+                for param in row {
+                    assert_eq!(param.value(), 1.0);
+                }
+            }
+            assert_eq!(view.translation[2].value(), 1.0);
+        }
+    }
+
+    #[test]
+    fn too_many_views() {
+        // num_views_minus1 = 1024
+        let msg = SeiMessage {
+            payload_type: HeaderType::MultiviewAcquisitionInfo,
+            payload: &hex!("00 20 09")[..],
+        };
+        assert!(matches!(
+            MultiviewAcquisitionInfo::read(&msg),
+            Err(MultiviewAcquisitionInfoError::InvalidNumViewsMinus1(1024))
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum MultiviewViewPositionError {
+    RbspError(BitReaderError),
+    /// `num_views_minus1` must be in the range `0` to `1023` inclusive.
+    InvalidNumViewsMinus1(u32),
+}
+impl From<BitReaderError> for MultiviewViewPositionError {
+    fn from(e: BitReaderError) -> Self {
+        MultiviewViewPositionError::RbspError(e)
+    }
+}
+
+/// The `multiview_view_position` SEI message, which gives the left-to-right order in which the
+/// views of an MVC bitstream should be displayed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MultiviewViewPosition {
+    /// `view_position[i]` for each view, indexed by view order index. A view with a smaller
+    /// position is displayed to the left of one with a larger position.
+    pub view_positions: Vec<u32>,
+}
+impl MultiviewViewPosition {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<MultiviewViewPosition, MultiviewViewPositionError> {
+        assert_eq!(msg.payload_type, HeaderType::MultiviewViewPosition);
+        let mut r = BitReader::new(msg.payload);
+        let num_views_minus1 = r.read_ue("num_views_minus1")?;
+        if num_views_minus1 > 1023 {
+            return Err(MultiviewViewPositionError::InvalidNumViewsMinus1(
+                num_views_minus1,
+            ));
+        }
+        let mut view_positions = Vec::with_capacity(num_views_minus1 as usize + 1);
+        for _ in 0..=num_views_minus1 {
+            view_positions.push(r.read_ue("view_position")?);
+        }
+        // Decoders must ignore any extension data, which is present if this flag is set.
+        if !r.read_bool("multiview_view_position_extension_flag")? {
+            r.finish_sei_payload()?;
+        }
+        Ok(MultiviewViewPosition { view_positions })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // num_views_minus1=1 (010), view_position = [1 (010), 0 (1)], extension flag 0, then
+        // trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::MultiviewViewPosition,
+            payload: &[0b0100_1010, 0b1000_0000],
+        };
+        assert_eq!(
+            MultiviewViewPosition::read(&msg).unwrap(),
+            MultiviewViewPosition {
+                view_positions: vec![1, 0],
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_data() {
+        let msg = SeiMessage {
+            payload_type: HeaderType::MultiviewViewPosition,
+            payload: &[0b0100_1010, 0b1000_0001],
+        };
+        assert!(matches!(
+            MultiviewViewPosition::read(&msg),
+            Err(MultiviewViewPositionError::RbspError(
+                BitReaderError::RemainingData
+            ))
+        ));
+    }
+}
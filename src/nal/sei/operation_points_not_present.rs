@@ -0,0 +1,96 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum OperationPointsNotPresentError {
+    RbspError(BitReaderError),
+    /// `num_operation_points` must be in the range `0` to `2047` inclusive.
+    InvalidNumOperationPoints(u32),
+}
+impl From<BitReaderError> for OperationPointsNotPresentError {
+    fn from(e: BitReaderError) -> Self {
+        OperationPointsNotPresentError::RbspError(e)
+    }
+}
+
+/// The `operation_points_not_present` SEI message, which lists the operation points of an MVC
+/// bitstream (as identified in the `view_scalability_info` SEI message) that aren't present.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OperationPointsNotPresent {
+    /// The `operation_point_not_present_id` of each operation point which isn't present.
+    pub operation_point_ids: Vec<u32>,
+}
+impl OperationPointsNotPresent {
+    pub fn read(
+        msg: &SeiMessage<'_>,
+    ) -> Result<OperationPointsNotPresent, OperationPointsNotPresentError> {
+        assert_eq!(msg.payload_type, HeaderType::OperationPointsNotPresent);
+        let mut r = BitReader::new(msg.payload);
+        let num_operation_points = r.read_ue("num_operation_points")?;
+        if num_operation_points > 2047 {
+            return Err(OperationPointsNotPresentError::InvalidNumOperationPoints(
+                num_operation_points,
+            ));
+        }
+        let mut operation_point_ids = Vec::with_capacity(num_operation_points as usize);
+        for _ in 0..num_operation_points {
+            operation_point_ids.push(r.read_ue("operation_point_not_present_id")?);
+        }
+        r.finish_sei_payload()?;
+        Ok(OperationPointsNotPresent {
+            operation_point_ids,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // num_operation_points=1 (010), operation_point_not_present_id=4 (00101), then trailing
+        // bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::OperationPointsNotPresent,
+            payload: &[0b0100_0101, 0b1000_0000],
+        };
+        assert_eq!(
+            OperationPointsNotPresent::read(&msg).unwrap(),
+            OperationPointsNotPresent {
+                operation_point_ids: vec![4],
+            }
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        // num_operation_points=1000, but no ids.
+        let msg = SeiMessage {
+            payload_type: HeaderType::OperationPointsNotPresent,
+            payload: &[0x00, 0x7d, 0x20],
+        };
+        assert!(matches!(
+            OperationPointsNotPresent::read(&msg),
+            Err(OperationPointsNotPresentError::RbspError(_))
+        ));
+    }
+
+    #[test]
+    fn too_many_operation_points() {
+        // num_operation_points=2048 (11 zero bits, then 1000_0000_0001).
+        let msg = SeiMessage {
+            payload_type: HeaderType::OperationPointsNotPresent,
+            payload: &[0x00, 0x10, 0x02],
+        };
+        assert!(matches!(
+            OperationPointsNotPresent::read(&msg),
+            Err(OperationPointsNotPresentError::InvalidNumOperationPoints(
+                2048
+            ))
+        ));
+    }
+}
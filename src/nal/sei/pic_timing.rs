@@ -9,6 +9,9 @@ use crate::rbsp::BitReaderError;
 pub enum PicTimingError {
     RbspError(BitReaderError),
     InvalidPicStructId(u8),
+    /// The SPS has both NAL and VCL HRD parameters, but they disagree on the length of the
+    /// `cpb_removal_delay` or `dpb_output_delay` fields.
+    InconsistentDelayLengths,
 }
 impl From<BitReaderError> for PicTimingError {
     fn from(e: BitReaderError) -> Self {
@@ -18,8 +21,8 @@ impl From<BitReaderError> for PicTimingError {
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct Delays {
-    cpb_removal_delay: u32,
-    dpb_output_delay: u32,
+    pub cpb_removal_delay: u32,
+    pub dpb_output_delay: u32,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -258,32 +261,42 @@ impl PicTiming {
         Ok(pic_timing)
     }
 
+    /// Reads the delays, which are present iff `CpbDpbDelaysPresentFlag` is set, i.e. iff the SPS
+    /// has either NAL or VCL HRD parameters. If the SPS has neither but the message contains
+    /// delays anyway, the extra data will cause `finish_sei_payload` to fail.
     fn read_delays<R: BitRead>(
         r: &mut R,
         sps: &sps::SeqParameterSet,
     ) -> Result<Option<Delays>, PicTimingError> {
-        Ok(if let Some(ref vui_params) = sps.vui_parameters {
-            if let Some(ref hrd) = vui_params
-                .nal_hrd_parameters
-                .as_ref()
-                .or_else(|| vui_params.nal_hrd_parameters.as_ref())
-            {
-                Some(Delays {
-                    cpb_removal_delay: r.read_u32(
-                        u32::from(hrd.cpb_removal_delay_length_minus1) + 1,
-                        "cpb_removal_delay",
-                    )?,
-                    dpb_output_delay: r.read_u32(
-                        u32::from(hrd.dpb_output_delay_length_minus1) + 1,
-                        "dpb_output_delay",
-                    )?,
-                })
-            } else {
-                None
+        let vui_params = match sps.vui_parameters {
+            Some(ref vui_params) => vui_params,
+            None => return Ok(None),
+        };
+        let hrd = match (
+            &vui_params.nal_hrd_parameters,
+            &vui_params.vcl_hrd_parameters,
+        ) {
+            (Some(nal), Some(vcl)) => {
+                if nal.cpb_removal_delay_length_minus1 != vcl.cpb_removal_delay_length_minus1
+                    || nal.dpb_output_delay_length_minus1 != vcl.dpb_output_delay_length_minus1
+                {
+                    return Err(PicTimingError::InconsistentDelayLengths);
+                }
+                nal
             }
-        } else {
-            None
-        })
+            (Some(hrd), None) | (None, Some(hrd)) => hrd,
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Delays {
+            cpb_removal_delay: r.read_u32(
+                u32::from(hrd.cpb_removal_delay_length_minus1) + 1,
+                "cpb_removal_delay",
+            )?,
+            dpb_output_delay: r.read_u32(
+                u32::from(hrd.dpb_output_delay_length_minus1) + 1,
+                "dpb_output_delay",
+            )?,
+        }))
     }
 
     fn read_pic_struct<R: BitRead>(
@@ -361,4 +374,59 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn hrd_variants() {
+        // The SPS from `parse`, which has both NAL and VCL HRD parameters.
+        let sps = hex!(
+            "
+            4d 60 15 8d 8d 28 58 9d 08 00 00 0f a0 00 07 53
+            07 00 00 00 92 7c 00 00 12 4f 80 fb dc 18 00 00
+            0f 42 40 00 07 a1 20 7d ee 07 c6 0c 62 60
+        "
+        );
+        let both_sps = sps::SeqParameterSet::from_bits(rbsp::BitReader::new(&sps[..])).unwrap();
+        let vui = both_sps.vui_parameters.as_ref().unwrap();
+        assert!(vui.nal_hrd_parameters.is_some());
+        assert!(vui.vcl_hrd_parameters.is_some());
+        let msg = SeiMessage {
+            payload_type: HeaderType::PicTiming,
+            payload: &hex!("00 00 00 00 00 0c 72")[..],
+        };
+        let expected = PicTiming::read(&both_sps, &msg).unwrap();
+
+        // NAL HRD parameters only.
+        let mut nal_sps = both_sps.clone();
+        nal_sps.vui_parameters.as_mut().unwrap().vcl_hrd_parameters = None;
+        assert_eq!(PicTiming::read(&nal_sps, &msg).unwrap(), expected);
+
+        // VCL HRD parameters only.
+        let mut vcl_sps = both_sps.clone();
+        vcl_sps.vui_parameters.as_mut().unwrap().nal_hrd_parameters = None;
+        assert_eq!(PicTiming::read(&vcl_sps, &msg).unwrap(), expected);
+
+        // Both, disagreeing.
+        let mut inconsistent_sps = both_sps.clone();
+        let vui = inconsistent_sps.vui_parameters.as_mut().unwrap();
+        vui.vcl_hrd_parameters
+            .as_mut()
+            .unwrap()
+            .dpb_output_delay_length_minus1 += 1;
+        assert!(matches!(
+            PicTiming::read(&inconsistent_sps, &msg),
+            Err(PicTimingError::InconsistentDelayLengths)
+        ));
+
+        // Neither, in which case the delays in the message are unexpected.
+        let mut no_hrd_sps = vcl_sps;
+        no_hrd_sps
+            .vui_parameters
+            .as_mut()
+            .unwrap()
+            .vcl_hrd_parameters = None;
+        assert!(matches!(
+            PicTiming::read(&no_hrd_sps, &msg),
+            Err(PicTimingError::RbspError(_))
+        ));
+    }
 }
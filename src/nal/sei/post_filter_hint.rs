@@ -0,0 +1,157 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::nal::sps;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum PostFilterHintError {
+    RbspError(BitReaderError),
+    /// The named `filter_hint_size_*` field must be in the range `1` to `15` inclusive.
+    InvalidFilterHintSize(&'static str, u32),
+}
+impl From<BitReaderError> for PostFilterHintError {
+    fn from(e: BitReaderError) -> Self {
+        PostFilterHintError::RbspError(e)
+    }
+}
+
+/// How the coefficients of a [`PostFilterHint`] are to be interpreted, per `filter_hint_type`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FilterHintType {
+    /// The coefficients of a two-dimensional FIR filter.
+    TwoDimensionalFir,
+    /// The coefficients of two one-dimensional FIR filters, the horizontal filter in the first
+    /// row and the vertical filter in the second.
+    OneDimensionalFirs,
+    /// A cross-correlation matrix between the original and decoded signals.
+    CrossCorrelation,
+    Reserved(u8),
+}
+
+/// The `post_filter_hint` SEI message, which suggests a filter to apply to the decoded
+/// pictures, e.g. for deblocking or denoising.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PostFilterHint {
+    pub filter_hint_size_y: u32,
+    pub filter_hint_size_x: u32,
+    pub filter_hint_type: FilterHintType,
+    /// The coefficients, indexed by `[cIdx][cy][cx]`. There's a single colour component for
+    /// monochrome streams, and otherwise three.
+    pub filter_hint: Vec<Vec<Vec<i32>>>,
+}
+impl PostFilterHint {
+    /// Parses a `PostFilterHint` from the given SEI message, which refers to `sps` for the
+    /// number of colour components.
+    pub fn read(
+        sps: &sps::SeqParameterSet,
+        msg: &SeiMessage<'_>,
+    ) -> Result<PostFilterHint, PostFilterHintError> {
+        assert_eq!(msg.payload_type, HeaderType::PostFilterHint);
+        let mut r = BitReader::new(msg.payload);
+        let filter_hint_size_y = read_size(&mut r, "filter_hint_size_y")?;
+        let filter_hint_size_x = read_size(&mut r, "filter_hint_size_x")?;
+        let filter_hint_type = match r.read_u8(2, "filter_hint_type")? {
+            0 => FilterHintType::TwoDimensionalFir,
+            1 => FilterHintType::OneDimensionalFirs,
+            2 => FilterHintType::CrossCorrelation,
+            other => FilterHintType::Reserved(other),
+        };
+        let num_components = if sps.chroma_info.chroma_format == sps::ChromaFormat::Monochrome {
+            1
+        } else {
+            3
+        };
+        // The sizes are at most 15, so this is bounded at 675 coefficients.
+        let filter_hint = (0..num_components)
+            .map(|_| {
+                (0..filter_hint_size_y)
+                    .map(|_| {
+                        (0..filter_hint_size_x)
+                            .map(|_| r.read_se("filter_hint"))
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Decoders must ignore any extension data, which is present if this flag is set.
+        if !r.read_bool("additional_extension_flag")? {
+            r.finish_sei_payload()?;
+        }
+        Ok(PostFilterHint {
+            filter_hint_size_y,
+            filter_hint_size_x,
+            filter_hint_type,
+            filter_hint,
+        })
+    }
+}
+
+fn read_size<R: BitRead>(r: &mut R, name: &'static str) -> Result<u32, PostFilterHintError> {
+    let size = r.read_ue(name)?;
+    if !(1..=15).contains(&size) {
+        return Err(PostFilterHintError::InvalidFilterHintSize(name, size));
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nal::sps::SeqParameterSetBuilder;
+    use crate::rbsp::{BitWrite, BitWriter};
+
+    #[test]
+    fn parse() {
+        let mut sps = SeqParameterSetBuilder::default().build();
+        sps.chroma_info.chroma_format = sps::ChromaFormat::Monochrome;
+        let mut w = BitWriter::new(Vec::new());
+        w.write_ue(2).unwrap(); // filter_hint_size_y
+        w.write_ue(3).unwrap(); // filter_hint_size_x
+        w.write_u8(2, 1).unwrap(); // filter_hint_type
+        for v in [1, -2, 3, 0, 5, -6] {
+            w.write_se(v).unwrap();
+        }
+        w.write_bool(false).unwrap(); // additional_extension_flag
+        w.write_rbsp_trailing_bits().unwrap();
+        let payload = w.into_writer();
+        let msg = SeiMessage {
+            payload_type: HeaderType::PostFilterHint,
+            payload: &payload[..],
+        };
+        assert_eq!(
+            PostFilterHint::read(&sps, &msg).unwrap(),
+            PostFilterHint {
+                filter_hint_size_y: 2,
+                filter_hint_size_x: 3,
+                filter_hint_type: FilterHintType::OneDimensionalFirs,
+                filter_hint: vec![vec![vec![1, -2, 3], vec![0, 5, -6]]],
+            }
+        );
+
+        // With chroma, there are three times as many coefficients; these are missing.
+        sps.chroma_info.chroma_format = sps::ChromaFormat::YUV420;
+        assert!(matches!(
+            PostFilterHint::read(&sps, &msg),
+            Err(PostFilterHintError::RbspError(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_size() {
+        let sps = SeqParameterSetBuilder::default().build();
+        // filter_hint_size_y = 0
+        let msg = SeiMessage {
+            payload_type: HeaderType::PostFilterHint,
+            payload: &[0b1000_0000],
+        };
+        assert!(matches!(
+            PostFilterHint::read(&sps, &msg),
+            Err(PostFilterHintError::InvalidFilterHintSize(
+                "filter_hint_size_y",
+                0
+            ))
+        ));
+    }
+}
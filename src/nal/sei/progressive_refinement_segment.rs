@@ -0,0 +1,114 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum ProgressiveRefinementSegmentError {
+    RbspError(BitReaderError),
+}
+impl From<BitReaderError> for ProgressiveRefinementSegmentError {
+    fn from(e: BitReaderError) -> Self {
+        ProgressiveRefinementSegmentError::RbspError(e)
+    }
+}
+
+/// The `progressive_refinement_segment_start` SEI message, which marks the start of a sequence
+/// of pictures each refining the quality of the previous one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ProgressiveRefinementSegmentStart {
+    pub progressive_refinement_id: u32,
+    /// The number of refinement steps in the segment, minus 1; `0` indicates that the number is
+    /// unknown.
+    pub num_refinement_steps_minus1: u32,
+}
+impl ProgressiveRefinementSegmentStart {
+    pub fn read(
+        msg: &SeiMessage<'_>,
+    ) -> Result<ProgressiveRefinementSegmentStart, ProgressiveRefinementSegmentError> {
+        assert_eq!(
+            msg.payload_type,
+            HeaderType::ProgressiveRefinementSegmentStart
+        );
+        let mut r = BitReader::new(msg.payload);
+        let start = ProgressiveRefinementSegmentStart {
+            progressive_refinement_id: r.read_ue("progressive_refinement_id")?,
+            num_refinement_steps_minus1: r.read_ue("num_refinement_steps_minus1")?,
+        };
+        r.finish_sei_payload()?;
+        Ok(start)
+    }
+}
+
+/// The `progressive_refinement_segment_end` SEI message, which marks the end of the segment
+/// started by the [`ProgressiveRefinementSegmentStart`] with the same id.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ProgressiveRefinementSegmentEnd {
+    pub progressive_refinement_id: u32,
+}
+impl ProgressiveRefinementSegmentEnd {
+    pub fn read(
+        msg: &SeiMessage<'_>,
+    ) -> Result<ProgressiveRefinementSegmentEnd, ProgressiveRefinementSegmentError> {
+        assert_eq!(
+            msg.payload_type,
+            HeaderType::ProgressiveRefinementSegmentEnd
+        );
+        let mut r = BitReader::new(msg.payload);
+        let end = ProgressiveRefinementSegmentEnd {
+            progressive_refinement_id: r.read_ue("progressive_refinement_id")?,
+        };
+        r.finish_sei_payload()?;
+        Ok(end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn start() {
+        // progressive_refinement_id=3 (00100), num_refinement_steps_minus1=0 (1), then
+        // trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::ProgressiveRefinementSegmentStart,
+            payload: &[0b0010_0110],
+        };
+        assert_eq!(
+            ProgressiveRefinementSegmentStart::read(&msg).unwrap(),
+            ProgressiveRefinementSegmentStart {
+                progressive_refinement_id: 3,
+                num_refinement_steps_minus1: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn end() {
+        // progressive_refinement_id=3 (00100), then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::ProgressiveRefinementSegmentEnd,
+            payload: &[0b0010_0100],
+        };
+        assert_eq!(
+            ProgressiveRefinementSegmentEnd::read(&msg).unwrap(),
+            ProgressiveRefinementSegmentEnd {
+                progressive_refinement_id: 3,
+            }
+        );
+
+        // A stray bit after the trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::ProgressiveRefinementSegmentEnd,
+            payload: &[0b0010_0101],
+        };
+        assert!(matches!(
+            ProgressiveRefinementSegmentEnd::read(&msg),
+            Err(ProgressiveRefinementSegmentError::RbspError(
+                BitReaderError::RemainingData
+            ))
+        ));
+    }
+}
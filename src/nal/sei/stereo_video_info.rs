@@ -0,0 +1,117 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum StereoVideoInfoError {
+    RbspError(BitReaderError),
+}
+impl From<BitReaderError> for StereoVideoInfoError {
+    fn from(e: BitReaderError) -> Self {
+        StereoVideoInfoError::RbspError(e)
+    }
+}
+
+/// How the two views are arranged, as signalled by `field_views_flag`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StereoViews {
+    /// The views are carried in the opposite fields of each frame (`field_views_flag` set).
+    Fields { top_field_is_left_view_flag: bool },
+    /// The views are carried in alternating frames (`field_views_flag` clear).
+    Frames {
+        current_frame_is_left_view_flag: bool,
+        next_frame_is_second_view_flag: bool,
+    },
+}
+
+/// The `stereo_video_info` SEI message, which indicates that the pictures of the bitstream
+/// carry the left and right views of stereoscopic video.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct StereoVideoInfo {
+    pub views: StereoViews,
+    pub left_view_self_contained_flag: bool,
+    pub right_view_self_contained_flag: bool,
+}
+impl StereoVideoInfo {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<StereoVideoInfo, StereoVideoInfoError> {
+        assert_eq!(msg.payload_type, HeaderType::StereoVideoInfo);
+        let mut r = BitReader::new(msg.payload);
+        let views = if r.read_bool("field_views_flag")? {
+            StereoViews::Fields {
+                top_field_is_left_view_flag: r.read_bool("top_field_is_left_view_flag")?,
+            }
+        } else {
+            StereoViews::Frames {
+                current_frame_is_left_view_flag: r.read_bool("current_frame_is_left_view_flag")?,
+                next_frame_is_second_view_flag: r.read_bool("next_frame_is_second_view_flag")?,
+            }
+        };
+        let stereo_video_info = StereoVideoInfo {
+            views,
+            left_view_self_contained_flag: r.read_bool("left_view_self_contained_flag")?,
+            right_view_self_contained_flag: r.read_bool("right_view_self_contained_flag")?,
+        };
+        r.finish_sei_payload()?;
+        Ok(stereo_video_info)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // field_views_flag=1, top_field_is_left_view_flag=0, left_view_self_contained_flag=1,
+        // right_view_self_contained_flag=0, then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::StereoVideoInfo,
+            payload: &[0b1010_1000],
+        };
+        assert_eq!(
+            StereoVideoInfo::read(&msg).unwrap(),
+            StereoVideoInfo {
+                views: StereoViews::Fields {
+                    top_field_is_left_view_flag: false,
+                },
+                left_view_self_contained_flag: true,
+                right_view_self_contained_flag: false,
+            }
+        );
+
+        // field_views_flag=0, current_frame_is_left_view_flag=1,
+        // next_frame_is_second_view_flag=1, left_view_self_contained_flag=0,
+        // right_view_self_contained_flag=1, then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::StereoVideoInfo,
+            payload: &[0b0110_1100],
+        };
+        assert_eq!(
+            StereoVideoInfo::read(&msg).unwrap(),
+            StereoVideoInfo {
+                views: StereoViews::Frames {
+                    current_frame_is_left_view_flag: true,
+                    next_frame_is_second_view_flag: true,
+                },
+                left_view_self_contained_flag: false,
+                right_view_self_contained_flag: true,
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_data() {
+        let msg = SeiMessage {
+            payload_type: HeaderType::StereoVideoInfo,
+            payload: &[0b1010_1100],
+        };
+        assert!(matches!(
+            StereoVideoInfo::read(&msg),
+            Err(StereoVideoInfoError::RbspError(
+                BitReaderError::RemainingData
+            ))
+        ));
+    }
+}
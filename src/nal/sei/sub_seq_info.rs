@@ -0,0 +1,101 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum SubSeqInfoError {
+    RbspError(BitReaderError),
+    /// `sub_seq_layer_num` must be in the range `0` to `255` inclusive.
+    InvalidSubSeqLayerNum(u32),
+    /// `sub_seq_id` must be in the range `0` to `65535` inclusive.
+    InvalidSubSeqId(u32),
+}
+impl From<BitReaderError> for SubSeqInfoError {
+    fn from(e: BitReaderError) -> Self {
+        SubSeqInfoError::RbspError(e)
+    }
+}
+
+/// The `sub_seq_info` SEI message, which identifies the sub-sequence (and sub-sequence layer)
+/// that the current picture belongs to.
+#[derive(Debug, Eq, PartialEq)]
+pub struct SubSeqInfo {
+    pub sub_seq_layer_num: u8,
+    pub sub_seq_id: u16,
+    pub first_ref_pic_flag: bool,
+    pub leading_non_ref_pic_flag: bool,
+    pub last_pic_flag: bool,
+    /// Present when `sub_seq_frame_num_flag` is set.
+    pub sub_seq_frame_num: Option<u32>,
+}
+impl SubSeqInfo {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<SubSeqInfo, SubSeqInfoError> {
+        assert_eq!(msg.payload_type, HeaderType::SubSeqInfo);
+        let mut r = BitReader::new(msg.payload);
+        let sub_seq_layer_num = r.read_ue("sub_seq_layer_num")?;
+        if sub_seq_layer_num > 255 {
+            return Err(SubSeqInfoError::InvalidSubSeqLayerNum(sub_seq_layer_num));
+        }
+        let sub_seq_id = r.read_ue("sub_seq_id")?;
+        if sub_seq_id > 65535 {
+            return Err(SubSeqInfoError::InvalidSubSeqId(sub_seq_id));
+        }
+        let sub_seq_info = SubSeqInfo {
+            sub_seq_layer_num: sub_seq_layer_num as u8,
+            sub_seq_id: sub_seq_id as u16,
+            first_ref_pic_flag: r.read_bool("first_ref_pic_flag")?,
+            leading_non_ref_pic_flag: r.read_bool("leading_non_ref_pic_flag")?,
+            last_pic_flag: r.read_bool("last_pic_flag")?,
+            sub_seq_frame_num: if r.read_bool("sub_seq_frame_num_flag")? {
+                Some(r.read_ue("sub_seq_frame_num")?)
+            } else {
+                None
+            },
+        };
+        r.finish_sei_payload()?;
+        Ok(sub_seq_info)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // sub_seq_layer_num=1 (010), sub_seq_id=0 (1), first_ref_pic_flag=1,
+        // leading_non_ref_pic_flag=0, last_pic_flag=0, sub_seq_frame_num_flag=1,
+        // sub_seq_frame_num=2 (011), then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::SubSeqInfo,
+            payload: &[0b0101_1001, 0b0111_0000],
+        };
+        assert_eq!(
+            SubSeqInfo::read(&msg).unwrap(),
+            SubSeqInfo {
+                sub_seq_layer_num: 1,
+                sub_seq_id: 0,
+                first_ref_pic_flag: true,
+                leading_non_ref_pic_flag: false,
+                last_pic_flag: false,
+                sub_seq_frame_num: Some(2),
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_data() {
+        // As above, but without sub_seq_frame_num and with a stray bit set before the trailing
+        // bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::SubSeqInfo,
+            payload: &[0b0101_1000, 0b1100_0000],
+        };
+        assert!(matches!(
+            SubSeqInfo::read(&msg),
+            Err(SubSeqInfoError::RbspError(BitReaderError::RemainingData))
+        ));
+    }
+}
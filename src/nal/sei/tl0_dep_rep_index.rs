@@ -0,0 +1,71 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum Tl0DepRepIndexError {
+    RbspError(BitReaderError),
+    /// The payload wasn't the expected 3 bytes long.
+    InvalidPayloadLength(usize),
+}
+impl From<BitReaderError> for Tl0DepRepIndexError {
+    fn from(e: BitReaderError) -> Self {
+        Tl0DepRepIndexError::RbspError(e)
+    }
+}
+
+/// The `tl0_dep_rep_index` SEI message of an SVC stream, which lets a receiver detect the loss
+/// of temporal base layer pictures.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Tl0DepRepIndex {
+    /// The index of the temporal base layer dependency representation, modulo 256.
+    pub tl0_dep_rep_idx: u8,
+    /// The `idr_pic_id` of the most recent IDR access unit.
+    pub effective_idr_pic_id: u16,
+}
+impl Tl0DepRepIndex {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<Tl0DepRepIndex, Tl0DepRepIndexError> {
+        assert_eq!(msg.payload_type, HeaderType::Tl0DepRepIndex);
+        if msg.payload.len() != 3 {
+            return Err(Tl0DepRepIndexError::InvalidPayloadLength(msg.payload.len()));
+        }
+        let mut r = BitReader::new(msg.payload);
+        let index = Tl0DepRepIndex {
+            tl0_dep_rep_idx: r.read_u8(8, "tl0_dep_rep_idx")?,
+            effective_idr_pic_id: r.read_u16(16, "effective_idr_pic_id")?,
+        };
+        r.finish_sei_payload()?;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let msg = SeiMessage {
+            payload_type: HeaderType::Tl0DepRepIndex,
+            payload: &[0x05, 0x12, 0x34],
+        };
+        assert_eq!(
+            Tl0DepRepIndex::read(&msg).unwrap(),
+            Tl0DepRepIndex {
+                tl0_dep_rep_idx: 5,
+                effective_idr_pic_id: 0x1234,
+            }
+        );
+
+        let msg = SeiMessage {
+            payload_type: HeaderType::Tl0DepRepIndex,
+            payload: &[0x05, 0x12],
+        };
+        assert!(matches!(
+            Tl0DepRepIndex::read(&msg),
+            Err(Tl0DepRepIndexError::InvalidPayloadLength(2))
+        ));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::nal::sei::HeaderType;
+use crate::nal::sei::SeiMessage;
+use crate::rbsp::BitRead;
+use crate::rbsp::BitReader;
+use crate::rbsp::BitReaderError;
+
+#[derive(Debug)]
+pub enum TlSwitchingPointError {
+    RbspError(BitReaderError),
+}
+impl From<BitReaderError> for TlSwitchingPointError {
+    fn from(e: BitReaderError) -> Self {
+        TlSwitchingPointError::RbspError(e)
+    }
+}
+
+/// The `tl_switching_point` SEI message of an SVC stream, which marks an access unit at which
+/// decoding can switch up to the temporal layer of the associated dependency representation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TlSwitchingPoint {
+    /// The difference between the `frame_num` of the current access unit and that of the
+    /// earliest access unit from which the switch is possible.
+    pub delta_frame_num: i32,
+}
+impl TlSwitchingPoint {
+    pub fn read(msg: &SeiMessage<'_>) -> Result<TlSwitchingPoint, TlSwitchingPointError> {
+        assert_eq!(msg.payload_type, HeaderType::TlSwitchingPoint);
+        let mut r = BitReader::new(msg.payload);
+        let point = TlSwitchingPoint {
+            delta_frame_num: r.read_se("delta_frame_num")?,
+        };
+        r.finish_sei_payload()?;
+        Ok(point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // delta_frame_num=-2 (00101), then trailing bits.
+        let msg = SeiMessage {
+            payload_type: HeaderType::TlSwitchingPoint,
+            payload: &[0b0010_1100],
+        };
+        assert_eq!(
+            TlSwitchingPoint::read(&msg).unwrap(),
+            TlSwitchingPoint {
+                delta_frame_num: -2
+            }
+        );
+
+        let msg = SeiMessage {
+            payload_type: HeaderType::TlSwitchingPoint,
+            payload: &[],
+        };
+        assert!(matches!(
+            TlSwitchingPoint::read(&msg),
+            Err(TlSwitchingPointError::RbspError(_))
+        ));
+    }
+}
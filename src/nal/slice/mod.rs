@@ -2,32 +2,93 @@ use crate::nal::pps;
 use crate::nal::pps::{PicParamSetId, PicParameterSet};
 use crate::nal::sps;
 use crate::nal::sps::SeqParameterSet;
-use crate::nal::NalHeader;
+use crate::nal::{Nal, NalHeader, NalHeaderError, UnitType};
 use crate::rbsp::BitRead;
 use crate::rbsp::BitReaderError;
+use crate::rbsp::BitWrite;
 use crate::Context;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
-enum SliceFamily {
+pub mod partition;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceFamily {
     P,
     B,
     I,
     SP,
     SI,
 }
-#[derive(Debug, PartialEq)]
-enum SliceExclusive {
+impl SliceFamily {
+    /// True for `I` and `SI` slices, which use only intra prediction.
+    pub fn is_intra(&self) -> bool {
+        matches!(self, SliceFamily::I | SliceFamily::SI)
+    }
+
+    /// True for `P`, `B` and `SP` slices, which may use inter prediction.
+    pub fn is_inter(&self) -> bool {
+        !self.is_intra()
+    }
+
+    /// True for the switching slice families, `SP` and `SI`, which allow a decoder to switch
+    /// between bitstreams (e.g. of different bitrates) at the switching picture.
+    pub fn is_switching(&self) -> bool {
+        matches!(self, SliceFamily::SP | SliceFamily::SI)
+    }
+}
+impl fmt::Display for SliceFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SliceFamily::P => "P",
+            SliceFamily::B => "B",
+            SliceFamily::I => "I",
+            SliceFamily::SP => "SP",
+            SliceFamily::SI => "SI",
+        })
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceExclusive {
     /// All slices in the picture have the same type
     Exclusive,
     /// Other slices in the picture may have a different type than the current slice
     NonExclusive,
 }
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SliceType {
     family: SliceFamily,
     exclusive: SliceExclusive,
 }
 impl SliceType {
+    pub fn family(&self) -> SliceFamily {
+        self.family
+    }
+
+    pub fn exclusive(&self) -> SliceExclusive {
+        self.exclusive
+    }
+
+    /// True if this slice was signalled with a `slice_type` value in the range `5` to `9`,
+    /// meaning that all other slices of the picture have the same type.
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive == SliceExclusive::Exclusive
+    }
+
+    /// See [`SliceFamily::is_intra`].
+    pub fn is_intra(&self) -> bool {
+        self.family.is_intra()
+    }
+
+    /// See [`SliceFamily::is_inter`].
+    pub fn is_inter(&self) -> bool {
+        self.family.is_inter()
+    }
+
+    /// See [`SliceFamily::is_switching`].
+    pub fn is_switching(&self) -> bool {
+        self.family.is_switching()
+    }
+
     fn from_id(id: u32) -> Result<SliceType, SliceHeaderError> {
         match id {
             0 => Ok(SliceType {
@@ -73,6 +134,25 @@ impl SliceType {
             _ => Err(SliceHeaderError::InvalidSliceType(id)),
         }
     }
+
+    fn id(&self) -> u32 {
+        let id = match self.family {
+            SliceFamily::P => 0,
+            SliceFamily::B => 1,
+            SliceFamily::I => 2,
+            SliceFamily::SP => 3,
+            SliceFamily::SI => 4,
+        };
+        match self.exclusive {
+            SliceExclusive::NonExclusive => id,
+            SliceExclusive::Exclusive => id + 5,
+        }
+    }
+}
+impl fmt::Display for SliceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.family, f)
+    }
 }
 
 #[derive(Debug)]
@@ -86,6 +166,8 @@ pub enum SliceHeaderError {
     InvalidModificationOfPicNumIdc(u32),
     InvalidMemoryManagementControlOperation(u32),
     InvalidSliceQpDelta(i32),
+    /// `cabac_init_idc` was outside the expected range of `0` to `2`.
+    InvalidCabacInitIdc(u32),
     InvalidSliceQsDelta(i32),
     InvalidDisableDeblockingFilterIdc(u32),
     /// `slice_alpha_c0_offset_div2` was outside the expected range of `-6` to `+6`
@@ -95,12 +177,29 @@ pub enum SliceHeaderError {
     InvalidNumRefIdx(&'static str, u32),
     /// The header contained syntax elements that the parser isn't able to handle yet
     UnsupportedSyntax(&'static str),
+    /// The NAL header given to [`SliceHeader::from_nal`] was invalid.
+    InvalidNalHeader(NalHeaderError),
+    /// The NAL given to [`SliceHeader::from_nal`] isn't of a type which starts with a slice header.
+    UnsupportedNalUnitType(UnitType),
+    /// [`SliceHeader::write`] was given a header lacking the named field, which the SPS, PPS or
+    /// NAL header require to be present.
+    MissingField(&'static str),
+    /// [`SliceHeader::write`] was given a header with the named field in a form inconsistent with
+    /// the SPS, PPS or other fields of the header, so it couldn't be read back as given.
+    InconsistentField(&'static str),
+    /// [`SliceHeader::write`] failed to write to the underlying writer.
+    WriteError(std::io::Error),
 }
 impl From<BitReaderError> for SliceHeaderError {
     fn from(e: BitReaderError) -> Self {
         SliceHeaderError::RbspError(e)
     }
 }
+impl From<std::io::Error> for SliceHeaderError {
+    fn from(e: std::io::Error) -> Self {
+        SliceHeaderError::WriteError(e)
+    }
+}
 impl From<pps::PicParamSetIdError> for SliceHeaderError {
     fn from(e: pps::PicParamSetIdError) -> Self {
         SliceHeaderError::InvalidSeqParamSetId(e)
@@ -112,7 +211,7 @@ impl From<ColourPlaneError> for SliceHeaderError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ColourPlane {
     /// Indicates the _chroma_ colour plane
     Y,
@@ -134,9 +233,17 @@ impl ColourPlane {
             _ => Err(ColourPlaneError::InvalidId(id)),
         }
     }
+
+    fn id(&self) -> u8 {
+        match self {
+            ColourPlane::Y => 0,
+            ColourPlane::Cb => 1,
+            ColourPlane::Cr => 2,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Field {
     Top,
     Bottom,
@@ -150,7 +257,13 @@ pub enum FieldPic {
 
 #[derive(Debug, PartialEq)]
 pub enum PicOrderCountLsb {
+    /// `pic_order_cnt_lsb` of a frame, for which `delta_pic_order_cnt_bottom` isn't present.
     Frame(u32),
+    /// `pic_order_cnt_lsb` of a single field, of the given parity.
+    Field {
+        field: Field,
+        pic_order_cnt_lsb: u32,
+    },
     FieldsAbsolute {
         pic_order_cnt_lsb: u32,
         delta_pic_order_cnt_bottom: i32,
@@ -158,7 +271,7 @@ pub enum PicOrderCountLsb {
     FieldsDelta([i32; 2]),
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum NumRefIdxActive {
     P {
         num_ref_idx_l0_active_minus1: u32,
@@ -182,13 +295,24 @@ impl NumRefIdxActive {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ModificationOfPicNums {
     Subtract(u32),
     Add(u32),
     LongTermRef(u32),
 }
-#[derive(Debug)]
+
+/// Identifies a reference picture within a reference picture list, as used by
+/// [`RefPicListModifications::apply_l0`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PicId {
+    /// A short-term reference picture, identified by its `PicNum`.
+    ShortTerm(i32),
+    /// A long-term reference picture, identified by its `LongTermPicNum`.
+    LongTerm(u32),
+}
+
+#[derive(Debug, PartialEq)]
 pub enum RefPicListModifications {
     I,
     P {
@@ -216,6 +340,156 @@ impl RefPicListModifications {
         })
     }
 
+    /// Applies the modifications of reference picture list 0 to its initial list, per Rec. ITU-T
+    /// H.264 (03/2010) section 8.2.4.3.
+    ///
+    /// `initial_list` must contain `num_ref_idx_l0_active_minus1 + 1` entries, as produced by the
+    /// initialisation process of section 8.2.4.2; the result has the same length. `curr_pic_num`
+    /// and `max_pic_num` are `CurrPicNum` and `MaxPicNum` as defined in section 7.4.3.
+    ///
+    /// The list is returned unmodified for I and SI slices, and when the slice has no
+    /// modifications.
+    pub fn apply_l0(
+        &self,
+        initial_list: &[PicId],
+        curr_pic_num: u32,
+        max_pic_num: u32,
+    ) -> Vec<PicId> {
+        let modifications = match self {
+            RefPicListModifications::I => &[][..],
+            RefPicListModifications::P {
+                ref_pic_list_modification_l0,
+            }
+            | RefPicListModifications::B {
+                ref_pic_list_modification_l0,
+                ..
+            } => &ref_pic_list_modification_l0[..],
+        };
+        Self::apply_list(modifications, initial_list, curr_pic_num, max_pic_num)
+    }
+
+    /// Applies the modifications of reference picture list 1 to its initial list. See
+    /// [`RefPicListModifications::apply_l0`].
+    ///
+    /// The list is returned unmodified for slices other than B slices.
+    pub fn apply_l1(
+        &self,
+        initial_list: &[PicId],
+        curr_pic_num: u32,
+        max_pic_num: u32,
+    ) -> Vec<PicId> {
+        let modifications = match self {
+            RefPicListModifications::B {
+                ref_pic_list_modification_l1,
+                ..
+            } => &ref_pic_list_modification_l1[..],
+            _ => &[][..],
+        };
+        Self::apply_list(modifications, initial_list, curr_pic_num, max_pic_num)
+    }
+
+    fn apply_list(
+        modifications: &[ModificationOfPicNums],
+        initial_list: &[PicId],
+        curr_pic_num: u32,
+        max_pic_num: u32,
+    ) -> Vec<PicId> {
+        let curr_pic_num = i64::from(curr_pic_num);
+        let max_pic_num = i64::from(max_pic_num);
+        let mut list = initial_list.to_vec();
+        let mut pic_num_pred = curr_pic_num;
+        for (ref_idx, modification) in modifications.iter().enumerate() {
+            if ref_idx >= initial_list.len() {
+                break;
+            }
+            let pic = match *modification {
+                ModificationOfPicNums::Subtract(abs_diff_pic_num_minus1) => {
+                    let mut no_wrap = pic_num_pred - (i64::from(abs_diff_pic_num_minus1) + 1);
+                    if no_wrap < 0 {
+                        no_wrap += max_pic_num;
+                    }
+                    pic_num_pred = no_wrap;
+                    Self::short_term(no_wrap, curr_pic_num, max_pic_num)
+                }
+                ModificationOfPicNums::Add(abs_diff_pic_num_minus1) => {
+                    let mut no_wrap = pic_num_pred + (i64::from(abs_diff_pic_num_minus1) + 1);
+                    if no_wrap >= max_pic_num {
+                        no_wrap -= max_pic_num;
+                    }
+                    pic_num_pred = no_wrap;
+                    Self::short_term(no_wrap, curr_pic_num, max_pic_num)
+                }
+                ModificationOfPicNums::LongTermRef(long_term_pic_num) => {
+                    PicId::LongTerm(long_term_pic_num)
+                }
+            };
+            // Insert the picture at ref_idx, shifting the others along, then remove its later
+            // occurrence (if any) and truncate to the original length.
+            list.insert(ref_idx, pic);
+            if let Some(dup) = list[ref_idx + 1..].iter().position(|&p| p == pic) {
+                list.remove(ref_idx + 1 + dup);
+            }
+            list.truncate(initial_list.len());
+        }
+        list
+    }
+
+    /// Computes `picNumLX` from `picNumLXNoWrap`.
+    fn short_term(no_wrap: i64, curr_pic_num: i64, max_pic_num: i64) -> PicId {
+        let pic_num = if no_wrap > curr_pic_num {
+            no_wrap - max_pic_num
+        } else {
+            no_wrap
+        };
+        // Conforming streams have `MaxPicNum` of at most 2^17.
+        PicId::ShortTerm(pic_num as i32)
+    }
+
+    fn write<W: BitWrite>(&self, w: &mut W) -> Result<(), SliceHeaderError> {
+        match self {
+            RefPicListModifications::I => {}
+            RefPicListModifications::P {
+                ref_pic_list_modification_l0,
+            } => Self::write_list(w, ref_pic_list_modification_l0)?,
+            RefPicListModifications::B {
+                ref_pic_list_modification_l0,
+                ref_pic_list_modification_l1,
+            } => {
+                Self::write_list(w, ref_pic_list_modification_l0)?;
+                Self::write_list(w, ref_pic_list_modification_l1)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_list<W: BitWrite>(
+        w: &mut W,
+        list: &[ModificationOfPicNums],
+    ) -> Result<(), SliceHeaderError> {
+        w.write_bool(!list.is_empty())?;
+        if list.is_empty() {
+            return Ok(());
+        }
+        for modification in list {
+            match *modification {
+                ModificationOfPicNums::Subtract(abs_diff_pic_num_minus1) => {
+                    w.write_ue(0)?;
+                    w.write_ue(abs_diff_pic_num_minus1)?;
+                }
+                ModificationOfPicNums::Add(abs_diff_pic_num_minus1) => {
+                    w.write_ue(1)?;
+                    w.write_ue(abs_diff_pic_num_minus1)?;
+                }
+                ModificationOfPicNums::LongTermRef(long_term_pic_num) => {
+                    w.write_ue(2)?;
+                    w.write_ue(long_term_pic_num)?;
+                }
+            }
+        }
+        w.write_ue(3)?;
+        Ok(())
+    }
+
     fn read_list<R: BitRead>(r: &mut R) -> Result<Vec<ModificationOfPicNums>, SliceHeaderError> {
         let mut result = vec![];
         // either ref_pic_list_modification_flag_l0 or ref_pic_list_modification_flag_l1 depending
@@ -242,12 +516,12 @@ impl RefPicListModifications {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct PredWeight {
     pub weight: i32,
     pub offset: i32,
 }
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct PredWeightTable {
     pub luma_log2_weight_denom: u32,
     pub chroma_log2_weight_denom: Option<u32>,
@@ -262,13 +536,7 @@ impl PredWeightTable {
         sps: &sps::SeqParameterSet,
         num_ref_active: &Option<NumRefIdxActive>,
     ) -> Result<PredWeightTable, SliceHeaderError> {
-        let chroma_array_type = if sps.chroma_info.separate_colour_plane_flag {
-            // TODO: "Otherwise (separate_colour_plane_flag is equal to 1), ChromaArrayType is
-            //       set equal to 0."  ...does this mean ChromaFormat::Monochrome then?
-            sps::ChromaFormat::Monochrome
-        } else {
-            sps.chroma_info.chroma_format
-        };
+        let chroma_array_type = sps.chroma_array_type();
         let luma_log2_weight_denom = r.read_ue("luma_log2_weight_denom")?;
         let chroma_log2_weight_denom = if chroma_array_type != sps::ChromaFormat::Monochrome {
             Some(r.read_ue("chroma_log2_weight_denom")?)
@@ -313,9 +581,51 @@ impl PredWeightTable {
             chroma_weights,
         })
     }
+
+    fn write<W: BitWrite>(
+        &self,
+        w: &mut W,
+        slice_type: &SliceType,
+        sps: &sps::SeqParameterSet,
+    ) -> Result<(), SliceHeaderError> {
+        if slice_type.family == SliceFamily::B {
+            return Err(SliceHeaderError::UnsupportedSyntax("B frame"));
+        }
+        let has_chroma = sps.chroma_array_type() != sps::ChromaFormat::Monochrome;
+        w.write_ue(self.luma_log2_weight_denom)?;
+        if has_chroma {
+            w.write_ue(*required(
+                &self.chroma_log2_weight_denom,
+                "chroma_log2_weight_denom",
+            )?)?;
+        }
+        for (i, luma_weight) in self.luma_weights.iter().enumerate() {
+            w.write_bool(luma_weight.is_some())?;
+            if let Some(luma_weight) = luma_weight {
+                w.write_se(luma_weight.weight)?;
+                w.write_se(luma_weight.offset)?;
+            }
+            if has_chroma {
+                let chroma_weights = self
+                    .chroma_weights
+                    .get(i)
+                    .ok_or(SliceHeaderError::MissingField("chroma_weight_l0"))?;
+                // chroma_weight_l0_flag covers both the Cb and Cr weights.
+                if !matches!(chroma_weights.len(), 0 | 2) {
+                    return Err(SliceHeaderError::InconsistentField("chroma_weight_l0"));
+                }
+                w.write_bool(!chroma_weights.is_empty())?;
+                for chroma_weight in chroma_weights {
+                    w.write_se(chroma_weight.weight)?;
+                    w.write_se(chroma_weight.offset)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum MemoryManagementControlOperation {
     /// `memory_management_control_operation` value of `1`
     ShortTermUnusedForRef { difference_of_pic_nums_minus1: u32 },
@@ -335,7 +645,7 @@ pub enum MemoryManagementControlOperation {
 }
 
 /// Decoded reference picture marking
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum DecRefPicMarking {
     Idr {
         no_output_of_prior_pics_flag: bool,
@@ -412,9 +722,90 @@ impl DecRefPicMarking {
             },
         )
     }
+
+    fn write<W: BitWrite>(&self, w: &mut W) -> Result<(), SliceHeaderError> {
+        match self {
+            DecRefPicMarking::Idr {
+                no_output_of_prior_pics_flag,
+                long_term_reference_flag,
+            } => {
+                w.write_bool(*no_output_of_prior_pics_flag)?;
+                w.write_bool(*long_term_reference_flag)?;
+            }
+            DecRefPicMarking::SlidingWindow => w.write_bool(false)?,
+            DecRefPicMarking::Adaptive(ctl) => {
+                w.write_bool(true)?;
+                for op in ctl {
+                    match *op {
+                        MemoryManagementControlOperation::ShortTermUnusedForRef {
+                            difference_of_pic_nums_minus1,
+                        } => {
+                            w.write_ue(1)?;
+                            w.write_ue(difference_of_pic_nums_minus1)?;
+                        }
+                        MemoryManagementControlOperation::LongTermUnusedForRef {
+                            long_term_pic_num,
+                        } => {
+                            w.write_ue(2)?;
+                            w.write_ue(long_term_pic_num)?;
+                        }
+                        MemoryManagementControlOperation::ShortTermUsedForLongTerm {
+                            difference_of_pic_nums_minus1,
+                            long_term_frame_idx,
+                        } => {
+                            w.write_ue(3)?;
+                            w.write_ue(difference_of_pic_nums_minus1)?;
+                            w.write_ue(long_term_frame_idx)?;
+                        }
+                        MemoryManagementControlOperation::MaxUsedLongTermFrameRef {
+                            max_long_term_frame_idx_plus1,
+                        } => {
+                            w.write_ue(4)?;
+                            w.write_ue(max_long_term_frame_idx_plus1)?;
+                        }
+                        MemoryManagementControlOperation::AllRefPicturesUnused => w.write_ue(5)?,
+                        MemoryManagementControlOperation::CurrentUsedForLongTerm {
+                            long_term_frame_idx,
+                        } => {
+                            w.write_ue(6)?;
+                            w.write_ue(long_term_frame_idx)?;
+                        }
+                    }
+                }
+                w.write_ue(0)?;
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+/// The meaning of a slice's `disable_deblocking_filter_idc`, along with the filter offsets which
+/// apply when the deblocking filter is enabled. See [`SliceHeader::deblocking_control`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeblockingFilterControl {
+    /// `disable_deblocking_filter_idc` 0: all edges are filtered.
+    Enabled {
+        slice_alpha_c0_offset_div2: i32,
+        slice_beta_offset_div2: i32,
+    },
+    /// `disable_deblocking_filter_idc` 1: the deblocking filter is disabled for this slice.
+    Disabled,
+    /// `disable_deblocking_filter_idc` 2: all edges are filtered except those on the slice
+    /// boundary.
+    EnabledExceptSliceEdges {
+        slice_alpha_c0_offset_div2: i32,
+        slice_beta_offset_div2: i32,
+    },
+    /// `disable_deblocking_filter_idc` 3 to 6, which are only used by SVC (Rec. ITU-T H.264
+    /// Annex G).
+    Extended {
+        disable_deblocking_filter_idc: u8,
+        slice_alpha_c0_offset_div2: i32,
+        slice_beta_offset_div2: i32,
+    },
+}
+
+#[derive(Debug, PartialEq)]
 pub struct SliceHeader {
     pub first_mb_in_slice: u32,
     pub slice_type: SliceType,
@@ -431,11 +822,39 @@ pub struct SliceHeader {
     pub dec_ref_pic_marking: Option<DecRefPicMarking>,
     pub cabac_init_idc: Option<u32>,
     pub slice_qp_delta: i32,
+    /// Present for `SP` slices. If set, the slice is decoded as a switching picture for use when
+    /// switching from another bitstream, rather than as a primary SP picture.
     pub sp_for_switch_flag: Option<bool>,
+    /// `QSY`, the quantization parameter for `SP` and `SI` slices, computed as
+    /// `26 + pic_init_qs_minus26 + slice_qs_delta`. Present iff [`SliceHeader::is_switching`].
     pub slice_qs: Option<u32>,
     pub disable_deblocking_filter_idc: u8,
+    /// `0` if not present in the bitstream.
+    pub slice_alpha_c0_offset_div2: i32,
+    /// `0` if not present in the bitstream.
+    pub slice_beta_offset_div2: i32,
 }
 impl SliceHeader {
+    /// Parses the slice header of the given NAL, which must be a
+    /// `SliceLayerWithoutPartitioningIdr`, `SliceLayerWithoutPartitioningNonIdr` or
+    /// `SliceDataPartitionALayer` NAL.
+    ///
+    /// This is a shortcut for checking the NAL type and calling [`SliceHeader::from_bits`]. Use
+    /// that instead to continue reading the slice data following the header.
+    pub fn from_nal<'a, N: Nal>(
+        ctx: &'a Context,
+        nal: &N,
+    ) -> Result<(SliceHeader, &'a SeqParameterSet, &'a PicParameterSet), SliceHeaderError> {
+        let header = nal.header().map_err(SliceHeaderError::InvalidNalHeader)?;
+        match header.nal_unit_type() {
+            UnitType::SliceLayerWithoutPartitioningIdr
+            | UnitType::SliceLayerWithoutPartitioningNonIdr
+            | UnitType::SliceDataPartitionALayer => {}
+            other => return Err(SliceHeaderError::UnsupportedNalUnitType(other)),
+        }
+        Self::from_bits(ctx, &mut nal.rbsp_bits(), header)
+    }
+
     pub fn from_bits<'a, R: BitRead>(
         ctx: &'a Context,
         r: &mut R,
@@ -458,7 +877,7 @@ impl SliceHeader {
             None
         };
         let frame_num = r.read_u16(u32::from(sps.log2_max_frame_num()), "frame_num")?;
-        let field_pic = if let sps::FrameMbsFlags::Fields { .. } = sps.frame_mbs_flags {
+        let field_pic = if !sps.frame_mbs_flags.frame_mbs_only() {
             if r.read_bool("field_pic_flag")? {
                 if r.read_bool("bottom_field_flag")? {
                     FieldPic::Field(Field::Bottom)
@@ -485,19 +904,20 @@ impl SliceHeader {
                     u32::from(log2_max_pic_order_cnt_lsb_minus4) + 4,
                     "pic_order_cnt_lsb",
                 )?;
-                Some(
-                    if pps.bottom_field_pic_order_in_frame_present_flag
-                        && field_pic == FieldPic::Frame
-                    {
+                Some(match field_pic {
+                    FieldPic::Field(field) => PicOrderCountLsb::Field {
+                        field,
+                        pic_order_cnt_lsb,
+                    },
+                    FieldPic::Frame if pps.bottom_field_pic_order_in_frame_present_flag => {
                         let delta_pic_order_cnt_bottom = r.read_se("delta_pic_order_cnt_bottom")?;
                         PicOrderCountLsb::FieldsAbsolute {
                             pic_order_cnt_lsb,
                             delta_pic_order_cnt_bottom,
                         }
-                    } else {
-                        PicOrderCountLsb::Frame(pic_order_cnt_lsb)
-                    },
-                )
+                    }
+                    FieldPic::Frame => PicOrderCountLsb::Frame(pic_order_cnt_lsb),
+                })
             }
             sps::PicOrderCntType::TypeOne {
                 delta_pic_order_always_zero_flag,
@@ -560,8 +980,8 @@ impl SliceHeader {
         } else {
             RefPicListModifications::read(&slice_type.family, r)?
         };
-        let pred_weight_table = if (pps.weighted_pred_flag && slice_type.family == SliceFamily::P
-            || slice_type.family == SliceFamily::SP)
+        let pred_weight_table = if (pps.weighted_pred_flag
+            && (slice_type.family == SliceFamily::P || slice_type.family == SliceFamily::SP))
             || (pps.weighted_bipred_idc == 1 && slice_type.family == SliceFamily::B)
         {
             Some(PredWeightTable::read(
@@ -583,13 +1003,19 @@ impl SliceHeader {
             && slice_type.family != SliceFamily::I
             && slice_type.family != SliceFamily::SI
         {
-            Some(r.read_ue("cabac_init_idc")?)
+            let cabac_init_idc = r.read_ue("cabac_init_idc")?;
+            if cabac_init_idc > 2 {
+                return Err(SliceHeaderError::InvalidCabacInitIdc(cabac_init_idc));
+            }
+            Some(cabac_init_idc)
         } else {
             None
         };
         let slice_qp_delta = r.read_se("slice_qp_delta")?;
-        if slice_qp_delta > 51 {
-            // TODO: or less than -qp_bd_offset
+        // SliceQPY must be in the range -QpBdOffsetY to 51 inclusive.
+        let qp_bd_offset_y = 6 * i64::from(sps.chroma_info.bit_depth_luma_minus8);
+        let slice_qp_y = 26 + i64::from(pps.pic_init_qp_minus26) + i64::from(slice_qp_delta);
+        if slice_qp_y < -qp_bd_offset_y || 51 < slice_qp_y {
             return Err(SliceHeaderError::InvalidSliceQpDelta(slice_qp_delta));
         }
         let mut sp_for_switch_flag = None;
@@ -608,6 +1034,8 @@ impl SliceHeader {
                 None
             };
         let mut disable_deblocking_filter_idc = 0;
+        let mut slice_alpha_c0_offset_div2 = 0;
+        let mut slice_beta_offset_div2 = 0;
         if pps.deblocking_filter_control_present_flag {
             disable_deblocking_filter_idc = {
                 let v = r.read_ue("disable_deblocking_filter_idc")?;
@@ -617,13 +1045,13 @@ impl SliceHeader {
                 v as u8
             };
             if disable_deblocking_filter_idc != 1 {
-                let slice_alpha_c0_offset_div2 = r.read_se("slice_alpha_c0_offset_div2")?;
+                slice_alpha_c0_offset_div2 = r.read_se("slice_alpha_c0_offset_div2")?;
                 if slice_alpha_c0_offset_div2 < -6 || 6 < slice_alpha_c0_offset_div2 {
                     return Err(SliceHeaderError::InvalidSliceAlphaC0OffsetDiv2(
                         slice_alpha_c0_offset_div2,
                     ));
                 }
-                let _slice_beta_offset_div2 = r.read_se("slice_beta_offset_div2")?;
+                slice_beta_offset_div2 = r.read_se("slice_beta_offset_div2")?;
             }
         }
         if !r.has_more_rbsp_data("slice_header")? {
@@ -654,9 +1082,242 @@ impl SliceHeader {
             sp_for_switch_flag,
             slice_qs,
             disable_deblocking_filter_idc,
+            slice_alpha_c0_offset_div2,
+            slice_beta_offset_div2,
         };
         Ok((header, sps, pps))
     }
+
+    /// Writes the slice header in RBSP form, as the inverse of [`SliceHeader::from_bits`].
+    ///
+    /// `sps`, `pps` and `header` must be those of the slice; as when parsing, they determine
+    /// which syntax elements are present. The PPS's `pic_parameter_set_id` is written. Returns
+    /// [`SliceHeaderError::MissingField`] if a field they require is `None`; fields which they
+    /// don't require are ignored.
+    ///
+    /// The `rbsp_trailing_bits` aren't written, since the slice data would normally follow.
+    /// A reference picture list modification which was signalled with an empty list is written
+    /// with its flag unset.
+    pub fn write<W: BitWrite>(
+        &self,
+        w: &mut W,
+        sps: &SeqParameterSet,
+        pps: &PicParameterSet,
+        header: NalHeader,
+    ) -> Result<(), SliceHeaderError> {
+        let family = self.slice_type.family;
+        w.write_ue(self.first_mb_in_slice)?;
+        w.write_ue(self.slice_type.id())?;
+        w.write_ue(u32::from(pps.pic_parameter_set_id.id()))?;
+        if sps.chroma_info.separate_colour_plane_flag {
+            let colour_plane = required(&self.colour_plane, "colour_plane_id")?;
+            w.write_u8(2, colour_plane.id())?;
+        }
+        w.write_u16(u32::from(sps.log2_max_frame_num()), self.frame_num)?;
+        if !sps.frame_mbs_flags.frame_mbs_only() {
+            match self.field_pic {
+                FieldPic::Frame => w.write_bool(false)?,
+                FieldPic::Field(field) => {
+                    w.write_bool(true)?;
+                    w.write_bool(field == Field::Bottom)?;
+                }
+            }
+        }
+        if header.nal_unit_type() == UnitType::SliceLayerWithoutPartitioningIdr {
+            w.write_ue(*required(&self.idr_pic_id, "idr_pic_id")?)?;
+        }
+        match sps.pic_order_cnt {
+            sps::PicOrderCntType::TypeZero {
+                log2_max_pic_order_cnt_lsb_minus4,
+            } => {
+                let bits = u32::from(log2_max_pic_order_cnt_lsb_minus4) + 4;
+                // The variant must match what the reader would produce given field_pic and the
+                // PPS, since they determine whether delta_pic_order_cnt_bottom is present.
+                let bottom_present = pps.bottom_field_pic_order_in_frame_present_flag;
+                match (
+                    required(&self.pic_order_cnt_lsb, "pic_order_cnt_lsb")?,
+                    &self.field_pic,
+                ) {
+                    (PicOrderCountLsb::Frame(pic_order_cnt_lsb), FieldPic::Frame)
+                        if !bottom_present =>
+                    {
+                        w.write_u32(bits, *pic_order_cnt_lsb)?
+                    }
+                    (
+                        PicOrderCountLsb::Field {
+                            field,
+                            pic_order_cnt_lsb,
+                        },
+                        FieldPic::Field(field_pic),
+                    ) if field == field_pic => w.write_u32(bits, *pic_order_cnt_lsb)?,
+                    (
+                        PicOrderCountLsb::FieldsAbsolute {
+                            pic_order_cnt_lsb,
+                            delta_pic_order_cnt_bottom,
+                        },
+                        FieldPic::Frame,
+                    ) if bottom_present => {
+                        w.write_u32(bits, *pic_order_cnt_lsb)?;
+                        w.write_se(*delta_pic_order_cnt_bottom)?;
+                    }
+                    (PicOrderCountLsb::FieldsDelta(_), _) => {
+                        return Err(SliceHeaderError::MissingField("pic_order_cnt_lsb"))
+                    }
+                    _ => return Err(SliceHeaderError::InconsistentField("pic_order_cnt_lsb")),
+                }
+            }
+            sps::PicOrderCntType::TypeOne {
+                delta_pic_order_always_zero_flag: false,
+                ..
+            } => match self.pic_order_cnt_lsb {
+                Some(PicOrderCountLsb::FieldsDelta([delta0, delta1])) => {
+                    w.write_se(delta0)?;
+                    w.write_se(delta1)?;
+                }
+                _ => return Err(SliceHeaderError::MissingField("delta_pic_order_cnt")),
+            },
+            sps::PicOrderCntType::TypeOne { .. } | sps::PicOrderCntType::TypeTwo => {}
+        }
+        if pps.redundant_pic_cnt_present_flag {
+            w.write_ue(*required(&self.redundant_pic_cnt, "redundant_pic_cnt")?)?;
+        }
+        if family == SliceFamily::B {
+            w.write_bool(*required(
+                &self.direct_spatial_mv_pred_flag,
+                "direct_spatial_mv_pred_flag",
+            )?)?;
+        }
+        if family == SliceFamily::P || family == SliceFamily::SP || family == SliceFamily::B {
+            match self.num_ref_idx_active {
+                None => w.write_bool(false)?,
+                Some(NumRefIdxActive::P {
+                    num_ref_idx_l0_active_minus1,
+                }) if family != SliceFamily::B => {
+                    w.write_bool(true)?;
+                    w.write_ue(num_ref_idx_l0_active_minus1)?;
+                }
+                Some(NumRefIdxActive::B {
+                    num_ref_idx_l0_active_minus1,
+                    num_ref_idx_l1_active_minus1,
+                }) if family == SliceFamily::B => {
+                    w.write_bool(true)?;
+                    w.write_ue(num_ref_idx_l0_active_minus1)?;
+                    w.write_ue(num_ref_idx_l1_active_minus1)?;
+                }
+                Some(_) => return Err(SliceHeaderError::MissingField("num_ref_idx_active")),
+            }
+        }
+        match header.nal_unit_type() {
+            UnitType::SliceExtension | UnitType::SliceExtensionViewComponent => {
+                return Err(SliceHeaderError::UnsupportedSyntax(
+                    "NALU types 20 and 21 not yet supported",
+                ));
+            }
+            _ => match &self.ref_pic_list_modification {
+                Some(m) => m.write(w)?,
+                None if family.is_intra() => {}
+                None => return Err(SliceHeaderError::MissingField("ref_pic_list_modification")),
+            },
+        }
+        if (pps.weighted_pred_flag && (family == SliceFamily::P || family == SliceFamily::SP))
+            || (pps.weighted_bipred_idc == 1 && family == SliceFamily::B)
+        {
+            required(&self.pred_weight_table, "pred_weight_table")?.write(
+                w,
+                &self.slice_type,
+                sps,
+            )?;
+        }
+        if header.nal_ref_idc() != 0 {
+            required(&self.dec_ref_pic_marking, "dec_ref_pic_marking")?.write(w)?;
+        }
+        if pps.entropy_coding_mode_flag && !family.is_intra() {
+            w.write_ue(*required(&self.cabac_init_idc, "cabac_init_idc")?)?;
+        }
+        w.write_se(self.slice_qp_delta)?;
+        if family == SliceFamily::SP || family == SliceFamily::SI {
+            if family == SliceFamily::SP {
+                w.write_bool(*required(&self.sp_for_switch_flag, "sp_for_switch_flag")?)?;
+            }
+            let slice_qs = *required(&self.slice_qs, "slice_qs")?;
+            w.write_se(slice_qs as i32 - 26 - pps.pic_init_qs_minus26)?;
+        }
+        if pps.deblocking_filter_control_present_flag {
+            w.write_ue(u32::from(self.disable_deblocking_filter_idc))?;
+            if self.disable_deblocking_filter_idc != 1 {
+                w.write_se(self.slice_alpha_c0_offset_div2)?;
+                w.write_se(self.slice_beta_offset_div2)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// True if this slice belongs to a redundant coded picture, i.e. `redundant_pic_cnt` is
+    /// present and greater than zero. Decoders may ignore such slices if the corresponding
+    /// primary coded picture was received intact.
+    pub fn is_redundant(&self) -> bool {
+        matches!(self.redundant_pic_cnt, Some(n) if n > 0)
+    }
+
+    /// True if this is an `SP` or `SI` slice; see [`SliceFamily::is_switching`]. Such slices have
+    /// [`SliceHeader::slice_qs`] set, and `SP` slices also [`SliceHeader::sp_for_switch_flag`].
+    pub fn is_switching(&self) -> bool {
+        self.slice_type.is_switching()
+    }
+
+    /// True if this slice belongs to a coded field rather than a coded frame.
+    pub fn is_field(&self) -> bool {
+        self.field().is_some()
+    }
+
+    /// Returns the parity of the coded field this slice belongs to, or `None` for a frame.
+    pub fn field(&self) -> Option<Field> {
+        match self.field_pic {
+            FieldPic::Frame => None,
+            FieldPic::Field(field) => Some(field),
+        }
+    }
+
+    /// True if this slice belongs to a coded bottom field.
+    pub fn is_bottom_field(&self) -> bool {
+        self.field() == Some(Field::Bottom)
+    }
+
+    /// Returns the initial luma quantization parameter of the slice, `SliceQPY`, which is
+    /// `26 + pic_init_qp_minus26 + slice_qp_delta`.
+    ///
+    /// `pps` must be the PPS referenced by this slice, as returned by
+    /// [`SliceHeader::from_bits`], which checks that the result is in the legal range of
+    /// `-QpBdOffsetY` to `51` inclusive.
+    pub fn slice_qp(&self, pps: &PicParameterSet) -> i32 {
+        26 + pps.pic_init_qp_minus26 + self.slice_qp_delta
+    }
+
+    /// Interprets `disable_deblocking_filter_idc` along with the filter offsets.
+    pub fn deblocking_control(&self) -> DeblockingFilterControl {
+        let slice_alpha_c0_offset_div2 = self.slice_alpha_c0_offset_div2;
+        let slice_beta_offset_div2 = self.slice_beta_offset_div2;
+        match self.disable_deblocking_filter_idc {
+            0 => DeblockingFilterControl::Enabled {
+                slice_alpha_c0_offset_div2,
+                slice_beta_offset_div2,
+            },
+            1 => DeblockingFilterControl::Disabled,
+            2 => DeblockingFilterControl::EnabledExceptSliceEdges {
+                slice_alpha_c0_offset_div2,
+                slice_beta_offset_div2,
+            },
+            disable_deblocking_filter_idc => DeblockingFilterControl::Extended {
+                disable_deblocking_filter_idc,
+                slice_alpha_c0_offset_div2,
+                slice_beta_offset_div2,
+            },
+        }
+    }
+}
+
+fn required<'a, T>(field: &'a Option<T>, name: &'static str) -> Result<&'a T, SliceHeaderError> {
+    field.as_ref().ok_or(SliceHeaderError::MissingField(name))
 }
 
 fn read_num_ref_idx<R: BitRead>(r: &mut R, name: &'static str) -> Result<u32, SliceHeaderError> {
@@ -670,9 +1331,273 @@ fn read_num_ref_idx<R: BitRead>(r: &mut R, name: &'static str) -> Result<u32, Sl
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::nal::{Nal, RefNal};
+    use crate::nal::RefNal;
     use hex_literal::hex;
 
+    #[test]
+    fn slice_type() {
+        let t = SliceType::from_id(0).unwrap();
+        assert_eq!(t.family(), SliceFamily::P);
+        assert!(t.is_inter());
+        assert!(!t.is_switching());
+        assert!(!t.is_exclusive());
+        assert_eq!(t.to_string(), "P");
+
+        let t = SliceType::from_id(3).unwrap();
+        assert_eq!(t.family(), SliceFamily::SP);
+        assert!(t.is_inter());
+        assert!(t.is_switching());
+
+        let t = SliceType::from_id(9).unwrap();
+        assert_eq!(t.family(), SliceFamily::SI);
+        assert!(t.is_intra());
+        assert!(t.is_switching());
+        assert!(t.is_exclusive());
+        assert_eq!(t.to_string(), "SI");
+
+        assert!(SliceType::from_id(10).is_err());
+    }
+
+    #[test]
+    fn from_nal() {
+        let mut ctx = crate::Context::default();
+        let sps = RefNal::new(
+            &hex!(
+                "67 64 00 0A AC 72 84 44 26 84 00 00 03
+                00 04 00 00 03 00 CA 3C 48 96 11 80"
+            )[..],
+            &[],
+            true,
+        );
+        ctx.put_seq_param_set(SeqParameterSet::from_bits(sps.rbsp_bits()).unwrap());
+        let pps_nal = RefNal::new(&hex!("68 E8 43 8F 13 21 30")[..], &[], true);
+        ctx.put_pic_param_set(PicParameterSet::from_bits(&ctx, pps_nal.rbsp_bits()).unwrap());
+
+        // An IDR I slice header, followed by a token amount of slice data.
+        let nal = RefNal::new(&hex!("65 88 81 00 2a 80")[..], &[], true);
+        let (header, sps, pps) = SliceHeader::from_nal(&ctx, &nal).unwrap();
+        assert_eq!(header.slice_type.family(), SliceFamily::I);
+        assert_eq!(header.idr_pic_id, Some(0));
+        assert!(!header.is_redundant());
+        assert!(!header.is_switching());
+        assert_eq!(header.slice_qs, None);
+        assert_eq!(sps.id(), pps.seq_parameter_set_id);
+        assert_eq!(
+            header.deblocking_control(),
+            DeblockingFilterControl::Disabled
+        );
+        assert_eq!(header.slice_qp(pps), 23);
+
+        assert!(matches!(
+            SliceHeader::from_nal(&ctx, &pps_nal),
+            Err(SliceHeaderError::UnsupportedNalUnitType(
+                UnitType::PicParameterSet
+            ))
+        ));
+    }
+
+    #[test]
+    fn field_pic_order_cnt_lsb() {
+        let mut ctx = crate::Context::default();
+        let sps = RefNal::new(
+            &hex!(
+                "67 64 00 0A AC 72 84 44 26 84 00 00 03
+                00 04 00 00 03 00 CA 3C 48 96 11 80"
+            )[..],
+            &[],
+            true,
+        );
+        let mut sps = SeqParameterSet::from_bits(sps.rbsp_bits()).unwrap();
+        sps.log2_max_frame_num_minus4 = 0;
+        sps.pic_order_cnt = sps::PicOrderCntType::TypeZero {
+            log2_max_pic_order_cnt_lsb_minus4: 0,
+        };
+        sps.frame_mbs_flags = sps::FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: false,
+        };
+        ctx.put_seq_param_set(sps);
+        let pps_nal = RefNal::new(&hex!("68 E8 43 8F 13 21 30")[..], &[], true);
+        ctx.put_pic_param_set(PicParameterSet::from_bits(&ctx, pps_nal.rbsp_bits()).unwrap());
+
+        // An IDR I slice of the bottom field, with pic_order_cnt_lsb=5,
+        // slice_alpha_c0_offset_div2=-2 and slice_beta_offset_div2=3.
+        let nal = RefNal::new(&hex!("65 88 87 5f 29 bf ff")[..], &[], true);
+        let (header, _, _) =
+            SliceHeader::from_bits(&ctx, &mut nal.rbsp_bits(), nal.header().unwrap()).unwrap();
+        assert_eq!(header.field_pic, FieldPic::Field(Field::Bottom));
+        assert!(header.is_field());
+        assert_eq!(header.field(), Some(Field::Bottom));
+        assert!(header.is_bottom_field());
+        assert_eq!(
+            header.pic_order_cnt_lsb,
+            Some(PicOrderCountLsb::Field {
+                field: Field::Bottom,
+                pic_order_cnt_lsb: 5,
+            })
+        );
+        assert_eq!(
+            header.deblocking_control(),
+            DeblockingFilterControl::Enabled {
+                slice_alpha_c0_offset_div2: -2,
+                slice_beta_offset_div2: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn write() {
+        let mut ctx = crate::Context::default();
+        let sps = RefNal::new(
+            &hex!(
+                "67 64 00 0A AC 72 84 44 26 84 00 00 03
+                00 04 00 00 03 00 CA 3C 48 96 11 80"
+            )[..],
+            &[],
+            true,
+        );
+        let mut sps = SeqParameterSet::from_bits(sps.rbsp_bits()).unwrap();
+        sps.log2_max_frame_num_minus4 = 0;
+        sps.pic_order_cnt = sps::PicOrderCntType::TypeZero {
+            log2_max_pic_order_cnt_lsb_minus4: 0,
+        };
+        sps.frame_mbs_flags = sps::FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: false,
+        };
+        ctx.put_seq_param_set(sps);
+        let pps_nal = RefNal::new(&hex!("68 E8 43 8F 13 21 30")[..], &[], true);
+        let mut pps = PicParameterSet::from_bits(&ctx, pps_nal.rbsp_bits()).unwrap();
+        ctx.put_pic_param_set(pps.clone());
+        pps.pic_parameter_set_id = PicParamSetId::from_u32(1).unwrap();
+        pps.weighted_pred_flag = false;
+        ctx.put_pic_param_set(pps);
+
+        // The slice of the `field_pic_order_cnt_lsb` test.
+        let nal = RefNal::new(&hex!("65 88 87 5f 29 bf ff")[..], &[], true);
+        let nal_header = nal.header().unwrap();
+        let (mut header, sps, pps) =
+            SliceHeader::from_bits(&ctx, &mut nal.rbsp_bits(), nal_header).unwrap();
+
+        // Rewrite frame_num, and check that everything else survives the round trip.
+        header.frame_num = 9;
+        let mut w = crate::rbsp::BitWriter::new(Vec::new());
+        header.write(&mut w, sps, pps, nal_header).unwrap();
+        // A token bit of slice data, which from_bits expects to follow the header.
+        w.write_bool(true).unwrap();
+        w.write_rbsp_trailing_bits().unwrap();
+        let rbsp = w.into_writer();
+        let mut r = crate::rbsp::BitReader::from_rbsp(&rbsp);
+        let (reparsed, _, _) = SliceHeader::from_bits(&ctx, &mut r, nal_header).unwrap();
+        assert!(r.read_bool("slice_data").unwrap());
+        r.finish_rbsp().unwrap();
+        assert_eq!(reparsed, header);
+
+        // An SP slice has a pred_weight_table only if the PPS's weighted_pred_flag is set.
+        let unweighted = ctx.pps_by_id(PicParamSetId::from_u32(1).unwrap()).unwrap();
+        let pred_weight_table = header.pred_weight_table.take();
+        header.slice_type = SliceType::from_id(3).unwrap();
+        header.ref_pic_list_modification = Some(RefPicListModifications::P {
+            ref_pic_list_modification_l0: vec![],
+        });
+        header.cabac_init_idc = Some(1);
+        header.sp_for_switch_flag = Some(true);
+        header.slice_qs = Some(20);
+        let mut w = crate::rbsp::BitWriter::new(Vec::new());
+        header.write(&mut w, sps, unweighted, nal_header).unwrap();
+        w.write_bool(true).unwrap();
+        w.write_rbsp_trailing_bits().unwrap();
+        let rbsp = w.into_writer();
+        let mut r = crate::rbsp::BitReader::from_rbsp(&rbsp);
+        let (reparsed, _, _) = SliceHeader::from_bits(&ctx, &mut r, nal_header).unwrap();
+        assert!(r.read_bool("slice_data").unwrap());
+        r.finish_rbsp().unwrap();
+        assert_eq!(reparsed, header);
+        header.sp_for_switch_flag = None;
+        header.slice_qs = None;
+        header.pred_weight_table = pred_weight_table;
+
+        // The PPS uses CABAC, so a P slice has a cabac_init_idc, which can't be above 2.
+        header.slice_type = SliceType::from_id(0).unwrap();
+        header.ref_pic_list_modification = Some(RefPicListModifications::P {
+            ref_pic_list_modification_l0: vec![],
+        });
+        let num_ref_idx_l0_active = pps.num_ref_idx_l0_default_active_minus1 as usize + 1;
+        header.pred_weight_table = Some(PredWeightTable {
+            luma_log2_weight_denom: 0,
+            chroma_log2_weight_denom: Some(0),
+            luma_weights: (0..num_ref_idx_l0_active).map(|_| None).collect(),
+            chroma_weights: (0..num_ref_idx_l0_active).map(|_| vec![]).collect(),
+        });
+        header.cabac_init_idc = Some(3);
+        let mut w = crate::rbsp::BitWriter::new(Vec::new());
+        header.write(&mut w, sps, pps, nal_header).unwrap();
+        w.write_bool(true).unwrap();
+        w.write_rbsp_trailing_bits().unwrap();
+        let rbsp = w.into_writer();
+        let mut r = crate::rbsp::BitReader::from_rbsp(&rbsp);
+        assert!(matches!(
+            SliceHeader::from_bits(&ctx, &mut r, nal_header),
+            Err(SliceHeaderError::InvalidCabacInitIdc(3))
+        ));
+
+        // A field's pic_order_cnt_lsb can't be written as that of a frame.
+        let pic_order_cnt_lsb = header.pic_order_cnt_lsb.replace(PicOrderCountLsb::Frame(0));
+        let mut w = crate::rbsp::BitWriter::new(Vec::new());
+        assert!(matches!(
+            header.write(&mut w, sps, pps, nal_header),
+            Err(SliceHeaderError::InconsistentField("pic_order_cnt_lsb"))
+        ));
+        header.pic_order_cnt_lsb = pic_order_cnt_lsb;
+
+        // Chroma weights come in Cb, Cr pairs.
+        header.pred_weight_table.as_mut().unwrap().chroma_weights[0] = vec![PredWeight {
+            weight: 1,
+            offset: 0,
+        }];
+        let mut w = crate::rbsp::BitWriter::new(Vec::new());
+        assert!(matches!(
+            header.write(&mut w, sps, pps, nal_header),
+            Err(SliceHeaderError::InconsistentField("chroma_weight_l0"))
+        ));
+
+        header.idr_pic_id = None;
+        let mut w = crate::rbsp::BitWriter::new(Vec::new());
+        assert!(matches!(
+            header.write(&mut w, sps, pps, nal_header),
+            Err(SliceHeaderError::MissingField("idr_pic_id"))
+        ));
+    }
+
+    #[test]
+    fn ref_pic_list_modification() {
+        use PicId::{LongTerm, ShortTerm};
+        let initial = [ShortTerm(4), ShortTerm(3), ShortTerm(2), LongTerm(0)];
+        let modifications = RefPicListModifications::B {
+            ref_pic_list_modification_l0: vec![
+                // 5 - 3 = 2
+                ModificationOfPicNums::Subtract(2),
+                ModificationOfPicNums::LongTermRef(0),
+                // 2 + 1 = 3
+                ModificationOfPicNums::Add(0),
+            ],
+            ref_pic_list_modification_l1: vec![
+                // 1 - 3 wraps to 14, which is greater than CurrPicNum so becomes -2.
+                ModificationOfPicNums::Subtract(2),
+            ],
+        };
+        assert_eq!(
+            modifications.apply_l0(&initial, 5, 16),
+            &[ShortTerm(2), LongTerm(0), ShortTerm(3), ShortTerm(4)]
+        );
+        assert_eq!(
+            modifications.apply_l1(&initial[..2], 1, 16),
+            &[ShortTerm(-2), ShortTerm(4)]
+        );
+        assert_eq!(
+            RefPicListModifications::I.apply_l0(&initial, 5, 16),
+            initial
+        );
+    }
+
     #[test]
     fn invalid_num_ref_idx() {
         // Examples from fuzz testing.
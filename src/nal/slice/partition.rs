@@ -0,0 +1,174 @@
+//! Headers of the slice data partition NAL units (types 2, 3 and 4), which split the slice data
+//! of a single slice into up to three separately-transmitted partitions.
+//!
+//! Partition A carries the slice header and macroblock headers, while partitions B and C carry
+//! residual data.  Partitions of the same slice are associated by their `slice_id`.
+
+use super::{ColourPlane, SliceHeader, SliceHeaderError};
+use crate::nal::pps::PicParameterSet;
+use crate::nal::sps::SeqParameterSet;
+use crate::nal::{Nal, NalHeader, UnitType};
+use crate::rbsp::BitRead;
+use crate::Context;
+
+/// The header of a `SliceDataPartitionALayer` NAL, being a normal slice header followed by
+/// `slice_id`.
+#[derive(Debug, PartialEq)]
+pub struct PartitionAHeader {
+    pub slice_header: SliceHeader,
+    pub slice_id: u32,
+}
+impl PartitionAHeader {
+    /// Parses the header of the given NAL, which must be a `SliceDataPartitionALayer` NAL.
+    ///
+    /// This is a shortcut for checking the NAL type and calling [`PartitionAHeader::from_bits`].
+    pub fn from_nal<'a, N: Nal>(
+        ctx: &'a Context,
+        nal: &N,
+    ) -> Result<(PartitionAHeader, &'a SeqParameterSet, &'a PicParameterSet), SliceHeaderError>
+    {
+        let header = nal.header().map_err(SliceHeaderError::InvalidNalHeader)?;
+        if header.nal_unit_type() != UnitType::SliceDataPartitionALayer {
+            return Err(SliceHeaderError::UnsupportedNalUnitType(
+                header.nal_unit_type(),
+            ));
+        }
+        Self::from_bits(ctx, &mut nal.rbsp_bits(), header)
+    }
+
+    /// Reads the partition A header, leaving `r` positioned at the start of the partitioned
+    /// slice data.
+    pub fn from_bits<'a, R: BitRead>(
+        ctx: &'a Context,
+        r: &mut R,
+        header: NalHeader,
+    ) -> Result<(PartitionAHeader, &'a SeqParameterSet, &'a PicParameterSet), SliceHeaderError>
+    {
+        let (slice_header, sps, pps) = SliceHeader::from_bits(ctx, r, header)?;
+        let slice_id = r.read_ue("slice_id")?;
+        Ok((
+            PartitionAHeader {
+                slice_header,
+                slice_id,
+            },
+            sps,
+            pps,
+        ))
+    }
+}
+
+/// The header of a `SliceDataPartitionBLayer` or `SliceDataPartitionCLayer` NAL.
+///
+/// These headers don't identify a parameter set themselves; the SPS and PPS are those of the
+/// partition A with the same `slice_id`.
+#[derive(Debug, PartialEq)]
+pub struct PartitionBcHeader {
+    pub slice_id: u32,
+    pub colour_plane: Option<ColourPlane>,
+    pub redundant_pic_cnt: Option<u32>,
+}
+impl PartitionBcHeader {
+    /// Parses the header of the given NAL, which must be a `SliceDataPartitionBLayer` or
+    /// `SliceDataPartitionCLayer` NAL.
+    ///
+    /// This is a shortcut for checking the NAL type and calling [`PartitionBcHeader::from_bits`].
+    pub fn from_nal<N: Nal>(
+        nal: &N,
+        sps: &SeqParameterSet,
+        pps: &PicParameterSet,
+    ) -> Result<PartitionBcHeader, SliceHeaderError> {
+        let header = nal.header().map_err(SliceHeaderError::InvalidNalHeader)?;
+        match header.nal_unit_type() {
+            UnitType::SliceDataPartitionBLayer | UnitType::SliceDataPartitionCLayer => {}
+            other => return Err(SliceHeaderError::UnsupportedNalUnitType(other)),
+        }
+        Self::from_bits(&mut nal.rbsp_bits(), sps, pps)
+    }
+
+    /// Reads the partition B or C header, leaving `r` positioned at the start of the
+    /// partitioned residual data.
+    pub fn from_bits<R: BitRead>(
+        r: &mut R,
+        sps: &SeqParameterSet,
+        pps: &PicParameterSet,
+    ) -> Result<PartitionBcHeader, SliceHeaderError> {
+        let slice_id = r.read_ue("slice_id")?;
+        let colour_plane = if sps.chroma_info.separate_colour_plane_flag {
+            Some(ColourPlane::from_id(r.read_u8(2, "colour_plane_id")?)?)
+        } else {
+            None
+        };
+        let redundant_pic_cnt = if pps.redundant_pic_cnt_present_flag {
+            Some(r.read_ue("redundant_pic_cnt")?)
+        } else {
+            None
+        };
+        Ok(PartitionBcHeader {
+            slice_id,
+            colour_plane,
+            redundant_pic_cnt,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nal::slice::DecRefPicMarking;
+    use crate::nal::RefNal;
+    use crate::rbsp::{BitReader, BitWrite, BitWriter};
+    use hex_literal::hex;
+
+    #[test]
+    fn partition_headers() {
+        let mut ctx = Context::default();
+        let sps = RefNal::new(
+            &hex!(
+                "67 64 00 0A AC 72 84 44 26 84 00 00 03
+                00 04 00 00 03 00 CA 3C 48 96 11 80"
+            )[..],
+            &[],
+            true,
+        );
+        ctx.put_seq_param_set(SeqParameterSet::from_bits(sps.rbsp_bits()).unwrap());
+        let pps = RefNal::new(&hex!("68 E8 43 8F 13 21 30")[..], &[], true);
+        ctx.put_pic_param_set(PicParameterSet::from_bits(&ctx, pps.rbsp_bits()).unwrap());
+
+        // Turn an IDR slice's header into that of a (non-IDR) partition A.
+        let nal = RefNal::new(&hex!("65 88 81 00 2a 80")[..], &[], true);
+        let (mut slice_header, sps, pps) = SliceHeader::from_nal(&ctx, &nal).unwrap();
+        slice_header.idr_pic_id = None;
+        slice_header.dec_ref_pic_marking = Some(DecRefPicMarking::SlidingWindow);
+        let nal_header = NalHeader::new(0x62).unwrap();
+        let mut w = BitWriter::new(Vec::new());
+        slice_header.write(&mut w, sps, pps, nal_header).unwrap();
+        w.write_ue(5).unwrap();
+        w.write_bool(true).unwrap();
+        w.write_rbsp_trailing_bits().unwrap();
+        let rbsp = w.into_writer();
+        let mut r = BitReader::from_rbsp(&rbsp);
+        let (a, _, _) = PartitionAHeader::from_bits(&ctx, &mut r, nal_header).unwrap();
+        assert!(r.read_bool("slice_data").unwrap());
+        r.finish_rbsp().unwrap();
+        assert_eq!(a.slice_id, 5);
+        assert_eq!(a.slice_header, slice_header);
+
+        // slice_id 5, then slice data.
+        let nal = RefNal::new(&hex!("63 30")[..], &[], true);
+        let b = PartitionBcHeader::from_nal(&nal, sps, pps).unwrap();
+        assert_eq!(
+            b,
+            PartitionBcHeader {
+                slice_id: 5,
+                colour_plane: None,
+                redundant_pic_cnt: None,
+            }
+        );
+        assert!(matches!(
+            PartitionBcHeader::from_nal(&RefNal::new(&hex!("65 30")[..], &[], true), sps, pps),
+            Err(SliceHeaderError::UnsupportedNalUnitType(
+                UnitType::SliceLayerWithoutPartitioningIdr
+            ))
+        ));
+    }
+}
@@ -1,5 +1,8 @@
-use crate::rbsp::{BitRead, BitReaderError};
+use crate::rbsp::{BitRead, BitReaderError, BitWrite};
+use crate::ParseOptions;
+use std::convert::TryFrom;
 use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug, PartialEq)]
 pub enum SeqParamSetIdError {
@@ -41,6 +44,9 @@ pub enum SpsError {
     CroppingError(FrameCropping),
     /// The `cpb_cnt_minus1` field must be between 0 and 31 inclusive.
     CpbCountOutOfRange(u32),
+    /// The named field had a reserved or invalid value, when parsing with
+    /// [`ParseOptions::strict`].
+    ReservedValue(&'static str, u32),
 }
 
 impl From<BitReaderError> for SpsError {
@@ -49,15 +55,25 @@ impl From<BitReaderError> for SpsError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Profile {
     Unknown(u8),
     Baseline,
+    /// Baseline profile with `constraint_set1_flag` set. Only produced by
+    /// [`Profile::effective`], since it shares `profile_idc` 66 with [`Profile::Baseline`].
+    ConstrainedBaseline,
     Main,
     High,
+    /// High profile with `constraint_set4_flag` set. Only produced by [`Profile::effective`].
+    ProgressiveHigh,
+    /// High profile with `constraint_set4_flag` and `constraint_set5_flag` set. Only produced by
+    /// [`Profile::effective`].
+    ConstrainedHigh,
     High422,
     High10,
     High444,
+    /// CAVLC 4:4:4 Intra profile.
+    Cavlc444Intra,
     Extended,
     ScalableBase,
     ScalableHigh,
@@ -70,7 +86,7 @@ pub enum Profile {
 
 impl Profile {
     pub fn from_profile_idc(profile_idc: ProfileIdc) -> Profile {
-        // TODO: accept constraint_flags too, as Level does?
+        // See `effective` for refinement using constraint_flags, as Level does.
         match profile_idc.0 {
             66 => Profile::Baseline,
             77 => Profile::Main,
@@ -78,6 +94,7 @@ impl Profile {
             122 => Profile::High422,
             110 => Profile::High10,
             244 => Profile::High444,
+            44 => Profile::Cavlc444Intra,
             88 => Profile::Extended,
             83 => Profile::ScalableBase,
             86 => Profile::ScalableHigh,
@@ -89,14 +106,32 @@ impl Profile {
             other => Profile::Unknown(other),
         }
     }
+    /// Refines this profile using the given constraint flags, which may indicate that the
+    /// bitstream conforms to a more restrictive profile sharing the same `profile_idc`.
+    pub fn effective(&self, constraint_flags: ConstraintFlags) -> Profile {
+        match *self {
+            Profile::Baseline if constraint_flags.constrained_baseline() => {
+                Profile::ConstrainedBaseline
+            }
+            Profile::High if constraint_flags.frame_mbs_only() => {
+                if constraint_flags.no_b_slices() {
+                    Profile::ConstrainedHigh
+                } else {
+                    Profile::ProgressiveHigh
+                }
+            }
+            other => other,
+        }
+    }
     pub fn profile_idc(&self) -> u8 {
         match *self {
-            Profile::Baseline => 66,
+            Profile::Baseline | Profile::ConstrainedBaseline => 66,
             Profile::Main => 77,
-            Profile::High => 100,
+            Profile::High | Profile::ProgressiveHigh | Profile::ConstrainedHigh => 100,
             Profile::High422 => 122,
             Profile::High10 => 110,
-            Profile::High444 => 144,
+            Profile::High444 => 244,
+            Profile::Cavlc444Intra => 44,
             Profile::Extended => 88,
             Profile::ScalableBase => 83,
             Profile::ScalableHigh => 86,
@@ -110,7 +145,7 @@ impl Profile {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct ConstraintFlags(u8);
 impl From<u8> for ConstraintFlags {
     fn from(v: u8) -> Self {
@@ -141,6 +176,31 @@ impl ConstraintFlags {
     pub fn flag5(self) -> bool {
         self.0 & 0b0000_0100 != 0
     }
+    /// `constraint_set0_flag`: the bitstream obeys all constraints of the Baseline profile.
+    pub fn baseline_compatible(self) -> bool {
+        self.flag0()
+    }
+    /// `constraint_set1_flag`: the bitstream obeys all constraints of the Main profile.
+    ///
+    /// In combination with a Baseline `profile_idc`, this identifies the Constrained Baseline
+    /// profile.
+    pub fn constrained_baseline(self) -> bool {
+        self.flag1()
+    }
+    /// `constraint_set2_flag`: the bitstream obeys all constraints of the Extended profile.
+    pub fn extended_compatible(self) -> bool {
+        self.flag2()
+    }
+    /// `constraint_set4_flag`: for the Main, Extended and High-family profiles, indicates that
+    /// `frame_mbs_only_flag` is set (i.e. the bitstream is progressive).
+    pub fn frame_mbs_only(self) -> bool {
+        self.flag4()
+    }
+    /// `constraint_set5_flag`: for the Main, Extended and High-family profiles, indicates that
+    /// no B slices are present.
+    pub fn no_b_slices(self) -> bool {
+        self.flag5()
+    }
     pub fn reserved_zero_two_bits(self) -> u8 {
         self.0 & 0b0000_0011
     }
@@ -159,7 +219,15 @@ impl Debug for ConstraintFlags {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// A level, which constrains the stream's bitrate, frame size, decoded picture buffer size and
+/// so on.
+///
+/// Levels are ordered by capability, so that e.g. `sps.level() >= Level::L4` checks that a stream
+/// may require at least the capabilities of level 4. This is the order of `level_idc`, except
+/// that `L1_b` (which shares `level_idc` 11 with `L1_1`, or has `level_idc` 9 in the High-family
+/// profiles) falls between `L1` and `L1_1`. `Unknown`
+/// levels are ordered by their `level_idc`, after any known level with the same value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
 pub enum Level {
     Unknown(u8),
@@ -181,12 +249,25 @@ pub enum Level {
     L5_1,
     L5_2,
 }
+impl PartialOrd for Level {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Level {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.ordering_key().cmp(&other.ordering_key())
+    }
+}
 impl Level {
     pub fn from_constraint_flags_and_level_idc(
         constraint_flags: ConstraintFlags,
         level_idc: u8,
     ) -> Level {
         match level_idc {
+            // Only used by the High-family profiles, which don't signal level 1b with
+            // `constraint_set3_flag`.
+            9 => Level::L1_b,
             10 => Level::L1,
             11 => {
                 if constraint_flags.flag3() {
@@ -212,6 +293,8 @@ impl Level {
             _ => Level::Unknown(level_idc),
         }
     }
+    /// Returns the `level_idc` signalling this level. For `L1_b` this is 11, as used along with
+    /// `constraint_set3_flag` outside the High-family profiles; those profiles use 9 instead.
     pub fn level_idc(&self) -> u8 {
         match *self {
             Level::L1 => 10,
@@ -233,9 +316,38 @@ impl Level {
             Level::Unknown(level_idc) => level_idc,
         }
     }
+
+    /// The key by which levels are ordered.
+    fn ordering_key(&self) -> (u16, bool) {
+        let level_idc = u16::from(self.level_idc()) * 2;
+        match *self {
+            Level::L1_b => (level_idc - 1, false),
+            Level::Unknown(_) => (level_idc, true),
+            _ => (level_idc, false),
+        }
+    }
+
+    /// Returns the maximum decoded picture buffer size `MaxDpbMbs`, in macroblocks, per
+    /// Rec. ITU-T H.264 (03/2010) Table A-1, or `None` for an unknown level.
+    pub fn max_dpb_mbs(&self) -> Option<u32> {
+        match *self {
+            Level::L1 | Level::L1_b => Some(396),
+            Level::L1_1 => Some(900),
+            Level::L1_2 | Level::L1_3 | Level::L2 => Some(2376),
+            Level::L2_1 => Some(4752),
+            Level::L2_2 | Level::L3 => Some(8100),
+            Level::L3_1 => Some(18000),
+            Level::L3_2 => Some(20480),
+            Level::L4 | Level::L4_1 => Some(32768),
+            Level::L4_2 => Some(34816),
+            Level::L5 => Some(110400),
+            Level::L5_1 | Level::L5_2 => Some(184320),
+            Level::Unknown(_) => None,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub enum ChromaFormat {
     Monochrome,
     #[default]
@@ -254,15 +366,51 @@ impl ChromaFormat {
             _ => ChromaFormat::Invalid(chroma_format_idc),
         }
     }
+
+    /// Returns the `chroma_format_idc` value which identifies this format in the bitstream.
+    pub fn chroma_format_idc(&self) -> u32 {
+        match *self {
+            ChromaFormat::Monochrome => 0,
+            ChromaFormat::YUV420 => 1,
+            ChromaFormat::YUV422 => 2,
+            ChromaFormat::YUV444 => 3,
+            ChromaFormat::Invalid(chroma_format_idc) => chroma_format_idc,
+        }
+    }
+
+    /// Returns `SubWidthC`, the horizontal subsampling factor of the chroma arrays, per
+    /// Rec. ITU-T H.264 (03/2010) Table 6-1.
+    ///
+    /// Returns `None` for [`ChromaFormat::Monochrome`], which has no chroma arrays, and for
+    /// [`ChromaFormat::Invalid`].
+    pub fn sub_width_c(&self) -> Option<u32> {
+        match self {
+            ChromaFormat::YUV420 | ChromaFormat::YUV422 => Some(2),
+            ChromaFormat::YUV444 => Some(1),
+            ChromaFormat::Monochrome | ChromaFormat::Invalid(_) => None,
+        }
+    }
+
+    /// Returns `SubHeightC`, the vertical subsampling factor of the chroma arrays. See
+    /// [`ChromaFormat::sub_width_c`].
+    pub fn sub_height_c(&self) -> Option<u32> {
+        match self {
+            ChromaFormat::YUV420 => Some(2),
+            ChromaFormat::YUV422 | ChromaFormat::YUV444 => Some(1),
+            ChromaFormat::Monochrome | ChromaFormat::Invalid(_) => None,
+        }
+    }
 }
 
 // _Profile Indication_ value
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ProfileIdc(u8);
 impl ProfileIdc {
+    /// True if the SPS has `chroma_format_idc` and the other fields that follow it, as for the
+    /// High, scalable, multiview and 3D profiles.
     pub fn has_chroma_info(self) -> bool {
         match self.0 {
-            100 | 110 | 122 | 244 | 44 | 83 | 86 => true,
+            100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135 => true,
             _ => false,
         }
     }
@@ -321,7 +469,7 @@ impl From<BitReaderError> for ScalingMatrixError {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct SeqScalingMatrix {
     // TODO
 }
@@ -349,14 +497,15 @@ impl SeqScalingMatrix {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct ChromaInfo {
     pub chroma_format: ChromaFormat,
     pub separate_colour_plane_flag: bool,
     pub bit_depth_luma_minus8: u8,
     pub bit_depth_chroma_minus8: u8,
     pub qpprime_y_zero_transform_bypass_flag: bool,
-    pub scaling_matrix: SeqScalingMatrix,
+    /// Present if `seq_scaling_matrix_present_flag` is set.
+    pub scaling_matrix: Option<SeqScalingMatrix>,
 }
 impl ChromaInfo {
     pub fn read<R: BitRead>(r: &mut R, profile_idc: ProfileIdc) -> Result<ChromaInfo, SpsError> {
@@ -390,13 +539,32 @@ impl ChromaInfo {
     fn read_scaling_matrix<R: BitRead>(
         r: &mut R,
         chroma_format_idc: u32,
-    ) -> Result<SeqScalingMatrix, SpsError> {
+    ) -> Result<Option<SeqScalingMatrix>, SpsError> {
         let scaling_matrix_present_flag = r.read_bool("scaling_matrix_present_flag")?;
         if scaling_matrix_present_flag {
-            SeqScalingMatrix::read(r, chroma_format_idc).map_err(SpsError::ScalingMatrix)
+            SeqScalingMatrix::read(r, chroma_format_idc)
+                .map(Some)
+                .map_err(SpsError::ScalingMatrix)
         } else {
-            Ok(SeqScalingMatrix::default())
+            Ok(None)
+        }
+    }
+    fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        let chroma_format_idc = self.chroma_format.chroma_format_idc();
+        w.write_ue(chroma_format_idc)?;
+        if chroma_format_idc == 3 {
+            w.write_bool(self.separate_colour_plane_flag)?;
         }
+        w.write_ue(u32::from(self.bit_depth_luma_minus8))?;
+        w.write_ue(u32::from(self.bit_depth_chroma_minus8))?;
+        w.write_bool(self.qpprime_y_zero_transform_bypass_flag)?;
+        if self.scaling_matrix.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "can't write seq_scaling_matrix, as the parser doesn't retain its lists",
+            ));
+        }
+        w.write_bool(false) // seq_scaling_matrix_present_flag
     }
 }
 
@@ -416,7 +584,7 @@ impl From<BitReaderError> for PicOrderCntError {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PicOrderCntType {
     TypeZero {
         log2_max_pic_order_cnt_lsb_minus4: u8,
@@ -450,6 +618,45 @@ impl PicOrderCntType {
         }
     }
 
+    fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        match self {
+            PicOrderCntType::TypeZero {
+                log2_max_pic_order_cnt_lsb_minus4,
+            } => {
+                w.write_ue(0)?;
+                w.write_ue(u32::from(*log2_max_pic_order_cnt_lsb_minus4))
+            }
+            PicOrderCntType::TypeOne {
+                delta_pic_order_always_zero_flag,
+                offset_for_non_ref_pic,
+                offset_for_top_to_bottom_field,
+                offsets_for_ref_frame,
+            } => {
+                w.write_ue(1)?;
+                w.write_bool(*delta_pic_order_always_zero_flag)?;
+                w.write_se(*offset_for_non_ref_pic)?;
+                w.write_se(*offset_for_top_to_bottom_field)?;
+                w.write_ue(offsets_for_ref_frame.len() as u32)?;
+                for &offset in offsets_for_ref_frame {
+                    w.write_se(offset)?;
+                }
+                Ok(())
+            }
+            PicOrderCntType::TypeTwo => w.write_ue(2),
+        }
+    }
+
+    /// Returns `MaxPicOrderCntLsb`, `2 ^ (log2_max_pic_order_cnt_lsb_minus4 + 4)`, for
+    /// `pic_order_cnt_type` 0, or `None` for the other types which have no `pic_order_cnt_lsb`.
+    pub fn max_pic_order_cnt_lsb(&self) -> Option<u32> {
+        match *self {
+            PicOrderCntType::TypeZero {
+                log2_max_pic_order_cnt_lsb_minus4,
+            } => Some(1 << (u32::from(log2_max_pic_order_cnt_lsb_minus4) + 4)),
+            _ => None,
+        }
+    }
+
     fn read_log2_max_pic_order_cnt_lsb_minus4<R: BitRead>(
         r: &mut R,
     ) -> Result<u8, PicOrderCntError> {
@@ -477,7 +684,7 @@ impl PicOrderCntType {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum FrameMbsFlags {
     Frames,
     Fields { mb_adaptive_frame_field_flag: bool },
@@ -493,9 +700,37 @@ impl FrameMbsFlags {
             })
         }
     }
+
+    fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        match *self {
+            FrameMbsFlags::Frames => w.write_bool(true),
+            FrameMbsFlags::Fields {
+                mb_adaptive_frame_field_flag,
+            } => {
+                w.write_bool(false)?;
+                w.write_bool(mb_adaptive_frame_field_flag)
+            }
+        }
+    }
+
+    /// True if `frame_mbs_only_flag` is set, i.e. every coded picture is a frame of frame
+    /// macroblocks.
+    pub fn frame_mbs_only(&self) -> bool {
+        matches!(self, FrameMbsFlags::Frames)
+    }
+
+    /// True if macroblock-adaptive frame/field decoding (MBAFF) may be used within frames.
+    pub fn mbaff_enabled(&self) -> bool {
+        matches!(
+            self,
+            FrameMbsFlags::Fields {
+                mb_adaptive_frame_field_flag: true
+            }
+        )
+    }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct FrameCropping {
     pub left_offset: u32,
     pub right_offset: u32,
@@ -516,9 +751,19 @@ impl FrameCropping {
             None
         })
     }
+    fn write<W: BitWrite>(cropping: &Option<FrameCropping>, w: &mut W) -> std::io::Result<()> {
+        w.write_bool(cropping.is_some())?;
+        if let Some(c) = cropping {
+            w.write_ue(c.left_offset)?;
+            w.write_ue(c.right_offset)?;
+            w.write_ue(c.top_offset)?;
+            w.write_ue(c.bottom_offset)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum AspectRatioInfo {
     #[default]
     Unspecified,
@@ -608,9 +853,34 @@ impl AspectRatioInfo {
             }
         }
     }
+
+    /// Returns the `aspect_ratio_idc` value which identifies this aspect ratio in the bitstream.
+    pub fn idc(&self) -> u8 {
+        match *self {
+            AspectRatioInfo::Unspecified => 0,
+            AspectRatioInfo::Ratio1_1 => 1,
+            AspectRatioInfo::Ratio12_11 => 2,
+            AspectRatioInfo::Ratio10_11 => 3,
+            AspectRatioInfo::Ratio16_11 => 4,
+            AspectRatioInfo::Ratio40_33 => 5,
+            AspectRatioInfo::Ratio24_11 => 6,
+            AspectRatioInfo::Ratio20_11 => 7,
+            AspectRatioInfo::Ratio32_11 => 8,
+            AspectRatioInfo::Ratio80_33 => 9,
+            AspectRatioInfo::Ratio18_11 => 10,
+            AspectRatioInfo::Ratio15_11 => 11,
+            AspectRatioInfo::Ratio64_33 => 12,
+            AspectRatioInfo::Ratio160_99 => 13,
+            AspectRatioInfo::Ratio4_3 => 14,
+            AspectRatioInfo::Ratio3_2 => 15,
+            AspectRatioInfo::Ratio2_1 => 16,
+            AspectRatioInfo::Reserved(idc) => idc,
+            AspectRatioInfo::Extended(..) => 255,
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum OverscanAppropriate {
     #[default]
     Unspecified,
@@ -633,7 +903,7 @@ impl OverscanAppropriate {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum VideoFormat {
     #[default]
     Component,
@@ -653,13 +923,26 @@ impl VideoFormat {
             3 => VideoFormat::SECAM,
             4 => VideoFormat::MAC,
             5 => VideoFormat::Unspecified,
-            6 | 7 => VideoFormat::Reserved(video_format),
-            _ => panic!("unsupported video_format value {}", video_format),
+            // Only 6 and 7 can be read from the 3-bit field.
+            _ => VideoFormat::Reserved(video_format),
+        }
+    }
+
+    /// Returns the `video_format` value which identifies this format in the bitstream.
+    pub fn to_u8(&self) -> u8 {
+        match *self {
+            VideoFormat::Component => 0,
+            VideoFormat::PAL => 1,
+            VideoFormat::NTSC => 2,
+            VideoFormat::SECAM => 3,
+            VideoFormat::MAC => 4,
+            VideoFormat::Unspecified => 5,
+            VideoFormat::Reserved(video_format) => video_format,
         }
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct ColourDescription {
     pub colour_primaries: u8,
     pub transfer_characteristics: u8,
@@ -680,7 +963,7 @@ impl ColourDescription {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct VideoSignalType {
     pub video_format: VideoFormat,
     pub video_full_range_flag: bool,
@@ -701,7 +984,7 @@ impl VideoSignalType {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct ChromaLocInfo {
     pub chroma_sample_loc_type_top_field: u32,
     pub chroma_sample_loc_type_bottom_field: u32,
@@ -721,7 +1004,7 @@ impl ChromaLocInfo {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct TimingInfo {
     pub num_units_in_tick: u32,
     pub time_scale: u32,
@@ -742,7 +1025,7 @@ impl TimingInfo {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct CpbSpec {
     pub bit_rate_value_minus1: u32,
     pub cpb_size_value_minus1: u32,
@@ -758,7 +1041,7 @@ impl CpbSpec {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct HrdParameters {
     pub bit_rate_scale: u8,
     pub cpb_size_scale: u8,
@@ -802,9 +1085,33 @@ impl HrdParameters {
         }
         Ok(cpb_specs)
     }
+    fn write<W: BitWrite>(hrd: &Option<HrdParameters>, w: &mut W) -> std::io::Result<()> {
+        w.write_bool(hrd.is_some())?;
+        if let Some(hrd) = hrd {
+            let cpb_cnt_minus1 = hrd.cpb_specs.len().checked_sub(1).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "HRD parameters must have at least one cpb_spec",
+                )
+            })?;
+            w.write_ue(cpb_cnt_minus1 as u32)?;
+            w.write_u8(4, hrd.bit_rate_scale)?;
+            w.write_u8(4, hrd.cpb_size_scale)?;
+            for spec in &hrd.cpb_specs {
+                w.write_ue(spec.bit_rate_value_minus1)?;
+                w.write_ue(spec.cpb_size_value_minus1)?;
+                w.write_bool(spec.cbr_flag)?;
+            }
+            w.write_u8(5, hrd.initial_cpb_removal_delay_length_minus1)?;
+            w.write_u8(5, hrd.cpb_removal_delay_length_minus1)?;
+            w.write_u8(5, hrd.dpb_output_delay_length_minus1)?;
+            w.write_u8(5, hrd.time_offset_length)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct BitstreamRestrictions {
     pub motion_vectors_over_pic_boundaries_flag: bool,
     pub max_bytes_per_pic_denom: u32,
@@ -834,7 +1141,17 @@ impl BitstreamRestrictions {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Decoded picture buffer requirements of a stream. See [`SeqParameterSet::dpb_sizing`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DpbSizing {
+    /// The maximum number of frames which may precede any frame in decoding order and follow it
+    /// in output order.
+    pub max_num_reorder_frames: u32,
+    /// The required size of the decoded picture buffer, in frames.
+    pub max_dec_frame_buffering: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct VuiParameters {
     pub aspect_ratio_info: Option<AspectRatioInfo>,
     pub overscan_appropriate: OverscanAppropriate,
@@ -872,9 +1189,106 @@ impl VuiParameters {
             None
         })
     }
+
+    fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_bool(self.aspect_ratio_info.is_some())?;
+        if let Some(ref aspect_ratio_info) = self.aspect_ratio_info {
+            w.write_u8(8, aspect_ratio_info.idc())?;
+            if let AspectRatioInfo::Extended(sar_width, sar_height) = *aspect_ratio_info {
+                w.write_u16(16, sar_width)?;
+                w.write_u16(16, sar_height)?;
+            }
+        }
+        match self.overscan_appropriate {
+            OverscanAppropriate::Unspecified => w.write_bool(false)?,
+            OverscanAppropriate::Appropriate => {
+                w.write_bool(true)?;
+                w.write_bool(true)?;
+            }
+            OverscanAppropriate::Inappropriate => {
+                w.write_bool(true)?;
+                w.write_bool(false)?;
+            }
+        }
+        w.write_bool(self.video_signal_type.is_some())?;
+        if let Some(ref video_signal_type) = self.video_signal_type {
+            w.write_u8(3, video_signal_type.video_format.to_u8())?;
+            w.write_bool(video_signal_type.video_full_range_flag)?;
+            w.write_bool(video_signal_type.colour_description.is_some())?;
+            if let Some(ref colour_description) = video_signal_type.colour_description {
+                w.write_u8(8, colour_description.colour_primaries)?;
+                w.write_u8(8, colour_description.transfer_characteristics)?;
+                w.write_u8(8, colour_description.matrix_coefficients)?;
+            }
+        }
+        w.write_bool(self.chroma_loc_info.is_some())?;
+        if let Some(ref chroma_loc_info) = self.chroma_loc_info {
+            w.write_ue(chroma_loc_info.chroma_sample_loc_type_top_field)?;
+            w.write_ue(chroma_loc_info.chroma_sample_loc_type_bottom_field)?;
+        }
+        w.write_bool(self.timing_info.is_some())?;
+        if let Some(ref timing_info) = self.timing_info {
+            w.write_u32(32, timing_info.num_units_in_tick)?;
+            w.write_u32(32, timing_info.time_scale)?;
+            w.write_bool(timing_info.fixed_frame_rate_flag)?;
+        }
+        HrdParameters::write(&self.nal_hrd_parameters, w)?;
+        HrdParameters::write(&self.vcl_hrd_parameters, w)?;
+        if self.has_hrd() {
+            w.write_bool(self.low_delay_hrd_flag.unwrap_or(false))?;
+        }
+        w.write_bool(self.pic_struct_present_flag)?;
+        w.write_bool(self.bitstream_restrictions.is_some())?;
+        if let Some(ref b) = self.bitstream_restrictions {
+            w.write_bool(b.motion_vectors_over_pic_boundaries_flag)?;
+            w.write_ue(b.max_bytes_per_pic_denom)?;
+            w.write_ue(b.max_bits_per_mb_denom)?;
+            w.write_ue(b.log2_max_mv_length_horizontal)?;
+            w.write_ue(b.log2_max_mv_length_vertical)?;
+            w.write_ue(b.max_num_reorder_frames)?;
+            w.write_ue(b.max_dec_frame_buffering)?;
+        }
+        Ok(())
+    }
+
+    /// True if either NAL or VCL HRD parameters are present, in which case `low_delay_hrd_flag`
+    /// is present and `pic_timing` SEI messages include CPB and DPB delays.
+    pub fn has_hrd(&self) -> bool {
+        self.nal_hrd_parameters.is_some() || self.vcl_hrd_parameters.is_some()
+    }
+
+    /// Returns the frame rate as a `(numerator, denominator)` fraction in lowest terms, if
+    /// timing info is present. As with [`SeqParameterSet::fps`], this is
+    /// `time_scale / (2 * num_units_in_tick)`.
+    pub fn frame_rate(&self) -> Option<(u32, u32)> {
+        let timing_info = self.timing_info.as_ref()?;
+        if timing_info.num_units_in_tick == 0 || timing_info.time_scale == 0 {
+            return None;
+        }
+        let num = u64::from(timing_info.time_scale);
+        let den = 2 * u64::from(timing_info.num_units_in_tick);
+        let (mut a, mut b) = (num, den);
+        while b != 0 {
+            let t = a % b;
+            a = b;
+            b = t;
+        }
+        Some((u32::try_from(num / a).ok()?, u32::try_from(den / a).ok()?))
+    }
+}
+
+/// A violation of the spec found by [`SeqParameterSet::check_conformance`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConformanceWarning {
+    /// `direct_8x8_inference_flag` must be set when `frame_mbs_only_flag` isn't.
+    Direct8x8InferenceRequired,
+    /// The profile requires `frame_mbs_only_flag` to be set.
+    FrameMbsOnlyRequiredByProfile(Profile),
+    /// The level requires `frame_mbs_only_flag` to be set.
+    FrameMbsOnlyRequiredByLevel(Level),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SeqParameterSet {
     pub profile_idc: ProfileIdc,
     pub constraint_flags: ConstraintFlags,
@@ -893,35 +1307,270 @@ pub struct SeqParameterSet {
     pub vui_parameters: Option<VuiParameters>,
 }
 impl SeqParameterSet {
-    pub fn from_bits<R: BitRead>(mut r: R) -> Result<SeqParameterSet, SpsError> {
+    pub fn from_bits<R: BitRead>(r: R) -> Result<SeqParameterSet, SpsError> {
+        let (sps, _) = Self::read(r, false)?;
+        Ok(sps)
+    }
+
+    /// Writes this SPS as RBSP, including the `rbsp_trailing_bits`; the inverse of
+    /// [`SeqParameterSet::from_bits`]. Use [`crate::rbsp::encode_nal`] to form a NAL from the
+    /// result.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidInput`] if [`ChromaInfo::scaling_matrix`] is
+    /// set, as the parser doesn't retain the scaling lists needed to write it.
+    pub fn write<W: BitWrite>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_u8(8, self.profile_idc.into())?;
+        w.write_u8(8, self.constraint_flags.into())?;
+        w.write_u8(8, self.level_idc)?;
+        w.write_ue(u32::from(self.seq_parameter_set_id.id()))?;
+        if self.profile_idc.has_chroma_info() {
+            self.chroma_info.write(w)?;
+        }
+        w.write_ue(u32::from(self.log2_max_frame_num_minus4))?;
+        self.pic_order_cnt.write(w)?;
+        w.write_ue(self.max_num_ref_frames)?;
+        w.write_bool(self.gaps_in_frame_num_value_allowed_flag)?;
+        w.write_ue(self.pic_width_in_mbs_minus1)?;
+        w.write_ue(self.pic_height_in_map_units_minus1)?;
+        self.frame_mbs_flags.write(w)?;
+        w.write_bool(self.direct_8x8_inference_flag)?;
+        FrameCropping::write(&self.frame_cropping, w)?;
+        w.write_bool(self.vui_parameters.is_some())?;
+        if let Some(ref vui) = self.vui_parameters {
+            vui.write(w)?;
+        }
+        w.write_rbsp_trailing_bits()
+    }
+
+    /// Like [`SeqParameterSet::from_bits`], but if [`ParseOptions::strict`] is set, also fails
+    /// with [`SpsError::ReservedValue`] on reserved or invalid values which are otherwise accepted.
+    pub fn from_bits_with_options<R: BitRead>(
+        r: R,
+        options: &ParseOptions,
+    ) -> Result<SeqParameterSet, SpsError> {
+        let sps = Self::from_bits(r)?;
+        if options.strict {
+            sps.check_strict()?;
+        }
+        Ok(sps)
+    }
+
+    fn check_strict(&self) -> Result<(), SpsError> {
+        let reserved_zero_2bits = self.constraint_flags.reserved_zero_two_bits();
+        if reserved_zero_2bits != 0 {
+            return Err(SpsError::ReservedValue(
+                "reserved_zero_2bits",
+                u32::from(reserved_zero_2bits),
+            ));
+        }
+        if let ChromaFormat::Invalid(chroma_format_idc) = self.chroma_info.chroma_format {
+            return Err(SpsError::ReservedValue(
+                "chroma_format_idc",
+                chroma_format_idc,
+            ));
+        }
+        if let Some(ref vui) = self.vui_parameters {
+            if let Some(AspectRatioInfo::Reserved(aspect_ratio_idc)) = vui.aspect_ratio_info {
+                return Err(SpsError::ReservedValue(
+                    "aspect_ratio_idc",
+                    u32::from(aspect_ratio_idc),
+                ));
+            }
+            if let Some(VideoSignalType {
+                video_format: VideoFormat::Reserved(video_format),
+                ..
+            }) = vui.video_signal_type
+            {
+                return Err(SpsError::ReservedValue(
+                    "video_format",
+                    u32::from(video_format),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks constraints between fields which the parser doesn't enforce, returning every
+    /// violation found.
+    ///
+    /// An SPS failing these checks is still usable, but the stream doesn't conform to the spec
+    /// (or to the profile and level it claims).
+    pub fn check_conformance(&self) -> Result<(), Vec<ConformanceWarning>> {
+        let mut warnings = vec![];
+        let frame_mbs_only = self.frame_mbs_flags.frame_mbs_only();
+        if !frame_mbs_only && !self.direct_8x8_inference_flag {
+            warnings.push(ConformanceWarning::Direct8x8InferenceRequired);
+        }
+        if !frame_mbs_only {
+            // See Rec. ITU-T H.264 (03/2010) sections A.2 and A.3.
+            let profile = self.refined_profile();
+            match profile {
+                Profile::Baseline
+                | Profile::ConstrainedBaseline
+                | Profile::ProgressiveHigh
+                | Profile::ConstrainedHigh => {
+                    warnings.push(ConformanceWarning::FrameMbsOnlyRequiredByProfile(profile));
+                }
+                Profile::Main
+                | Profile::Extended
+                | Profile::High
+                | Profile::High10
+                | Profile::High422
+                | Profile::High444
+                | Profile::Cavlc444Intra => {
+                    // Table A-4.
+                    let level = self.level();
+                    if matches!(
+                        level,
+                        Level::L1
+                            | Level::L1_b
+                            | Level::L1_1
+                            | Level::L1_2
+                            | Level::L1_3
+                            | Level::L2
+                            | Level::L4_2
+                            | Level::L5
+                            | Level::L5_1
+                            | Level::L5_2
+                    ) {
+                        warnings.push(ConformanceWarning::FrameMbsOnlyRequiredByLevel(level));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(warnings)
+        }
+    }
+
+    /// Like [`SeqParameterSet::from_bits`], but tolerates errors in the VUI parameters.
+    ///
+    /// Errors in the fields preceding the VUI are still returned as `Err`. If the VUI fails to
+    /// parse (or is followed by unexpected data rather than the RBSP trailing bits), the
+    /// returned SPS has `vui_parameters` of `None` and the error is returned alongside it, so
+    /// that the core fields (e.g. [`SeqParameterSet::pixel_dimensions`]) remain usable.
+    pub fn from_bits_partial<R: BitRead>(
+        r: R,
+    ) -> Result<(SeqParameterSet, Option<SpsError>), SpsError> {
+        Self::read(r, true)
+    }
+
+    /// Parses only the fields up to and including `frame_cropping`, which is enough for
+    /// e.g. [`SeqParameterSet::pixel_dimensions`] and [`SeqParameterSet::profile`].
+    ///
+    /// The returned SPS is partial: `vui_parameters` is always `None`, even if VUI parameters
+    /// are present in the bitstream. They are neither read nor validated, and neither is the
+    /// rest of the RBSP, so this may succeed on input which `from_bits` would reject.
+    pub fn from_bits_header_only<R: BitRead>(mut r: R) -> Result<SeqParameterSet, SpsError> {
+        Self::read_header(&mut r)
+    }
+
+    fn read<R: BitRead>(
+        mut r: R,
+        tolerate_vui_errors: bool,
+    ) -> Result<(SeqParameterSet, Option<SpsError>), SpsError> {
+        let mut sps = Self::read_header(&mut r)?;
+        let vui_result = VuiParameters::read(&mut r)
+            .and_then(|vui| r.finish_rbsp().map(|()| vui).map_err(SpsError::from));
+        match vui_result {
+            Ok(vui_parameters) => {
+                sps.vui_parameters = vui_parameters;
+                Ok((sps, None))
+            }
+            Err(e) if tolerate_vui_errors => Ok((sps, Some(e))),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads the fields preceding the VUI parameters.
+    fn read_header<R: BitRead>(r: &mut R) -> Result<SeqParameterSet, SpsError> {
         let profile_idc = r.read_u8(8, "profile_idc")?.into();
-        let sps = SeqParameterSet {
+        Ok(SeqParameterSet {
             profile_idc,
             constraint_flags: r.read_u8(8, "constraint_flags")?.into(),
             level_idc: r.read_u8(8, "level_idc")?,
             seq_parameter_set_id: SeqParamSetId::from_u32(r.read_ue("seq_parameter_set_id")?)
                 .map_err(SpsError::BadSeqParamSetId)?,
-            chroma_info: ChromaInfo::read(&mut r, profile_idc)?,
-            log2_max_frame_num_minus4: Self::read_log2_max_frame_num_minus4(&mut r)?,
-            pic_order_cnt: PicOrderCntType::read(&mut r).map_err(SpsError::PicOrderCnt)?,
+            chroma_info: ChromaInfo::read(r, profile_idc)?,
+            log2_max_frame_num_minus4: Self::read_log2_max_frame_num_minus4(r)?,
+            pic_order_cnt: PicOrderCntType::read(r).map_err(SpsError::PicOrderCnt)?,
             max_num_ref_frames: r.read_ue("max_num_ref_frames")?,
             gaps_in_frame_num_value_allowed_flag: r
                 .read_bool("gaps_in_frame_num_value_allowed_flag")?,
             pic_width_in_mbs_minus1: r.read_ue("pic_width_in_mbs_minus1")?,
             pic_height_in_map_units_minus1: r.read_ue("pic_height_in_map_units_minus1")?,
-            frame_mbs_flags: FrameMbsFlags::read(&mut r)?,
+            frame_mbs_flags: FrameMbsFlags::read(r)?,
             direct_8x8_inference_flag: r.read_bool("direct_8x8_inference_flag")?,
-            frame_cropping: FrameCropping::read(&mut r)?,
-            vui_parameters: VuiParameters::read(&mut r)?,
-        };
-        r.finish_rbsp()?;
-        Ok(sps)
+            frame_cropping: FrameCropping::read(r)?,
+            vui_parameters: None,
+        })
     }
 
     pub fn id(&self) -> SeqParamSetId {
         self.seq_parameter_set_id
     }
 
+    /// Returns a hash of the parsed fields of this SPS, to cheaply detect when the SPS with a
+    /// given id has changed without keeping a copy of the old SPS to compare against.
+    ///
+    /// The contents of the scaling lists aren't retained by the parser, so only whether a
+    /// [`SeqScalingMatrix`] is present contributes to the hash: an SPS which differs only in its
+    /// scaling lists has the same hash.
+    ///
+    /// The value is stable within a given build, but may change across versions of this crate
+    /// (or of Rust), so shouldn't be persisted.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Compares all fields except `vui_parameters`.
+    ///
+    /// Most VUI fields (e.g. timing info or colour description) don't affect the decoding
+    /// process, so this can tell whether a changed SPS requires the decoder to be reinitialized.
+    /// However, the VUI's `bitstream_restrictions` do matter to decoding: their
+    /// `max_dec_frame_buffering` and `max_num_reorder_frames` determine the DPB size and when
+    /// pictures are output (see [`SeqParameterSet::dpb_sizing`]), so callers relying on those
+    /// should also compare them.
+    pub fn eq_ignoring_vui(&self, other: &SeqParameterSet) -> bool {
+        // Destructure so that any new field must be considered here.
+        let SeqParameterSet {
+            profile_idc,
+            constraint_flags,
+            level_idc,
+            seq_parameter_set_id,
+            chroma_info,
+            log2_max_frame_num_minus4,
+            pic_order_cnt,
+            max_num_ref_frames,
+            gaps_in_frame_num_value_allowed_flag,
+            pic_width_in_mbs_minus1,
+            pic_height_in_map_units_minus1,
+            frame_mbs_flags,
+            direct_8x8_inference_flag,
+            frame_cropping,
+            vui_parameters: _,
+        } = self;
+        *profile_idc == other.profile_idc
+            && *constraint_flags == other.constraint_flags
+            && *level_idc == other.level_idc
+            && *seq_parameter_set_id == other.seq_parameter_set_id
+            && *chroma_info == other.chroma_info
+            && *log2_max_frame_num_minus4 == other.log2_max_frame_num_minus4
+            && *pic_order_cnt == other.pic_order_cnt
+            && *max_num_ref_frames == other.max_num_ref_frames
+            && *gaps_in_frame_num_value_allowed_flag == other.gaps_in_frame_num_value_allowed_flag
+            && *pic_width_in_mbs_minus1 == other.pic_width_in_mbs_minus1
+            && *pic_height_in_map_units_minus1 == other.pic_height_in_map_units_minus1
+            && *frame_mbs_flags == other.frame_mbs_flags
+            && *direct_8x8_inference_flag == other.direct_8x8_inference_flag
+            && *frame_cropping == other.frame_cropping
+    }
+
     fn read_log2_max_frame_num_minus4<R: BitRead>(r: &mut R) -> Result<u8, SpsError> {
         let val = r.read_ue("log2_max_frame_num_minus4")?;
         if val > 12 {
@@ -935,14 +1584,133 @@ impl SeqParameterSet {
         Profile::from_profile_idc(self.profile_idc)
     }
 
+    /// Returns the profile as refined by the `constraint_flags`, distinguishing e.g. Constrained
+    /// Baseline from Baseline. See [`Profile::effective`].
+    pub fn refined_profile(&self) -> Profile {
+        self.profile().effective(self.constraint_flags)
+    }
+
+    /// True if the stream conforms to the Constrained Baseline profile.
+    pub fn is_constrained_baseline(&self) -> bool {
+        self.refined_profile() == Profile::ConstrainedBaseline
+    }
+
+    /// True if the three colour components of 4:4:4 video are coded separately, each as a
+    /// monochrome picture. Each slice then has a `colour_plane_id` (see
+    /// [`crate::nal::slice::SliceHeader::colour_plane`]), and `ChromaArrayType` is `0`.
+    pub fn is_separate_colour_plane(&self) -> bool {
+        self.chroma_info.separate_colour_plane_flag
+    }
+
+    /// Returns `ChromaArrayType`, which determines the chroma syntax and decoding processes
+    /// that apply, as the equivalent [`ChromaFormat`].
+    ///
+    /// This is the `chroma_format` of the SPS, except when
+    /// [`SeqParameterSet::is_separate_colour_plane`] is true, in which case `ChromaArrayType` is
+    /// `0` and this returns [`ChromaFormat::Monochrome`].
+    pub fn chroma_array_type(&self) -> ChromaFormat {
+        if self.is_separate_colour_plane() {
+            ChromaFormat::Monochrome
+        } else {
+            self.chroma_info.chroma_format
+        }
+    }
+
     pub fn level(&self) -> Level {
         Level::from_constraint_flags_and_level_idc(self.constraint_flags, self.level_idc)
     }
+    /// Returns the bit depth of luma samples, `BitDepthY`, in the range 8 to 14 inclusive.
+    ///
+    /// This is 8 for profiles which don't signal the bit depth.
+    pub fn bit_depth_luma(&self) -> u8 {
+        self.chroma_info.bit_depth_luma_minus8 + 8
+    }
+
+    /// Returns the bit depth of chroma samples, `BitDepthC`, in the range 8 to 14 inclusive.
+    ///
+    /// This is 8 for profiles which don't signal the bit depth.
+    pub fn bit_depth_chroma(&self) -> u8 {
+        self.chroma_info.bit_depth_chroma_minus8 + 8
+    }
+
     /// returned value will be in the range 4 to 16 inclusive
     pub fn log2_max_frame_num(&self) -> u8 {
         self.log2_max_frame_num_minus4 + 4
     }
 
+    /// Returns the number of "non-existing" frames implied by a gap in `frame_num` between two
+    /// consecutive reference pictures, as in Rec. ITU-T H.264 (03/2010) section 8.2.5.2.
+    ///
+    /// This is `(curr - prev) % MaxFrameNum - 1`, taking into account wrapping of `frame_num`.
+    /// Returns `0` if `curr` immediately follows `prev` or if they're equal (e.g. the second
+    /// field of a complementary field pair).
+    pub fn frame_num_gap(&self, prev: u16, curr: u16) -> u32 {
+        let max_frame_num = 1u32 << self.log2_max_frame_num();
+        let prev = u32::from(prev) % max_frame_num;
+        let curr = u32::from(curr) % max_frame_num;
+        ((curr + max_frame_num - prev) % max_frame_num).saturating_sub(1)
+    }
+
+    /// Converts a macroblock address into `(column, row)` coordinates, in units of macroblocks,
+    /// for pictures which don't use MBAFF (i.e. progressive frames and field pictures).
+    ///
+    /// For field pictures, the row is counted in field macroblock rows.
+    pub fn mb_to_coords(&self, mb_addr: u32) -> (u32, u32) {
+        // A width of 2^32 macroblocks would leave every address in the first row.
+        match self.pic_width_in_mbs_minus1.checked_add(1) {
+            Some(width) => (mb_addr % width, mb_addr / width),
+            None => (mb_addr, 0),
+        }
+    }
+
+    /// Converts a macroblock address within an MBAFF frame into `(column, row)` coordinates, in
+    /// units of macroblocks.
+    ///
+    /// In MBAFF frames, addresses advance through vertically-adjacent macroblock pairs: even
+    /// addresses are the top macroblock of a pair and odd addresses the bottom.
+    pub fn mbaff_mb_to_coords(&self, mb_addr: u32) -> (u32, u32) {
+        let (x, pair_row) = self.mb_to_coords(mb_addr / 2);
+        (x, pair_row.saturating_mul(2) + mb_addr % 2)
+    }
+
+    /// Returns the `(column, row)` coordinates, in units of macroblocks, of the first macroblock
+    /// of a slice with the given `first_mb_in_slice` and `field_pic_flag`.
+    ///
+    /// When MBAFF is in use (`mb_adaptive_frame_field_flag` is set and the slice is part of a
+    /// frame rather than a field), `first_mb_in_slice` is in units of macroblock pairs.
+    pub fn first_mb_in_slice_coords(
+        &self,
+        first_mb_in_slice: u32,
+        field_pic_flag: bool,
+    ) -> (u32, u32) {
+        if self.frame_mbs_flags.mbaff_enabled() && !field_pic_flag {
+            let (x, pair_row) = self.mb_to_coords(first_mb_in_slice);
+            (x, pair_row.saturating_mul(2))
+        } else {
+            self.mb_to_coords(first_mb_in_slice)
+        }
+    }
+
+    /// Returns the coded `(width, height)` of each frame in luma samples, i.e. the size of the
+    /// decoded picture before cropping is applied. See [`SeqParameterSet::pixel_dimensions`] for
+    /// the size after cropping.
+    ///
+    /// Values too large to be represented (which can only come from a corrupt SPS) saturate at
+    /// `u32::MAX`.
+    pub fn coded_dimensions(&self) -> (u32, u32) {
+        let frame_height_in_mbs = if self.frame_mbs_flags.frame_mbs_only() {
+            1
+        } else {
+            2
+        } * (u64::from(self.pic_height_in_map_units_minus1) + 1);
+        let width = (u64::from(self.pic_width_in_mbs_minus1) + 1) * 16;
+        let height = frame_height_in_mbs * 16;
+        (
+            u32::try_from(width).unwrap_or(u32::MAX),
+            u32::try_from(height).unwrap_or(u32::MAX),
+        )
+    }
+
     /// Helper to calculate the pixel-dimensions of the video image specified by this SPS, taking
     /// into account sample-format, interlacing and cropping.
     pub fn pixel_dimensions(&self) -> Result<(u32, u32), SpsError> {
@@ -954,28 +1722,20 @@ impl SeqParameterSet {
                 name: "pic_width_in_mbs_minus1",
                 value: self.pic_width_in_mbs_minus1,
             })?;
-        let mul = match self.frame_mbs_flags {
-            FrameMbsFlags::Fields { .. } => 2,
-            FrameMbsFlags::Frames => 1,
-        };
-        let vsub = if self.chroma_info.chroma_format == ChromaFormat::YUV420 {
+        let mul = if self.frame_mbs_flags.frame_mbs_only() {
             1
         } else {
-            0
+            2
         };
-        let hsub = if self.chroma_info.chroma_format == ChromaFormat::YUV420
-            || self.chroma_info.chroma_format == ChromaFormat::YUV422
-        {
-            1
-        } else {
-            0
-        };
-
-        let step_x = 1 << hsub;
-        let step_y = mul << vsub;
+        // CropUnitX and CropUnitY, which don't depend on subsampling when ChromaArrayType is 0.
+        let chroma_array_type = self.chroma_array_type();
+        let step_x = chroma_array_type.sub_width_c().unwrap_or(1);
+        let step_y = mul * chroma_array_type.sub_height_c().unwrap_or(1);
 
-        let height = (self.pic_height_in_map_units_minus1 + 1)
-            .checked_mul(mul * 16)
+        let height = self
+            .pic_height_in_map_units_minus1
+            .checked_add(1)
+            .and_then(|h| h.checked_mul(mul * 16))
             .ok_or_else(|| SpsError::FieldValueTooLarge {
                 name: "pic_height_in_map_units_minus1",
                 value: self.pic_height_in_map_units_minus1,
@@ -1021,10 +1781,142 @@ impl SeqParameterSet {
         }
     }
 
+    /// Returns the decoded picture buffer requirements of the stream.
+    ///
+    /// These are taken from the VUI bitstream restrictions if present, and otherwise inferred as
+    /// described in Rec. ITU-T H.264 (03/2010) section E.2.1: `0` for intra-only profiles, and
+    /// otherwise `MaxDpbFrames` as derived from the level's `MaxDpbMbs` limit. If the level is
+    /// unknown, `MaxDpbFrames` is taken to be its upper bound of `16`.
+    pub fn dpb_sizing(&self) -> DpbSizing {
+        if let Some(restrictions) = self
+            .vui_parameters
+            .as_ref()
+            .and_then(|vui| vui.bitstream_restrictions.as_ref())
+        {
+            return DpbSizing {
+                max_num_reorder_frames: restrictions.max_num_reorder_frames,
+                max_dec_frame_buffering: restrictions.max_dec_frame_buffering,
+            };
+        }
+        let intra_only = matches!(self.profile_idc.0, 44 | 86 | 100 | 110 | 122 | 244)
+            && self.constraint_flags.flag3();
+        let max_dpb_frames = if intra_only {
+            0
+        } else {
+            // A frame too large to compute the size of certainly doesn't fit in the DPB.
+            self.max_dpb_frames().unwrap_or(0)
+        };
+        DpbSizing {
+            max_num_reorder_frames: max_dpb_frames,
+            max_dec_frame_buffering: max_dpb_frames,
+        }
+    }
+
+    /// Returns the VUI parameters, with the values of any absent fields inferred as described in
+    /// Rec. ITU-T H.264 (06/2019) section E.2.1. This is a fully-defaulted `VuiParameters` if
+    /// the SPS has none.
+    ///
+    /// The inferred values are:
+    ///
+    /// *   `aspect_ratio_idc`: `0`, i.e. [`AspectRatioInfo::Unspecified`].
+    /// *   `video_format`: `5`, i.e. [`VideoFormat::Unspecified`]; `video_full_range_flag`:
+    ///     `false`.
+    /// *   `colour_primaries`, `transfer_characteristics` and `matrix_coefficients`: `2`
+    ///     (unspecified).
+    /// *   `chroma_sample_loc_type_top_field` and `chroma_sample_loc_type_bottom_field`: `0`.
+    /// *   `motion_vectors_over_pic_boundaries_flag`: `true`; `max_bytes_per_pic_denom`: `2`;
+    ///     `max_bits_per_mb_denom`: `1`; `log2_max_mv_length_horizontal` and
+    ///     `log2_max_mv_length_vertical`: `16`; `max_num_reorder_frames` and
+    ///     `max_dec_frame_buffering`: as in [`SeqParameterSet::dpb_sizing`].
+    ///
+    /// The timing info and HRD parameters have no inferred values, so remain `None` if absent.
+    pub fn vui_or_inferred(&self) -> VuiParameters {
+        let mut vui = self.vui_parameters.clone().unwrap_or_default();
+        vui.aspect_ratio_info
+            .get_or_insert(AspectRatioInfo::Unspecified);
+        let video_signal_type = vui.video_signal_type.get_or_insert(VideoSignalType {
+            video_format: VideoFormat::Unspecified,
+            video_full_range_flag: false,
+            colour_description: None,
+        });
+        video_signal_type
+            .colour_description
+            .get_or_insert(ColourDescription {
+                colour_primaries: 2,
+                transfer_characteristics: 2,
+                matrix_coefficients: 2,
+            });
+        vui.chroma_loc_info.get_or_insert(ChromaLocInfo {
+            chroma_sample_loc_type_top_field: 0,
+            chroma_sample_loc_type_bottom_field: 0,
+        });
+        if vui.bitstream_restrictions.is_none() {
+            let dpb_sizing = self.dpb_sizing();
+            vui.bitstream_restrictions = Some(BitstreamRestrictions {
+                motion_vectors_over_pic_boundaries_flag: true,
+                max_bytes_per_pic_denom: 2,
+                max_bits_per_mb_denom: 1,
+                log2_max_mv_length_horizontal: 16,
+                log2_max_mv_length_vertical: 16,
+                max_num_reorder_frames: dpb_sizing.max_num_reorder_frames,
+                max_dec_frame_buffering: dpb_sizing.max_dec_frame_buffering,
+            });
+        }
+        vui
+    }
+
+    /// `MaxDpbFrames` as defined in Rec. ITU-T H.264 (03/2010) section A.3.1, or `None` if the
+    /// frame size overflows.
+    fn max_dpb_frames(&self) -> Option<u32> {
+        let Some(max_dpb_mbs) = self.level().max_dpb_mbs() else {
+            return Some(16);
+        };
+        let frame_height_in_mbs = if self.frame_mbs_flags.frame_mbs_only() {
+            1
+        } else {
+            2
+        } * (u64::from(self.pic_height_in_map_units_minus1) + 1);
+        let frame_size_in_mbs =
+            (u64::from(self.pic_width_in_mbs_minus1) + 1).checked_mul(frame_height_in_mbs)?;
+        Some(std::cmp::min(u64::from(max_dpb_mbs) / frame_size_in_mbs, 16) as u32)
+    }
+
+    /// Returns the sample aspect ratio as `(width, height)`, if specified in the VUI parameters.
+    /// See [`AspectRatioInfo::get`].
+    pub fn sample_aspect_ratio(&self) -> Option<(u16, u16)> {
+        self.vui_parameters
+            .as_ref()?
+            .aspect_ratio_info
+            .as_ref()?
+            .get()
+    }
+
+    /// Returns the intended display size of the image, i.e. [`SeqParameterSet::pixel_dimensions`]
+    /// with the width scaled by the [`SeqParameterSet::sample_aspect_ratio`] (and rounded to the
+    /// nearest pixel). If no sample aspect ratio is specified, square samples are assumed.
+    pub fn display_dimensions(&self) -> Result<(u32, u32), SpsError> {
+        let (width, height) = self.pixel_dimensions()?;
+        let Some((sar_width, sar_height)) = self.sample_aspect_ratio() else {
+            return Ok((width, height));
+        };
+        let sar_width = u64::from(sar_width);
+        let sar_height = u64::from(sar_height);
+        let display_width = (u64::from(width) * sar_width + sar_height / 2) / sar_height;
+        let display_width =
+            u32::try_from(display_width).map_err(|_| SpsError::FieldValueTooLarge {
+                name: "sar_width",
+                value: sar_width as u32,
+            })?;
+        Ok((display_width, height))
+    }
+
     pub fn rfc6381(&self) -> rfc6381_codec::Codec {
         rfc6381_codec::Codec::avc1(self.profile_idc.0, self.constraint_flags.0, self.level_idc)
     }
 
+    /// Returns the frame rate, `time_scale / (2 * num_units_in_tick)`, if timing info is present.
+    ///
+    /// Returns `None` if `num_units_in_tick` or `time_scale` is zero, which the spec forbids.
     pub fn fps(&self) -> Option<f64> {
         let Some(vui) = &self.vui_parameters else {
             return None;
@@ -1032,9 +1924,223 @@ impl SeqParameterSet {
         let Some(timing_info) = &vui.timing_info else {
             return None;
         };
+        if timing_info.num_units_in_tick == 0 || timing_info.time_scale == 0 {
+            return None;
+        }
 
         Some((timing_info.time_scale as f64) / (2.0 * (timing_info.num_units_in_tick as f64)))
     }
+
+    /// Collects the properties of the stream most often wanted for display or logging.
+    pub fn summary(&self) -> SpsSummary {
+        SpsSummary {
+            pixel_dimensions: self.pixel_dimensions().ok(),
+            coded_dimensions: self.coded_dimensions(),
+            fps: self.fps(),
+            profile: self.refined_profile(),
+            level: self.level(),
+            chroma_format: self.chroma_info.chroma_format,
+            bit_depth_luma: self.bit_depth_luma(),
+            bit_depth_chroma: self.bit_depth_chroma(),
+        }
+    }
+
+    /// Collects which optional coding features the SPS uses, e.g. so that a decoder can quickly
+    /// check whether it supports the stream.
+    pub fn feature_flags(&self) -> SpsFeatures {
+        SpsFeatures {
+            scaling_matrix: self.chroma_info.scaling_matrix.is_some(),
+            separate_colour_planes: self.chroma_info.separate_colour_plane_flag,
+            field_coding: !self.frame_mbs_flags.frame_mbs_only(),
+            mbaff: self.frame_mbs_flags.mbaff_enabled(),
+            non_420_chroma: self.chroma_info.chroma_format != ChromaFormat::YUV420,
+            high_bit_depth: self.bit_depth_luma() > 8 || self.bit_depth_chroma() > 8,
+            transform_bypass: self.chroma_info.qpprime_y_zero_transform_bypass_flag,
+            hrd: matches!(&self.vui_parameters, Some(vui) if vui.has_hrd()),
+        }
+    }
+}
+
+/// Which optional coding features a [`SeqParameterSet`] uses, as returned by
+/// [`SeqParameterSet::feature_flags`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpsFeatures {
+    /// The SPS has its own scaling matrix, rather than using the flat default.
+    pub scaling_matrix: bool,
+    /// The colour planes of 4:4:4 video are coded separately.
+    pub separate_colour_planes: bool,
+    /// Pictures may be coded as fields, i.e. `frame_mbs_only_flag` isn't set.
+    pub field_coding: bool,
+    /// Frames may use macroblock-adaptive frame/field coding.
+    pub mbaff: bool,
+    /// The chroma format is monochrome, 4:2:2 or 4:4:4 rather than 4:2:0.
+    pub non_420_chroma: bool,
+    /// Luma or chroma samples have more than 8 bits.
+    pub high_bit_depth: bool,
+    /// `qpprime_y_zero_transform_bypass_flag` is set, allowing lossless coding.
+    pub transform_bypass: bool,
+    /// The VUI has NAL or VCL HRD parameters. See [`VuiParameters::has_hrd`].
+    pub hrd: bool,
+}
+
+/// An overview of a [`SeqParameterSet`], as returned by [`SeqParameterSet::summary`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpsSummary {
+    /// See [`SeqParameterSet::pixel_dimensions`]; `None` if the cropping is invalid.
+    pub pixel_dimensions: Option<(u32, u32)>,
+    /// See [`SeqParameterSet::coded_dimensions`].
+    pub coded_dimensions: (u32, u32),
+    /// See [`SeqParameterSet::fps`].
+    pub fps: Option<f64>,
+    /// See [`SeqParameterSet::refined_profile`].
+    pub profile: Profile,
+    pub level: Level,
+    pub chroma_format: ChromaFormat,
+    pub bit_depth_luma: u8,
+    pub bit_depth_chroma: u8,
+}
+
+/// Builds a [`SeqParameterSet`] from a few commonly-varied properties, for generating streams
+/// and in tests.
+///
+/// Defaults to a Baseline profile, level 3, 4:2:0, progressive 16x16 SPS with no VUI.
+///
+/// ```
+/// use h264_reader::nal::sps::{Level, Profile, SeqParameterSetBuilder};
+///
+/// let sps = SeqParameterSetBuilder::default()
+///     .profile(Profile::High)
+///     .level(Level::L4)
+///     .dimensions(1920, 1080)
+///     .build();
+/// assert_eq!(sps.pixel_dimensions().unwrap(), (1920, 1080));
+/// assert_eq!(sps.coded_dimensions(), (1920, 1088));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SeqParameterSetBuilder {
+    sps: SeqParameterSet,
+    level: Level,
+    width: u32,
+    height: u32,
+}
+impl Default for SeqParameterSetBuilder {
+    fn default() -> Self {
+        SeqParameterSetBuilder {
+            sps: SeqParameterSet {
+                profile_idc: ProfileIdc(Profile::Baseline.profile_idc()),
+                constraint_flags: ConstraintFlags(0),
+                level_idc: Level::L3.level_idc(),
+                seq_parameter_set_id: SeqParamSetId(0),
+                chroma_info: ChromaInfo::default(),
+                log2_max_frame_num_minus4: 0,
+                pic_order_cnt: PicOrderCntType::TypeTwo,
+                max_num_ref_frames: 1,
+                gaps_in_frame_num_value_allowed_flag: false,
+                pic_width_in_mbs_minus1: 0,
+                pic_height_in_map_units_minus1: 0,
+                frame_mbs_flags: FrameMbsFlags::Frames,
+                direct_8x8_inference_flag: true,
+                frame_cropping: None,
+                vui_parameters: None,
+            },
+            level: Level::L3,
+            width: 16,
+            height: 16,
+        }
+    }
+}
+impl SeqParameterSetBuilder {
+    /// Sets the displayed picture size in pixels. The coded size is rounded up to whole
+    /// macroblocks (or macroblock pairs, for interlaced streams), and frame cropping signalled
+    /// for the difference.
+    ///
+    /// A size which isn't a multiple of the cropping unit (e.g. an odd width in 4:2:0) is
+    /// rounded up to one which is.
+    pub fn dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets `profile_idc`, along with any constraint flags implied by `profile` (such as
+    /// `constraint_set1_flag` for [`Profile::ConstrainedBaseline`]).
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.sps.profile_idc = ProfileIdc(profile.profile_idc());
+        let flags = match profile {
+            Profile::ConstrainedBaseline => 0b0100_0000,
+            Profile::ProgressiveHigh => 0b0000_1000,
+            Profile::ConstrainedHigh => 0b0000_1100,
+            _ => 0,
+        };
+        self.sps.constraint_flags = ConstraintFlags(flags);
+        self
+    }
+
+    /// Sets the level. For [`Level::L1_b`], `level_idc` and `constraint_set3_flag` depend on the
+    /// profile, so are only resolved by [`SeqParameterSetBuilder::build`].
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn frame_mbs_flags(mut self, frame_mbs_flags: FrameMbsFlags) -> Self {
+        self.sps.frame_mbs_flags = frame_mbs_flags;
+        self
+    }
+
+    pub fn build(self) -> SeqParameterSet {
+        let mut sps = self.sps;
+        sps.level_idc = self.level.level_idc();
+        if self.level == Level::L1_b {
+            // The High-family profiles signal level 1b with its own `level_idc`, as they give
+            // `constraint_set3_flag` another meaning ("Intra"). Others use level 1.1's
+            // `level_idc` along with `constraint_set3_flag`.
+            if matches!(sps.profile_idc.0, 100 | 110 | 122 | 244 | 44) {
+                sps.level_idc = 9;
+            } else {
+                sps.constraint_flags = ConstraintFlags(sps.constraint_flags.0 | 0b0001_0000);
+            }
+        }
+        let map_unit_height = if sps.frame_mbs_flags.frame_mbs_only() {
+            16
+        } else {
+            32
+        };
+        let width_in_mbs = self.width.div_ceil(16).max(1);
+        let height_in_map_units = self.height.div_ceil(map_unit_height).max(1);
+        sps.pic_width_in_mbs_minus1 = width_in_mbs - 1;
+        sps.pic_height_in_map_units_minus1 = height_in_map_units - 1;
+
+        let (crop_unit_x, crop_unit_y) = {
+            let chroma_array_type = sps.chroma_array_type();
+            (
+                chroma_array_type.sub_width_c().unwrap_or(1),
+                chroma_array_type.sub_height_c().unwrap_or(1) * (map_unit_height / 16),
+            )
+        };
+        // The rounded-up coded size may not fit in a u32 for sizes close to u32::MAX, but the
+        // offsets are always less than a macroblock pair.
+        let crop = |coded: u64, size: u32, unit: u32| {
+            ((coded - u64::from(size.max(1))) / u64::from(unit)) as u32
+        };
+        let right_offset = crop(u64::from(width_in_mbs) * 16, self.width, crop_unit_x);
+        let bottom_offset = crop(
+            u64::from(height_in_map_units) * u64::from(map_unit_height),
+            self.height,
+            crop_unit_y,
+        );
+        sps.frame_cropping = if right_offset == 0 && bottom_offset == 0 {
+            None
+        } else {
+            Some(FrameCropping {
+                left_offset: 0,
+                right_offset,
+                top_offset: 0,
+                bottom_offset,
+            })
+        };
+        sps
+    }
 }
 
 #[cfg(test)]
@@ -1056,9 +2162,576 @@ mod test {
         assert_eq!(100, sps.profile_idc.0);
         assert_eq!(0, sps.constraint_flags.reserved_zero_two_bits());
         assert_eq!((64, 64), sps.pixel_dimensions().unwrap());
+        assert_eq!(8, sps.bit_depth_luma());
+        assert_eq!(8, sps.bit_depth_chroma());
         assert!(!sps.rfc6381().to_string().is_empty())
     }
 
+    #[test]
+    fn strict() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let sps = SeqParameterSet::from_bits_with_options(
+            rbsp::BitReader::new(&data[..]),
+            &ParseOptions::strict(),
+        )
+        .unwrap();
+        assert!(sps.check_strict().is_ok());
+
+        // Set reserved_zero_2bits.
+        let mut data = data;
+        data[1] = 0x01;
+        assert!(SeqParameterSet::from_bits_with_options(
+            rbsp::BitReader::new(&data[..]),
+            &ParseOptions::default(),
+        )
+        .is_ok());
+        assert!(matches!(
+            SeqParameterSet::from_bits_with_options(
+                rbsp::BitReader::new(&data[..]),
+                &ParseOptions::strict(),
+            ),
+            Err(SpsError::ReservedValue("reserved_zero_2bits", 1))
+        ));
+
+        let mut sps = sps;
+        sps.vui_parameters.as_mut().unwrap().aspect_ratio_info =
+            Some(AspectRatioInfo::Reserved(17));
+        assert!(matches!(
+            sps.check_strict(),
+            Err(SpsError::ReservedValue("aspect_ratio_idc", 17))
+        ));
+    }
+
+    #[test]
+    fn dpb_sizing() {
+        // Level 1.0, 64x64 pixels (16 macroblocks), with bitstream restrictions.
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let mut sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        let original = sps.clone();
+        let restrictions = sps
+            .vui_parameters
+            .as_mut()
+            .unwrap()
+            .bitstream_restrictions
+            .take()
+            .unwrap();
+        assert_eq!(
+            original.dpb_sizing(),
+            DpbSizing {
+                max_num_reorder_frames: restrictions.max_num_reorder_frames,
+                max_dec_frame_buffering: restrictions.max_dec_frame_buffering,
+            }
+        );
+        // Without bitstream restrictions, the values are inferred from the level's limit;
+        // 396 / 16 = 24, capped at 16.
+        assert_eq!(
+            sps.dpb_sizing(),
+            DpbSizing {
+                max_num_reorder_frames: 16,
+                max_dec_frame_buffering: 16,
+            }
+        );
+
+        // At 320x240 (300 macroblocks), only one frame fits.
+        sps.pic_width_in_mbs_minus1 = 19;
+        sps.pic_height_in_map_units_minus1 = 14;
+        assert_eq!(sps.dpb_sizing().max_dec_frame_buffering, 1);
+
+        // Extreme dimensions overflow the frame size, but don't panic.
+        let mut huge = sps.clone();
+        huge.pic_width_in_mbs_minus1 = u32::MAX;
+        huge.pic_height_in_map_units_minus1 = u32::MAX;
+        huge.frame_mbs_flags = FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: false,
+        };
+        assert_eq!(huge.dpb_sizing().max_dec_frame_buffering, 0);
+
+        // High 10 Intra.
+        sps.profile_idc = ProfileIdc::from(110);
+        sps.constraint_flags = ConstraintFlags::from(0b0001_0000);
+        assert_eq!(
+            sps.dpb_sizing(),
+            DpbSizing {
+                max_num_reorder_frames: 0,
+                max_dec_frame_buffering: 0,
+            }
+        );
+
+        // Explicit values take precedence.
+        sps.vui_parameters.as_mut().unwrap().bitstream_restrictions = Some(BitstreamRestrictions {
+            max_num_reorder_frames: 2,
+            max_dec_frame_buffering: 4,
+            ..Default::default()
+        });
+        assert_eq!(
+            sps.dpb_sizing(),
+            DpbSizing {
+                max_num_reorder_frames: 2,
+                max_dec_frame_buffering: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn vui_helpers() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let mut sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        assert_eq!(Some(25.0), sps.fps());
+        let timing_info = sps.vui_parameters.as_mut().unwrap().timing_info.as_mut();
+        timing_info.unwrap().num_units_in_tick = 0;
+        assert_eq!(None, sps.fps());
+        let mut vui = sps.vui_parameters.unwrap();
+        vui.timing_info.as_mut().unwrap().num_units_in_tick = 1;
+        assert!(!vui.has_hrd());
+        // num_units_in_tick=1, time_scale=50.
+        assert_eq!(Some((25, 1)), vui.frame_rate());
+
+        vui.vcl_hrd_parameters = Some(HrdParameters::default());
+        assert!(vui.has_hrd());
+        vui.timing_info = Some(TimingInfo {
+            num_units_in_tick: 1001,
+            time_scale: 60000,
+            fixed_frame_rate_flag: true,
+        });
+        assert_eq!(Some((30000, 1001)), vui.frame_rate());
+        vui.timing_info = Some(TimingInfo {
+            num_units_in_tick: u32::MAX,
+            time_scale: 1,
+            fixed_frame_rate_flag: true,
+        });
+        assert_eq!(None, vui.frame_rate());
+        vui.timing_info = None;
+        assert_eq!(None, vui.frame_rate());
+    }
+
+    #[test]
+    fn vui_or_inferred() {
+        let sps = SeqParameterSetBuilder::default().build();
+        assert!(sps.vui_parameters.is_none());
+        let vui = sps.vui_or_inferred();
+        assert_eq!(vui.aspect_ratio_info, Some(AspectRatioInfo::Unspecified));
+        assert_eq!(
+            vui.video_signal_type,
+            Some(VideoSignalType {
+                video_format: VideoFormat::Unspecified,
+                video_full_range_flag: false,
+                colour_description: Some(ColourDescription {
+                    colour_primaries: 2,
+                    transfer_characteristics: 2,
+                    matrix_coefficients: 2,
+                }),
+            })
+        );
+        assert!(vui.timing_info.is_none());
+        let restrictions = vui.bitstream_restrictions.unwrap();
+        assert_eq!(restrictions.max_bytes_per_pic_denom, 2);
+        assert_eq!(restrictions.max_bits_per_mb_denom, 1);
+        assert_eq!(restrictions.log2_max_mv_length_horizontal, 16);
+        assert_eq!(restrictions.log2_max_mv_length_vertical, 16);
+        assert_eq!(
+            restrictions.max_dec_frame_buffering,
+            sps.dpb_sizing().max_dec_frame_buffering
+        );
+
+        // Signalled values are kept.
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        let signalled = sps.vui_parameters.as_ref().unwrap();
+        let vui = sps.vui_or_inferred();
+        assert_eq!(vui.timing_info, signalled.timing_info);
+        assert_eq!(vui.bitstream_restrictions, signalled.bitstream_restrictions);
+    }
+
+    #[test]
+    fn display_dimensions() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let mut sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        assert_eq!(None, sps.sample_aspect_ratio());
+        assert_eq!((64, 64), sps.display_dimensions().unwrap());
+
+        let vui = sps.vui_parameters.as_mut().unwrap();
+        vui.aspect_ratio_info = Some(AspectRatioInfo::Ratio40_33);
+        assert_eq!(Some((40, 33)), sps.sample_aspect_ratio());
+        assert_eq!((78, 64), sps.display_dimensions().unwrap());
+
+        let vui = sps.vui_parameters.as_mut().unwrap();
+        vui.aspect_ratio_info = Some(AspectRatioInfo::Extended(0, 1));
+        assert_eq!(None, sps.sample_aspect_ratio());
+        assert_eq!((64, 64), sps.display_dimensions().unwrap());
+    }
+
+    #[test]
+    fn aspect_ratio_idc() {
+        for idc in 0..=255u8 {
+            let mut data = vec![0b1000_0000 | idc >> 1, idc << 7];
+            if idc == 255 {
+                data.extend_from_slice(&[0, 0, 0, 0]);
+            }
+            let mut r = rbsp::BitReader::new(&data[..]);
+            let info = AspectRatioInfo::read(&mut r).unwrap().unwrap();
+            assert_eq!(info.idc(), idc);
+        }
+    }
+
+    #[test]
+    fn video_format() {
+        for video_format in 0..=255u8 {
+            assert_eq!(VideoFormat::from(video_format).to_u8(), video_format);
+        }
+        assert_eq!(VideoFormat::from(2), VideoFormat::NTSC);
+        assert_eq!(VideoFormat::from(7), VideoFormat::Reserved(7));
+    }
+
+    #[test]
+    fn profile_idc() {
+        for profile_idc in 0..=255 {
+            let profile = Profile::from_profile_idc(ProfileIdc::from(profile_idc));
+            assert_eq!(profile.profile_idc(), profile_idc);
+        }
+        let cavlc444 = ProfileIdc::from(44);
+        assert!(cavlc444.has_chroma_info());
+        assert_eq!(Profile::from_profile_idc(cavlc444), Profile::Cavlc444Intra);
+    }
+
+    #[test]
+    fn stereo_high() {
+        // As in test_it, but with profile_idc 128 (Stereo High).
+        let data = hex!(
+            "80 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        assert_eq!(sps.profile(), Profile::StereoHigh);
+        assert_eq!(sps.chroma_info.chroma_format, ChromaFormat::YUV420);
+        assert_eq!((64, 64), sps.pixel_dimensions().unwrap());
+    }
+
+    #[test]
+    fn level_ord() {
+        assert!(Level::L1 < Level::L1_b);
+        assert!(Level::L1_b < Level::L1_1);
+        assert!(Level::L3_2 < Level::L4);
+        assert!(Level::L5_2 > Level::L4_1);
+        assert!(Level::Unknown(8) < Level::L1);
+        let high_1b = Level::from_constraint_flags_and_level_idc(ConstraintFlags::from(0), 9);
+        assert_eq!(high_1b, Level::L1_b);
+        assert!(high_1b >= Level::L1_b);
+        assert!(high_1b < Level::L1_1);
+        assert!(Level::Unknown(60) > Level::L5_2);
+        assert!(Level::Unknown(40) > Level::L4);
+        let mut levels = vec![Level::L1_1, Level::L2, Level::L1_b, Level::L1];
+        levels.sort();
+        assert_eq!(levels, [Level::L1, Level::L1_b, Level::L1_1, Level::L2]);
+    }
+
+    #[test]
+    fn constrained_baseline() {
+        let flags = ConstraintFlags::from(0b1100_0000);
+        assert!(flags.baseline_compatible());
+        assert!(flags.constrained_baseline());
+        assert!(!flags.extended_compatible());
+        assert_eq!(
+            Profile::from_profile_idc(ProfileIdc::from(66)).effective(flags),
+            Profile::ConstrainedBaseline
+        );
+        assert_eq!(Profile::ConstrainedBaseline.profile_idc(), 66);
+        assert_eq!(
+            Profile::from_profile_idc(ProfileIdc::from(66)).effective(ConstraintFlags::from(0)),
+            Profile::Baseline
+        );
+        assert_eq!(
+            Profile::from_profile_idc(ProfileIdc::from(77)).effective(flags),
+            Profile::Main
+        );
+    }
+
+    #[test]
+    fn refined_profile() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let mut sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        assert_eq!(sps.refined_profile(), Profile::High);
+        sps.constraint_flags = ConstraintFlags::from(0b0000_1000);
+        assert_eq!(sps.refined_profile(), Profile::ProgressiveHigh);
+        sps.constraint_flags = ConstraintFlags::from(0b0000_1100);
+        assert_eq!(sps.refined_profile(), Profile::ConstrainedHigh);
+        assert_eq!(Profile::ConstrainedHigh.profile_idc(), 100);
+        assert!(!sps.is_constrained_baseline());
+
+        sps.profile_idc = ProfileIdc::from(66);
+        sps.constraint_flags = ConstraintFlags::from(0b0100_0000);
+        assert!(sps.is_constrained_baseline());
+    }
+
+    #[test]
+    fn chroma_array_type() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let mut sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        assert!(!sps.is_separate_colour_plane());
+        assert_eq!(sps.chroma_array_type(), ChromaFormat::YUV420);
+
+        sps.chroma_info.chroma_format = ChromaFormat::YUV444;
+        sps.chroma_info.separate_colour_plane_flag = true;
+        assert!(sps.is_separate_colour_plane());
+        assert_eq!(sps.chroma_array_type(), ChromaFormat::Monochrome);
+    }
+
+    #[test]
+    fn chroma_subsampling() {
+        assert_eq!(ChromaFormat::YUV420.sub_width_c(), Some(2));
+        assert_eq!(ChromaFormat::YUV420.sub_height_c(), Some(2));
+        assert_eq!(ChromaFormat::YUV422.sub_width_c(), Some(2));
+        assert_eq!(ChromaFormat::YUV422.sub_height_c(), Some(1));
+        assert_eq!(ChromaFormat::YUV444.sub_width_c(), Some(1));
+        assert_eq!(ChromaFormat::YUV444.sub_height_c(), Some(1));
+        assert_eq!(ChromaFormat::Monochrome.sub_width_c(), None);
+        assert_eq!(ChromaFormat::Invalid(4).sub_height_c(), None);
+    }
+
+    #[test]
+    fn check_conformance() {
+        let mut sps = SeqParameterSetBuilder::default().build();
+        assert_eq!(sps.check_conformance(), Ok(()));
+
+        sps.frame_mbs_flags = FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: false,
+        };
+        sps.direct_8x8_inference_flag = false;
+        assert_eq!(
+            sps.check_conformance(),
+            Err(vec![
+                ConformanceWarning::Direct8x8InferenceRequired,
+                ConformanceWarning::FrameMbsOnlyRequiredByProfile(Profile::Baseline),
+            ])
+        );
+
+        sps.direct_8x8_inference_flag = true;
+        sps.profile_idc = ProfileIdc(Profile::Main.profile_idc());
+        assert_eq!(sps.check_conformance(), Ok(()));
+        sps.level_idc = Level::L4_2.level_idc();
+        assert_eq!(
+            sps.check_conformance(),
+            Err(vec![ConformanceWarning::FrameMbsOnlyRequiredByLevel(
+                Level::L4_2
+            )])
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let sps = SeqParameterSetBuilder::default()
+            .profile(Profile::High)
+            .level(Level::L4)
+            .dimensions(1920, 1080)
+            .build();
+        assert_eq!(
+            sps.summary(),
+            SpsSummary {
+                pixel_dimensions: Some((1920, 1080)),
+                coded_dimensions: (1920, 1088),
+                fps: None,
+                profile: Profile::High,
+                level: Level::L4,
+                chroma_format: ChromaFormat::YUV420,
+                bit_depth_luma: 8,
+                bit_depth_chroma: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn feature_flags() {
+        let mut sps = SeqParameterSetBuilder::default().build();
+        assert_eq!(sps.feature_flags(), SpsFeatures::default());
+        sps.chroma_info.chroma_format = ChromaFormat::YUV444;
+        sps.chroma_info.separate_colour_plane_flag = true;
+        sps.chroma_info.bit_depth_chroma_minus8 = 2;
+        sps.chroma_info.scaling_matrix = Some(SeqScalingMatrix::default());
+        sps.frame_mbs_flags = FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: true,
+        };
+        assert_eq!(
+            sps.feature_flags(),
+            SpsFeatures {
+                scaling_matrix: true,
+                separate_colour_planes: true,
+                field_coding: true,
+                mbaff: true,
+                non_420_chroma: true,
+                high_bit_depth: true,
+                transform_bypass: false,
+                hrd: false,
+            }
+        );
+    }
+
+    #[test]
+    fn builder() {
+        let sps = SeqParameterSetBuilder::default().build();
+        assert_eq!(sps.pixel_dimensions().unwrap(), (16, 16));
+        assert_eq!(sps.frame_cropping, None);
+        assert_eq!(sps.profile(), Profile::Baseline);
+        assert_eq!(sps.level(), Level::L3);
+
+        let sps = SeqParameterSetBuilder::default()
+            .profile(Profile::ConstrainedHigh)
+            .level(Level::L4)
+            .frame_mbs_flags(FrameMbsFlags::Fields {
+                mb_adaptive_frame_field_flag: true,
+            })
+            .dimensions(1919, 1080)
+            .build();
+        assert_eq!(sps.coded_dimensions(), (1920, 1088));
+        assert_eq!(sps.pixel_dimensions().unwrap(), (1920, 1080));
+        assert_eq!(sps.refined_profile(), Profile::ConstrainedHigh);
+        assert_eq!(sps.level(), Level::L4);
+
+        // Level 1b is signalled differently depending on the profile, whichever is set first.
+        let sps = SeqParameterSetBuilder::default()
+            .level(Level::L1_b)
+            .profile(Profile::Main)
+            .build();
+        assert_eq!(sps.level_idc, 11);
+        assert!(sps.constraint_flags.flag3());
+        assert_eq!(sps.level(), Level::L1_b);
+        let sps = SeqParameterSetBuilder::default()
+            .profile(Profile::High)
+            .level(Level::L1_b)
+            .build();
+        assert_eq!(sps.level_idc, 9);
+        assert!(!sps.constraint_flags.flag3());
+        assert_eq!(sps.level(), Level::L1_b);
+        let sps = SeqParameterSetBuilder::default()
+            .level(Level::L1_b)
+            .profile(Profile::High10)
+            .build();
+        assert_eq!(sps.level_idc, 9);
+        assert!(!sps.constraint_flags.flag3());
+
+        // The largest sizes round up beyond u32::MAX without overflowing.
+        let sps = SeqParameterSetBuilder::default()
+            .dimensions(u32::MAX - 1, u32::MAX - 1)
+            .build();
+        assert_eq!(sps.pic_width_in_mbs_minus1, (1 << 28) - 1);
+        assert_eq!(sps.frame_cropping.unwrap().right_offset, 1);
+    }
+
+    #[test]
+    fn max_pic_order_cnt_lsb() {
+        let poc = PicOrderCntType::TypeZero {
+            log2_max_pic_order_cnt_lsb_minus4: 0,
+        };
+        assert_eq!(poc.max_pic_order_cnt_lsb(), Some(16));
+        let poc = PicOrderCntType::TypeZero {
+            log2_max_pic_order_cnt_lsb_minus4: 12,
+        };
+        assert_eq!(poc.max_pic_order_cnt_lsb(), Some(65536));
+        assert_eq!(PicOrderCntType::TypeTwo.max_pic_order_cnt_lsb(), None);
+    }
+
+    #[test]
+    fn frame_mbs_flags() {
+        assert!(FrameMbsFlags::Frames.frame_mbs_only());
+        assert!(!FrameMbsFlags::Frames.mbaff_enabled());
+        let paff = FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: false,
+        };
+        assert!(!paff.frame_mbs_only());
+        assert!(!paff.mbaff_enabled());
+        let mbaff = FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: true,
+        };
+        assert!(!mbaff.frame_mbs_only());
+        assert!(mbaff.mbaff_enabled());
+    }
+
+    #[test]
+    fn mb_to_coords() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let mut sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        assert_eq!(sps.pic_width_in_mbs_minus1, 3);
+        assert_eq!(sps.mb_to_coords(0), (0, 0));
+        assert_eq!(sps.mb_to_coords(5), (1, 1));
+        assert_eq!(sps.first_mb_in_slice_coords(5, false), (1, 1));
+
+        sps.frame_mbs_flags = FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: true,
+        };
+        assert_eq!(sps.mbaff_mb_to_coords(0), (0, 0));
+        assert_eq!(sps.mbaff_mb_to_coords(1), (0, 1));
+        assert_eq!(sps.mbaff_mb_to_coords(2), (1, 0));
+        assert_eq!(sps.mbaff_mb_to_coords(9), (0, 3));
+        // first_mb_in_slice counts macroblock pairs in MBAFF frames, but not in field pictures.
+        assert_eq!(sps.first_mb_in_slice_coords(5, false), (1, 2));
+        assert_eq!(sps.first_mb_in_slice_coords(5, true), (1, 1));
+    }
+
+    #[test]
+    fn content_hash() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        let mut sps2 = sps.clone();
+        assert_eq!(sps.content_hash(), sps2.content_hash());
+        sps2.pic_width_in_mbs_minus1 += 1;
+        assert_ne!(sps.content_hash(), sps2.content_hash());
+    }
+
+    #[test]
+    fn eq_ignoring_vui() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        let mut sps2 = sps.clone();
+        sps2.vui_parameters = None;
+        assert_ne!(sps, sps2);
+        assert!(sps.eq_ignoring_vui(&sps2));
+        sps2.max_num_ref_frames += 1;
+        assert!(!sps.eq_ignoring_vui(&sps2));
+    }
+
+    #[test]
+    fn frame_num_gap() {
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let sps = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        let max_frame_num = 1u16 << sps.log2_max_frame_num();
+        assert_eq!(sps.frame_num_gap(3, 4), 0);
+        assert_eq!(sps.frame_num_gap(3, 3), 0);
+        assert_eq!(sps.frame_num_gap(3, 6), 2);
+        assert_eq!(sps.frame_num_gap(max_frame_num - 1, 0), 0);
+        assert_eq!(sps.frame_num_gap(max_frame_num - 2, 1), 2);
+    }
+
     #[test]
     fn test_dahua() {
         // From a Dahua IPC-HDW5231R-Z's sub stream, which is anamorphic.
@@ -1077,6 +2750,59 @@ mod test {
         );
     }
 
+    #[test]
+    fn partial_vui() {
+        // The Dahua SPS from above, truncated part-way through the VUI.
+        let data = hex!(
+            "64 00 16 AC 1B 1A 80 B0 3D FF FF
+           00 28 00 21 6E 0C 0C 0C 80 00 01"
+        );
+        assert!(SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).is_err());
+        let (sps, vui_error) =
+            SeqParameterSet::from_bits_partial(rbsp::BitReader::new(&data[..])).unwrap();
+        assert!(vui_error.is_some());
+        assert!(sps.vui_parameters.is_none());
+        assert_eq!((704, 480), sps.pixel_dimensions().unwrap());
+
+        // A well-formed SPS parses identically either way.
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let (sps, vui_error) =
+            SeqParameterSet::from_bits_partial(rbsp::BitReader::new(&data[..])).unwrap();
+        assert!(vui_error.is_none());
+        assert_eq!(
+            sps,
+            SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap()
+        );
+    }
+
+    #[test]
+    fn header_only() {
+        // The truncated Dahua SPS from `partial_vui`.
+        let data = hex!(
+            "64 00 16 AC 1B 1A 80 B0 3D FF FF
+           00 28 00 21 6E 0C 0C 0C 80 00 01"
+        );
+        let sps = SeqParameterSet::from_bits_header_only(rbsp::BitReader::new(&data[..])).unwrap();
+        assert!(sps.vui_parameters.is_none());
+        assert_eq!((704, 480), sps.pixel_dimensions().unwrap());
+        assert_eq!(Profile::High, sps.profile());
+
+        // For a well-formed SPS, everything but the VUI matches a full parse.
+        let data = hex!(
+            "64 00 0A AC 72 84 44 26 84 00 00
+            00 04 00 00 00 CA 3C 48 96 11 80"
+        );
+        let header =
+            SeqParameterSet::from_bits_header_only(rbsp::BitReader::new(&data[..])).unwrap();
+        let mut full = SeqParameterSet::from_bits(rbsp::BitReader::new(&data[..])).unwrap();
+        assert!(full.vui_parameters.is_some());
+        full.vui_parameters = None;
+        assert_eq!(header, full);
+    }
+
     #[test]
     fn crop_removes_all_pixels() {
         let sps = SeqParameterSet {
@@ -1111,6 +2837,70 @@ mod test {
         // should return Err, rather than assert due to integer underflow for example,
         let dim = sps.pixel_dimensions();
         assert!(matches!(dim, Err(SpsError::CroppingError(_))));
+        // The coded dimensions ignore cropping.
+        assert_eq!((32, 32), sps.coded_dimensions());
+    }
+
+    #[test]
+    fn coded_dimensions() {
+        let mut sps = SeqParameterSet {
+            profile_idc: ProfileIdc(0),
+            constraint_flags: ConstraintFlags(0),
+            level_idc: 0,
+            seq_parameter_set_id: SeqParamSetId::from_u32(0).unwrap(),
+            chroma_info: ChromaInfo::default(),
+            log2_max_frame_num_minus4: 0,
+            pic_order_cnt: PicOrderCntType::TypeTwo,
+            max_num_ref_frames: 0,
+            // 1920x1080, coded as 1920x1088.
+            frame_cropping: Some(FrameCropping {
+                bottom_offset: 4,
+                left_offset: 0,
+                right_offset: 0,
+                top_offset: 0,
+            }),
+            pic_width_in_mbs_minus1: 119,
+            pic_height_in_map_units_minus1: 67,
+            frame_mbs_flags: FrameMbsFlags::Frames,
+            gaps_in_frame_num_value_allowed_flag: false,
+            direct_8x8_inference_flag: false,
+            vui_parameters: None,
+        };
+        assert_eq!((1920, 1080), sps.pixel_dimensions().unwrap());
+        assert_eq!((1920, 1088), sps.coded_dimensions());
+
+        // Interlaced, with map units of field macroblock pairs.
+        sps.frame_mbs_flags = FrameMbsFlags::Fields {
+            mb_adaptive_frame_field_flag: false,
+        };
+        sps.pic_height_in_map_units_minus1 = 33;
+        sps.frame_cropping.as_mut().unwrap().bottom_offset = 2;
+        assert_eq!((1920, 1080), sps.pixel_dimensions().unwrap());
+        assert_eq!((1920, 1088), sps.coded_dimensions());
+
+        sps.pic_width_in_mbs_minus1 = u32::MAX - 1;
+        assert_eq!(u32::MAX, sps.coded_dimensions().0);
+        assert!(matches!(
+            sps.pixel_dimensions(),
+            Err(SpsError::FieldValueTooLarge {
+                name: "pic_width_in_mbs_minus1",
+                ..
+            })
+        ));
+
+        sps.pic_width_in_mbs_minus1 = 119;
+        for pic_height_in_map_units_minus1 in [u32::MAX, u32::MAX / 32] {
+            sps.pic_height_in_map_units_minus1 = pic_height_in_map_units_minus1;
+            assert!(matches!(
+                sps.pixel_dimensions(),
+                Err(SpsError::FieldValueTooLarge {
+                    name: "pic_height_in_map_units_minus1",
+                    ..
+                })
+            ));
+        }
+        sps.pic_width_in_mbs_minus1 = u32::MAX;
+        assert_eq!(sps.mb_to_coords(5), (5, 0));
     }
 
     #[test_case(
@@ -1414,6 +3204,7 @@ mod test {
             seq_parameter_set_id: SeqParamSetId::from_u32(0).unwrap(),
             chroma_info: ChromaInfo{
                 chroma_format: ChromaFormat::YUV420,
+                scaling_matrix: Some(SeqScalingMatrix::default()),
                 ..ChromaInfo::default()
             },
             /*seq_scaling_list: Some(SeqScalingList{
@@ -1639,5 +3430,15 @@ mod test {
         assert_eq!(width, width2);
         assert_eq!(height, height2);
         assert_eq!(fps, sps2.fps().unwrap());
+
+        // Writing reproduces the original NAL, except that scaling matrices can't be written.
+        let mut w = rbsp::BitWriter::new(Vec::new());
+        if sps.chroma_info.scaling_matrix.is_some() {
+            assert!(sps.write(&mut w).is_err());
+        } else {
+            sps.write(&mut w).unwrap();
+            let header = crate::nal::NalHeader::new(byts[0]).unwrap();
+            assert_eq!(rbsp::encode_nal(header, &w.into_writer()), byts);
+        }
     }
 }
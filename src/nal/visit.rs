@@ -0,0 +1,192 @@
+//! Traversal of already-parsed NAL structures.
+//!
+//! Implement [`NalVisitor`], overriding the methods for the structures of interest, then drive
+//! it with [`NalVisitor::visit_access_unit`] or any other `visit_*` method. Each method's
+//! default implementation calls the corresponding `walk_*` function, which visits the
+//! structure's children; an overriding method should call it too in order to keep descending.
+//!
+//! ```
+//! use h264_reader::nal::sps::HrdParameters;
+//! use h264_reader::nal::visit::NalVisitor;
+//!
+//! #[derive(Default)]
+//! struct CpbCounter(usize);
+//! impl NalVisitor for CpbCounter {
+//!     fn visit_hrd(&mut self, hrd: &HrdParameters) {
+//!         self.0 += hrd.cpb_specs.len();
+//!     }
+//! }
+//! ```
+
+use crate::nal::access_unit::AccessUnit;
+use crate::nal::pps::PicParameterSet;
+use crate::nal::sei::OwnedSeiMessage;
+use crate::nal::slice::{DecRefPicMarking, PredWeightTable, RefPicListModifications, SliceHeader};
+use crate::nal::sps::{
+    BitstreamRestrictions, HrdParameters, SeqParameterSet, TimingInfo, VuiParameters,
+};
+
+/// Visitor over parsed NAL structures. All methods default to visiting the structure's children
+/// via the corresponding `walk_*` function.
+pub trait NalVisitor {
+    fn visit_access_unit(&mut self, au: &AccessUnit) {
+        walk_access_unit(self, au);
+    }
+
+    fn visit_sps(&mut self, sps: &SeqParameterSet) {
+        walk_sps(self, sps);
+    }
+
+    fn visit_vui(&mut self, vui: &VuiParameters) {
+        walk_vui(self, vui);
+    }
+
+    fn visit_timing_info(&mut self, _timing_info: &TimingInfo) {}
+
+    /// Visits the NAL HRD parameters, then the VCL HRD parameters, of a VUI.
+    fn visit_hrd(&mut self, _hrd: &HrdParameters) {}
+
+    fn visit_bitstream_restrictions(&mut self, _restrictions: &BitstreamRestrictions) {}
+
+    fn visit_pps(&mut self, _pps: &PicParameterSet) {}
+
+    fn visit_slice_header(&mut self, header: &SliceHeader) {
+        walk_slice_header(self, header);
+    }
+
+    fn visit_ref_pic_list_modifications(&mut self, _modifications: &RefPicListModifications) {}
+
+    fn visit_pred_weight_table(&mut self, _table: &PredWeightTable) {}
+
+    fn visit_dec_ref_pic_marking(&mut self, _marking: &DecRefPicMarking) {}
+
+    fn visit_sei(&mut self, _msg: &OwnedSeiMessage) {}
+}
+
+/// Visits the SPS, PPS, SEI messages and slice headers of an access unit, in that order.
+pub fn walk_access_unit<V: NalVisitor + ?Sized>(v: &mut V, au: &AccessUnit) {
+    if let Some(sps) = &au.sps {
+        v.visit_sps(sps);
+    }
+    if let Some(pps) = &au.pps {
+        v.visit_pps(pps);
+    }
+    for msg in &au.sei_messages {
+        v.visit_sei(msg);
+    }
+    for slice in &au.slices {
+        v.visit_slice_header(slice);
+    }
+}
+
+/// Visits the VUI parameters of an SPS, if present.
+pub fn walk_sps<V: NalVisitor + ?Sized>(v: &mut V, sps: &SeqParameterSet) {
+    if let Some(vui) = &sps.vui_parameters {
+        v.visit_vui(vui);
+    }
+}
+
+/// Visits the timing info, HRD parameters and bitstream restrictions of VUI parameters, where
+/// present.
+pub fn walk_vui<V: NalVisitor + ?Sized>(v: &mut V, vui: &VuiParameters) {
+    if let Some(timing_info) = &vui.timing_info {
+        v.visit_timing_info(timing_info);
+    }
+    if let Some(hrd) = &vui.nal_hrd_parameters {
+        v.visit_hrd(hrd);
+    }
+    if let Some(hrd) = &vui.vcl_hrd_parameters {
+        v.visit_hrd(hrd);
+    }
+    if let Some(restrictions) = &vui.bitstream_restrictions {
+        v.visit_bitstream_restrictions(restrictions);
+    }
+}
+
+/// Visits the reference picture list modifications, prediction weight table and decoded
+/// reference picture marking of a slice header, where present.
+pub fn walk_slice_header<V: NalVisitor + ?Sized>(v: &mut V, header: &SliceHeader) {
+    if let Some(modifications) = &header.ref_pic_list_modification {
+        v.visit_ref_pic_list_modifications(modifications);
+    }
+    if let Some(table) = &header.pred_weight_table {
+        v.visit_pred_weight_table(table);
+    }
+    if let Some(marking) = &header.dec_ref_pic_marking {
+        v.visit_dec_ref_pic_marking(marking);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nal::RefNal;
+    use crate::Context;
+    use hex_literal::hex;
+
+    #[derive(Default)]
+    struct Recorder(Vec<&'static str>);
+    impl NalVisitor for Recorder {
+        fn visit_sps(&mut self, sps: &SeqParameterSet) {
+            self.0.push("sps");
+            walk_sps(self, sps);
+        }
+        fn visit_vui(&mut self, vui: &VuiParameters) {
+            self.0.push("vui");
+            walk_vui(self, vui);
+        }
+        fn visit_timing_info(&mut self, _timing_info: &TimingInfo) {
+            self.0.push("timing_info");
+        }
+        fn visit_pps(&mut self, _pps: &PicParameterSet) {
+            self.0.push("pps");
+        }
+        fn visit_sei(&mut self, _msg: &OwnedSeiMessage) {
+            self.0.push("sei");
+        }
+        fn visit_slice_header(&mut self, header: &SliceHeader) {
+            self.0.push("slice_header");
+            walk_slice_header(self, header);
+        }
+        fn visit_ref_pic_list_modifications(&mut self, _: &RefPicListModifications) {
+            self.0.push("ref_pic_list_modifications");
+        }
+        fn visit_dec_ref_pic_marking(&mut self, _marking: &DecRefPicMarking) {
+            self.0.push("dec_ref_pic_marking");
+        }
+    }
+
+    #[test]
+    fn walk() {
+        let sps = hex!(
+            "67 64 00 0A AC 72 84 44 26 84 00 00 03
+            00 04 00 00 03 00 CA 3C 48 96 11 80"
+        );
+        let pps = hex!("68 E8 43 8F 13 21 30");
+        let sei = hex!("06 06 01 84 80");
+        let slice = hex!("65 88 81 00 2a 80");
+        let nals = [
+            RefNal::new(&sps[..], &[], true),
+            RefNal::new(&pps[..], &[], true),
+            RefNal::new(&sei[..], &[], true),
+            RefNal::new(&slice[..], &[], true),
+        ];
+        let au = Context::default().parse_access_unit(&nals);
+        assert!(au.errors.is_empty());
+        let mut recorder = Recorder::default();
+        recorder.visit_access_unit(&au);
+        assert_eq!(
+            recorder.0,
+            &[
+                "sps",
+                "vui",
+                "timing_info",
+                "pps",
+                "sei",
+                "slice_header",
+                "ref_pic_list_modifications",
+                "dec_ref_pic_marking",
+            ]
+        );
+    }
+}
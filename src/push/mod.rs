@@ -1,6 +1,7 @@
 //! Push parsing of encoded NALs.
 
-use crate::nal::{NalHeader, RefNal};
+use crate::nal::{Nal, NalBuf, NalHeader, RefNal};
+use std::collections::VecDeque;
 
 /// [`AccumulatedNalHandler`]'s interest in receiving additional callbacks on a NAL.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -9,6 +10,16 @@ pub enum NalInterest {
     /// No effect if the NAL is complete.
     Buffer,
 
+    /// Buffer at most the given number of bytes of this NAL, then ignore the remainder.
+    ///
+    /// Later calls on this NAL present at most that many bytes. If bytes beyond the limit are
+    /// dropped, the call presents the truncated prefix as incomplete (so it can be told apart
+    /// from a short complete NAL), and is the last call on the NAL unless it returns a greater
+    /// limit or [`NalInterest::Buffer`]. Likewise, there are no more calls once the limit has
+    /// been buffered. This suits handlers which only need to parse a header from the start of a
+    /// potentially large NAL. No effect if the NAL is complete.
+    BufferUpTo(usize),
+
     /// Don't buffer any more of this NAL or make any more calls on it.
     Ignore,
 }
@@ -20,6 +31,10 @@ pub enum NalInterest {
 /// [NalAccumulator::handler_mut], or [NalAccumulator::into_handler].
 pub trait AccumulatedNalHandler {
     fn nal(&mut self, nal: RefNal<'_>) -> NalInterest;
+
+    /// Called with the offset of the next NAL's start code within the stream, for framing
+    /// parsers which track it. See [`NalFragmentHandler::nal_start`].
+    fn nal_start(&mut self, _stream_offset: u64) {}
 }
 
 impl<F: FnMut(RefNal<'_>) -> NalInterest> AccumulatedNalHandler for F {
@@ -38,6 +53,13 @@ pub trait NalFragmentHandler {
     /// The caller must ensure that each element of `bufs` (if there are any)
     /// is non-empty.
     fn nal_fragment(&mut self, bufs: &[&[u8]], end: bool);
+
+    /// Called before the first `nal_fragment` call of each NAL by framing parsers which track
+    /// stream positions (such as [`crate::annexb::AnnexBReader`]), with the byte offset of the
+    /// NAL's start code within the stream.
+    ///
+    /// If the NAL turns out to be empty, there may be no corresponding `nal_fragment` call.
+    fn nal_start(&mut self, _stream_offset: u64) {}
 }
 
 /// NAL accumulator for push parsers.
@@ -127,39 +149,101 @@ impl<H: AccumulatedNalHandler> NalAccumulator<H> {
         self.nal_handler
     }
 }
+
+/// Returns at most `limit` bytes of `bufs`, and whether any bytes were dropped.
+fn limit_bufs<'b>(bufs: &[&'b [u8]], mut limit: usize) -> (Vec<&'b [u8]>, bool) {
+    let mut limited = Vec::with_capacity(bufs.len());
+    for b in bufs {
+        if b.len() > limit {
+            if limit > 0 {
+                limited.push(&b[..limit]);
+            }
+            return (limited, true);
+        }
+        limited.push(*b);
+        limit -= b.len();
+    }
+    (limited, false)
+}
+
+/// True if `new` allows the handler to see more of a NAL than `old` does.
+fn wants_more(new: NalInterest, old: NalInterest) -> bool {
+    match (new, old) {
+        (NalInterest::Buffer, NalInterest::BufferUpTo(_)) => true,
+        (NalInterest::BufferUpTo(new), NalInterest::BufferUpTo(old)) => new > old,
+        _ => false,
+    }
+}
+
 impl<H: AccumulatedNalHandler> NalFragmentHandler for NalAccumulator<H> {
     /// Calls `nal_handler` with accumulated NAL unless any of the following are true:
-    /// *   a previous call on the same NAL returned [`NalInterest::Ignore`].
+    /// *   a previous call on the same NAL returned [`NalInterest::Ignore`], or returned
+    ///     [`NalInterest::BufferUpTo`] and the limit has been reached.
     /// *   the NAL is totally empty.
     /// *   `bufs` is empty and `end` is false.
     fn nal_fragment(&mut self, bufs: &[&[u8]], end: bool) {
-        if self.interest != NalInterest::Ignore {
+        // The interest which governs how much of `bufs` the handler may see.
+        let mut interest = self.interest;
+        while interest != NalInterest::Ignore {
+            let limited;
+            let (presented, truncated) = match interest {
+                NalInterest::BufferUpTo(limit) => {
+                    let (l, truncated) = limit_bufs(bufs, limit.saturating_sub(self.buf.len()));
+                    limited = l;
+                    (&limited[..], truncated)
+                }
+                _ => (bufs, false),
+            };
+            let complete = end && !truncated;
             let nal = if !self.buf.is_empty() {
-                RefNal::new(&self.buf[..], bufs, end)
-            } else if bufs.is_empty() {
-                return; // no-op.
+                RefNal::new(&self.buf[..], presented, complete)
+            } else if presented.is_empty() {
+                break; // no-op.
             } else {
-                RefNal::new(bufs[0], &bufs[1..], end)
+                RefNal::new(presented[0], &presented[1..], complete)
             };
 
             // Call the NAL handler. Avoid copying unless necessary.
-            match self.nal_handler.nal(nal) {
+            let new_interest = self.nal_handler.nal(nal);
+            if truncated && wants_more(new_interest, interest) {
+                // Present again the bytes which were dropped for the old limit.
+                interest = new_interest;
+                continue;
+            }
+            self.interest = match new_interest {
+                _ if truncated => NalInterest::Ignore,
                 NalInterest::Buffer if !end => {
-                    let len = bufs.iter().map(|b| b.len()).sum();
+                    let len = presented.iter().map(|b| b.len()).sum();
                     self.buf.reserve(len);
-                    for b in bufs {
+                    for b in presented {
                         self.buf.extend_from_slice(b);
                     }
+                    NalInterest::Buffer
                 }
-                NalInterest::Ignore => self.interest = NalInterest::Ignore,
-                _ => {}
-            }
+                NalInterest::BufferUpTo(limit) if !end => {
+                    for b in presented {
+                        let wanted = limit.saturating_sub(self.buf.len()).min(b.len());
+                        self.buf.extend_from_slice(&b[..wanted]);
+                    }
+                    if self.buf.len() >= limit {
+                        NalInterest::Ignore
+                    } else {
+                        new_interest
+                    }
+                }
+                _ => new_interest,
+            };
+            break;
         }
         if end {
             self.buf.clear();
             self.interest = NalInterest::Buffer;
         }
     }
+
+    fn nal_start(&mut self, stream_offset: u64) {
+        self.nal_handler.nal_start(stream_offset);
+    }
 }
 impl<H: AccumulatedNalHandler + std::fmt::Debug> std::fmt::Debug for NalAccumulator<H> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -172,9 +256,185 @@ impl<H: AccumulatedNalHandler + std::fmt::Debug> std::fmt::Debug for NalAccumula
     }
 }
 
+/// A [`NalFragmentHandler`] which buffers each complete NAL in a queue, from which it can later
+/// be popped. This allows NALs to be pulled from a framing parser rather than pushed to a
+/// callback, as is convenient e.g. in `async` code.
+///
+/// ```
+/// use h264_reader::annexb::AnnexBReader;
+/// use h264_reader::nal::{Nal, UnitType};
+/// use h264_reader::push::NalQueue;
+///
+/// let mut reader = AnnexBReader::for_fragment_handler(NalQueue::new());
+/// reader.push(&b"\x00\x00\x00\x01\x09\xf0\x00\x00\x01\x68\xE8"[..]);
+/// reader.push(&b"\x43\x8F\x13\x21\x30"[..]);
+/// reader.reset();
+///
+/// let queue = reader.fragment_handler_mut();
+/// let nal = queue.pop().unwrap();
+/// assert_eq!(nal.as_ref_nal().header().unwrap().nal_unit_type(), UnitType::AccessUnitDelimiter);
+/// assert_eq!(queue.pop().unwrap().as_bytes(), b"\x68\xE8\x43\x8F\x13\x21\x30");
+/// assert!(queue.pop().is_none());
+/// ```
+///
+/// Every NAL is buffered in full, including those the caller may not be interested in.
+#[derive(Debug, Default)]
+pub struct NalQueue {
+    /// The fragments of the current, incomplete NAL.
+    buf: Vec<u8>,
+    nals: VecDeque<NalBuf>,
+}
+impl NalQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes and returns the oldest complete NAL, if any.
+    pub fn pop(&mut self) -> Option<NalBuf> {
+        self.nals.pop_front()
+    }
+
+    /// Returns the number of complete NALs in the queue.
+    pub fn len(&self) -> usize {
+        self.nals.len()
+    }
+
+    /// Returns true if there are no complete NALs in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.nals.is_empty()
+    }
+}
+impl NalFragmentHandler for NalQueue {
+    fn nal_fragment(&mut self, bufs: &[&[u8]], end: bool) {
+        for b in bufs {
+            self.buf.extend_from_slice(b);
+        }
+        if end && !self.buf.is_empty() {
+            self.nals
+                .push_back(NalBuf::new(std::mem::take(&mut self.buf)));
+        }
+    }
+}
+
+/// A [`NalFragmentHandler`] which forwards every call to two inner handlers, so that one
+/// framing parser (such as [`crate::annexb::AnnexBReader`]) can feed several consumers.
+///
+/// To combine two [`AccumulatedNalHandler`]s, prefer a single [`NalAccumulator`] wrapping a
+/// [`TeeAccumulatedNalHandler`], which buffers each NAL only once.
+#[derive(Debug)]
+pub struct Tee<A, B> {
+    a: A,
+    b: B,
+}
+impl<A, B> Tee<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Tee { a, b }
+    }
+
+    /// Gets references to the inner handlers.
+    pub fn handlers(&self) -> (&A, &B) {
+        (&self.a, &self.b)
+    }
+
+    /// Gets mutable references to the inner handlers.
+    pub fn handlers_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.a, &mut self.b)
+    }
+
+    /// Unwraps this `Tee<A, B>`, returning the inner handlers.
+    pub fn into_handlers(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+impl<A: NalFragmentHandler, B: NalFragmentHandler> NalFragmentHandler for Tee<A, B> {
+    fn nal_fragment(&mut self, bufs: &[&[u8]], end: bool) {
+        self.a.nal_fragment(bufs, end);
+        self.b.nal_fragment(bufs, end);
+    }
+
+    fn nal_start(&mut self, stream_offset: u64) {
+        self.a.nal_start(stream_offset);
+        self.b.nal_start(stream_offset);
+    }
+}
+
+/// An [`AccumulatedNalHandler`] which forwards to two inner handlers.
+///
+/// The NAL is buffered if either inner handler returns [`NalInterest::Buffer`] (or
+/// [`NalInterest::BufferUpTo`], up to the larger limit), and ignored only once both have returned
+/// [`NalInterest::Ignore`]. As with [`NalAccumulator`], a handler which
+/// has returned `Ignore` isn't called again until the next NAL, even if the other handler is
+/// still interested.
+#[derive(Debug)]
+pub struct TeeAccumulatedNalHandler<A, B> {
+    a: A,
+    b: B,
+    a_interest: NalInterest,
+    b_interest: NalInterest,
+}
+impl<A, B> TeeAccumulatedNalHandler<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        TeeAccumulatedNalHandler {
+            a,
+            b,
+            a_interest: NalInterest::Buffer,
+            b_interest: NalInterest::Buffer,
+        }
+    }
+
+    /// Gets references to the inner handlers.
+    pub fn handlers(&self) -> (&A, &B) {
+        (&self.a, &self.b)
+    }
+
+    /// Gets mutable references to the inner handlers.
+    pub fn handlers_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.a, &mut self.b)
+    }
+
+    /// Unwraps this `TeeAccumulatedNalHandler<A, B>`, returning the inner handlers.
+    pub fn into_handlers(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+impl<A: AccumulatedNalHandler, B: AccumulatedNalHandler> AccumulatedNalHandler
+    for TeeAccumulatedNalHandler<A, B>
+{
+    fn nal(&mut self, nal: RefNal<'_>) -> NalInterest {
+        let complete = nal.is_complete();
+        if self.a_interest != NalInterest::Ignore {
+            self.a_interest = self.a.nal(nal.clone());
+        }
+        if self.b_interest != NalInterest::Ignore {
+            self.b_interest = self.b.nal(nal);
+        }
+        let interest = match (self.a_interest, self.b_interest) {
+            (NalInterest::Buffer, _) | (_, NalInterest::Buffer) => NalInterest::Buffer,
+            (NalInterest::BufferUpTo(a), NalInterest::BufferUpTo(b)) => {
+                NalInterest::BufferUpTo(a.max(b))
+            }
+            (NalInterest::BufferUpTo(limit), NalInterest::Ignore)
+            | (NalInterest::Ignore, NalInterest::BufferUpTo(limit)) => {
+                NalInterest::BufferUpTo(limit)
+            }
+            (NalInterest::Ignore, NalInterest::Ignore) => NalInterest::Ignore,
+        };
+        if complete || interest == NalInterest::Ignore {
+            // There will be no further calls on this NAL.
+            self.a_interest = NalInterest::Buffer;
+            self.b_interest = NalInterest::Buffer;
+        }
+        interest
+    }
+
+    fn nal_start(&mut self, stream_offset: u64) {
+        self.a.nal_start(stream_offset);
+        self.b.nal_start(stream_offset);
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::nal::Nal;
     use std::io::{BufRead, Read};
 
     use super::*;
@@ -237,4 +497,143 @@ mod test {
             ]
         );
     }
+
+    #[derive(Default)]
+    struct MockFragmentHandler {
+        data: Vec<u8>,
+        ended: u32,
+    }
+    impl NalFragmentHandler for MockFragmentHandler {
+        fn nal_fragment(&mut self, bufs: &[&[u8]], end: bool) {
+            for buf in bufs {
+                self.data.extend_from_slice(buf);
+            }
+            if end {
+                self.ended += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn tee() {
+        let mut tee = Tee::new(
+            MockFragmentHandler::default(),
+            MockFragmentHandler::default(),
+        );
+        tee.nal_fragment(&[&[0b0101_0001], &[1]], false);
+        tee.nal_fragment(&[&[2]], true);
+        let (a, b) = tee.into_handlers();
+        for h in [a, b] {
+            assert_eq!(h.data, &[0b0101_0001, 1, 2]);
+            assert_eq!(h.ended, 1);
+        }
+    }
+
+    #[test]
+    fn buffer_up_to() {
+        let mut calls = Vec::new();
+        let handler = |nal: RefNal<'_>| {
+            let mut buf = Vec::new();
+            let _ = nal.reader().read_to_end(&mut buf);
+            calls.push((buf, nal.is_complete()));
+            NalInterest::BufferUpTo(3)
+        };
+        let mut accumulator = NalAccumulator::new(handler);
+        accumulator.nal_fragment(&[&[0b0101_0001, 1]], false);
+        accumulator.nal_fragment(&[&[2, 3], &[4]], false);
+        accumulator.nal_fragment(&[&[5]], true);
+        // The next NAL is complete before the limit is reached.
+        accumulator.nal_fragment(&[&[0b0101_0001]], false);
+        accumulator.nal_fragment(&[&[6]], true);
+        assert_eq!(
+            calls,
+            &[
+                (vec![0b0101_0001, 1], false),
+                // Truncated to the limit, and so incomplete. There are no more calls on this NAL.
+                (vec![0b0101_0001, 1, 2], false),
+                (vec![0b0101_0001], false),
+                (vec![0b0101_0001, 6], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn buffer_up_to_then_more() {
+        // On seeing the truncated prefix, the handler asks for the whole NAL after all.
+        let mut calls = Vec::new();
+        let handler = |nal: RefNal<'_>| {
+            let mut buf = Vec::new();
+            let _ = nal.reader().read_to_end(&mut buf);
+            let interest = if buf.len() < 3 {
+                NalInterest::BufferUpTo(3)
+            } else {
+                NalInterest::Buffer
+            };
+            calls.push((buf, nal.is_complete()));
+            interest
+        };
+        let mut accumulator = NalAccumulator::new(handler);
+        accumulator.nal_fragment(&[&[0b0101_0001, 1]], false);
+        accumulator.nal_fragment(&[&[2, 3]], false);
+        accumulator.nal_fragment(&[&[4]], true);
+        assert_eq!(
+            calls,
+            &[
+                (vec![0b0101_0001, 1], false),
+                (vec![0b0101_0001, 1, 2], false),
+                (vec![0b0101_0001, 1, 2, 3], false),
+                (vec![0b0101_0001, 1, 2, 3, 4], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn tee_accumulated() {
+        // `a` only looks at the first fragment; `b` wants the whole NAL.
+        let mut a_calls = 0;
+        let mut b_complete = Vec::new();
+        let mut accumulator = NalAccumulator::new(TeeAccumulatedNalHandler::new(
+            |_nal: RefNal<'_>| {
+                a_calls += 1;
+                NalInterest::Ignore
+            },
+            |nal: RefNal<'_>| {
+                if nal.is_complete() {
+                    let mut buf = Vec::new();
+                    nal.reader().read_to_end(&mut buf).unwrap();
+                    b_complete.push(buf);
+                }
+                NalInterest::Buffer
+            },
+        ));
+        for _ in 0..2 {
+            accumulator.nal_fragment(&[&[0b0101_0001]], false);
+            accumulator.nal_fragment(&[&[1]], false);
+            accumulator.nal_fragment(&[&[2]], true);
+        }
+        drop(accumulator);
+        assert_eq!(a_calls, 2);
+        assert_eq!(b_complete, &[&[0b0101_0001, 1, 2][..]; 2]);
+    }
+
+    #[test]
+    fn nal_queue() {
+        let mut depacketizer = crate::rtp::RtpDepacketizer::for_fragment_handler(NalQueue::new());
+        // A STAP-A packet aggregating two NALs, then an FU-A fragmented NAL.
+        depacketizer.push(&[0x78, 0x00, 0x02, 0x09, 0xf0, 0x00, 0x01, 0x0c]);
+        depacketizer.push(&[0x7c, 0x85, 0x88, 0x81]);
+        let queue = depacketizer.fragment_handler_mut();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop().unwrap().as_bytes(), &[0x09, 0xf0]);
+        assert_eq!(queue.pop().unwrap().into_bytes(), &[0x0c]);
+        assert!(queue.is_empty());
+
+        depacketizer.push(&[0x7c, 0x45, 0x00, 0x05]);
+        let queue = depacketizer.fragment_handler_mut();
+        assert_eq!(
+            queue.pop().unwrap().as_bytes(),
+            &[0x65, 0x88, 0x81, 0x00, 0x05]
+        );
+        assert!(queue.pop().is_none());
+    }
 }
@@ -23,7 +23,9 @@
 //! the sequence `0x00 0x00 0x03` with `0x00 0x00`).
 
 use bitstream_io::read::BitRead as _;
+use bitstream_io::write::BitWrite as _;
 use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::io::BufRead;
 use std::io::Read;
 
@@ -44,6 +46,11 @@ enum ParseState {
 ///
 /// Typically used via a [`h264_reader::nal::Nal`]. Returns error on encountering
 /// invalid byte sequences.
+///
+/// Bytes are never held back awaiting a possible `emulation-prevention-three` byte: only the
+/// `0x03` itself is removed, so any `0x00` bytes at the end of the input (e.g. the NAL ending in
+/// `0x00 0x00`) are returned as-is. A final `0x00 0x00 0x03` has its `0x03` removed, as for the
+/// escaped `cabac_zero_word`s which may end a NAL.
 #[derive(Clone)]
 pub struct ByteReader<R: BufRead> {
     // self.inner[0..self.i] hasn't yet been emitted and is RBSP (has no
@@ -59,6 +66,13 @@ pub struct ByteReader<R: BufRead> {
     /// The maximum number of bytes in a fresh chunk. Surprisingly, it's
     /// significantly faster to limit this, maybe due to CPU cache effects.
     max_fill: usize,
+
+    /// The number of NAL bytes consumed from `inner`.
+    nal_pos: usize,
+
+    /// The NAL offsets of the `emulation-prevention-three` bytes removed so far, if tracking
+    /// was requested.
+    removed: Option<Vec<usize>>,
 }
 impl<R: BufRead> ByteReader<R> {
     /// Constructs an adapter from the given [BufRead]. The NAL header byte is
@@ -69,9 +83,86 @@ impl<R: BufRead> ByteReader<R> {
             state: ParseState::HeaderByte,
             i: 0,
             max_fill: 128,
+            nal_pos: 0,
+            removed: None,
+        }
+    }
+
+    /// Constructs an adapter from the given [BufRead], which must be positioned after the NAL
+    /// header (including any NAL unit header extension).
+    ///
+    /// Unlike [`ByteReader::new`], which skips the first byte as the NAL header, this only
+    /// removes `emulation-prevention-three` bytes. This suits data whose header has already been
+    /// consumed or handled separately, such as the remainder of a NAL after an extended header,
+    /// and avoids prepending a placeholder header byte just to strip the escapes.
+    ///
+    /// ```
+    /// # use h264_reader::rbsp::ByteReader;
+    /// # use std::io::Read;
+    /// let mut r = ByteReader::without_header(&b"\x12\x00\x00\x03\x01\x86"[..]);
+    /// let mut rbsp = Vec::new();
+    /// r.read_to_end(&mut rbsp).unwrap();
+    /// assert_eq!(rbsp, b"\x12\x00\x00\x01\x86");
+    /// ```
+    pub fn without_header(inner: R) -> Self {
+        Self {
+            state: ParseState::Start,
+            ..Self::new(inner)
         }
     }
 
+    /// Constructs an adapter which additionally records the position within the NAL of each
+    /// `emulation-prevention-three` byte it removes, as returned by
+    /// [`ByteReader::removed_positions`]. This allows RBSP offsets to be mapped back to NAL
+    /// offsets via [`ByteReader::nal_offset`], e.g. to report the location of an error in the
+    /// original data, or to re-insert the same escapes when writing a modified NAL.
+    ///
+    /// The positions are held in a `Vec` which grows by one `usize` per removed byte, so
+    /// tracking costs an allocation for any NAL which contains emulation prevention; the
+    /// adapter returned by [`ByteReader::new`] never allocates.
+    ///
+    /// ```
+    /// # use h264_reader::rbsp::ByteReader;
+    /// # use std::io::Read;
+    /// let mut r = ByteReader::with_position_tracking(&b"\x68\x12\x00\x00\x03\x01\x86"[..]);
+    /// let mut rbsp = Vec::new();
+    /// r.read_to_end(&mut rbsp).unwrap();
+    /// assert_eq!(rbsp, b"\x12\x00\x00\x01\x86");
+    /// assert_eq!(r.removed_positions(), Some(&[4][..]));
+    /// assert_eq!(r.nal_offset(3), Some(5));
+    /// ```
+    pub fn with_position_tracking(inner: R) -> Self {
+        Self {
+            removed: Some(Vec::new()),
+            ..Self::new(inner)
+        }
+    }
+
+    /// Returns the positions within the NAL (counting the header byte as position `0`) of the
+    /// `emulation-prevention-three` bytes removed so far, in increasing order, or `None` if the
+    /// adapter wasn't constructed with [`ByteReader::with_position_tracking`].
+    pub fn removed_positions(&self) -> Option<&[usize]> {
+        self.removed.as_deref()
+    }
+
+    /// Maps an offset within the RBSP to the corresponding offset within the NAL, or returns
+    /// `None` if the adapter wasn't constructed with [`ByteReader::with_position_tracking`].
+    ///
+    /// Only emulation prevention bytes which have already been removed are taken into account,
+    /// so the result is accurate for any RBSP byte which has been returned by this adapter.
+    pub fn nal_offset(&self, rbsp_offset: usize) -> Option<usize> {
+        let removed = self.removed.as_ref()?;
+        // Skip the header byte, then each removed byte at or before the offset found so far.
+        let mut offset = rbsp_offset + 1;
+        for &pos in removed {
+            if pos > offset {
+                break;
+            }
+            offset += 1;
+        }
+        Some(offset)
+    }
+
     /// Called when self.i == 0 only; returns false at EOF.
     /// Doesn't return actual buffer contents due to borrow checker limitations;
     /// caller will need to call fill_buf again.
@@ -115,12 +206,17 @@ impl<R: BufRead> ByteReader<R> {
                 ParseState::HeaderByte => {
                     debug_assert_eq!(self.i, 0);
                     self.inner.consume(1);
+                    self.nal_pos += 1;
                     self.state = ParseState::Start;
                     break;
                 }
                 ParseState::Three => {
                     debug_assert_eq!(self.i, 0);
                     self.inner.consume(1);
+                    if let Some(removed) = &mut self.removed {
+                        removed.push(self.nal_pos);
+                    }
+                    self.nal_pos += 1;
                     self.state = ParseState::PostThree;
                     break;
                 }
@@ -170,6 +266,7 @@ impl<R: BufRead> BufRead for ByteReader<R> {
     fn consume(&mut self, amt: usize) {
         self.i = self.i.checked_sub(amt).unwrap();
         self.inner.consume(amt);
+        self.nal_pos += amt;
     }
 }
 
@@ -200,6 +297,8 @@ pub fn decode_nal<'a>(nal_unit: &'a [u8]) -> Result<Cow<'a, [u8]>, std::io::Erro
         state: ParseState::HeaderByte,
         i: 0,
         max_fill: usize::MAX, // to borrow if at all possible.
+        nal_pos: 0,
+        removed: None,
     };
     let buf = reader.fill_buf()?;
     if buf.len() + 1 == nal_unit.len() {
@@ -219,6 +318,42 @@ pub fn decode_nal<'a>(nal_unit: &'a [u8]) -> Result<Cow<'a, [u8]>, std::io::Erro
     Ok(Cow::Owned(dst))
 }
 
+/// Returns a NAL from the given header and RBSP, inserting `emulation-prevention-three` bytes
+/// where needed. This is the inverse of [`decode_nal`].
+///
+/// ```
+/// # use h264_reader::nal::NalHeader;
+/// # use h264_reader::rbsp::encode_nal;
+/// let header = NalHeader::new(0x68).unwrap();
+/// assert_eq!(
+///     encode_nal(header, &b"\x12\x34\x00\x00\x00\x86"[..]),
+///     &b"\x68\x12\x34\x00\x00\x03\x00\x86"[..]);
+/// ```
+pub fn encode_nal(header: crate::nal::NalHeader, rbsp: &[u8]) -> Vec<u8> {
+    // Assume few emulation prevention bytes will be needed.
+    let mut nal = Vec::with_capacity(rbsp.len() + 2);
+    nal.push(u8::from(header));
+    let mut zeros = 0;
+    for &b in rbsp {
+        if zeros >= 2 && b <= 0x03 {
+            nal.push(0x03);
+            zeros = 0;
+        }
+        nal.push(b);
+        if b == 0x00 {
+            zeros += 1;
+        } else {
+            zeros = 0;
+        }
+    }
+    // A trailing zero (only possible after a `cabac_zero_word`) would run into any following
+    // start code, so must also be escaped.
+    if rbsp.last() == Some(&0x00) {
+        nal.push(0x03);
+    }
+    nal
+}
+
 #[derive(Debug)]
 pub enum BitReaderError {
     ReaderError(std::io::Error),
@@ -231,6 +366,15 @@ pub enum BitReaderError {
     RemainingData,
 
     Unaligned,
+
+    /// The named syntax element had a reserved value, when parsing with
+    /// [`crate::ParseOptions::strict`].
+    ReservedValue(&'static str, u32),
+}
+impl From<std::io::Error> for BitReaderError {
+    fn from(e: std::io::Error) -> Self {
+        BitReaderError::ReaderError(e)
+    }
 }
 
 pub trait BitRead {
@@ -242,12 +386,63 @@ pub trait BitRead {
     fn read_u32(&mut self, bit_count: u32, name: &'static str) -> Result<u32, BitReaderError>;
     fn read_i32(&mut self, bit_count: u32, name: &'static str) -> Result<i32, BitReaderError>;
 
+    /// Returns the next `bit_count` bits (at most 32) without consuming them. Only available
+    /// for readers which are `Clone`, as [`BitReader`] is.
+    ///
+    /// This is a convenience for the clone-and-read pattern used by
+    /// [`BitRead::has_more_rbsp_data`], e.g. for speculatively checking for an optional or
+    /// vendor-specific syntax element.
+    ///
+    /// ```
+    /// # use h264_reader::rbsp::{BitRead, BitReader};
+    /// let mut r = BitReader::new(&b"\xa5"[..]);
+    /// assert_eq!(r.peek_bits(4, "nibble").unwrap(), 0xa);
+    /// assert_eq!(r.read_u8(8, "byte").unwrap(), 0xa5);
+    /// ```
+    fn peek_bits(&self, bit_count: u32, name: &'static str) -> Result<u32, BitReaderError>
+    where
+        Self: Clone,
+    {
+        self.clone().read_u32(bit_count, name)
+    }
+
+    /// Fills `buf` with whole bytes, e.g. for a sequence of `user_data_payload_byte`s.
+    ///
+    /// This is efficient when byte-aligned, but also works (reading 8 bits at a time) when not.
+    fn read_bytes(&mut self, buf: &mut [u8], name: &'static str) -> Result<(), BitReaderError> {
+        for b in buf {
+            *b = self.read_u8(8, name)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if positioned at a byte boundary.
+    fn is_byte_aligned(&self) -> bool;
+
+    /// Discards any bits remaining before the next byte boundary. Does nothing if already
+    /// byte-aligned.
+    fn byte_align(&mut self) -> Result<(), BitReaderError> {
+        while !self.is_byte_aligned() {
+            self.read_bool("byte_align")?;
+        }
+        Ok(())
+    }
+
     /// Returns true if positioned before the RBSP trailing bits.
     ///
     /// This matches the definition of `more_rbsp_data()` in Rec. ITU-T H.264
     /// (03/2010) section 7.2.
     fn has_more_rbsp_data(&mut self, name: &'static str) -> Result<bool, BitReaderError>;
 
+    /// Returns true if positioned before the end of an `sei_payload`, excluding its trailing
+    /// bits. The reader must be bounded by the payload, as is the case for
+    /// [`crate::nal::sei::SeiMessage::payload`].
+    ///
+    /// This matches the definition of `more_data_in_payload()` in Rec. ITU-T H.264 (08/2021)
+    /// section D.2.1: unlike `more_rbsp_data()`, when byte-aligned there are no trailing bits,
+    /// so any remaining byte is payload data.
+    fn more_data_in_payload(&mut self, name: &'static str) -> Result<bool, BitReaderError>;
+
     /// Consumes the reader, returning error if it's not positioned at the RBSP trailing bits.
     fn finish_rbsp(self) -> Result<(), BitReaderError>;
 
@@ -260,10 +455,54 @@ pub trait BitRead {
 
 /// Reads H.264 bitstream syntax elements from an RBSP representation (no NAL
 /// header byte or emulation prevention three bytes).
+///
+/// To read from an encoded NAL, use [`crate::nal::Nal::rbsp_bits`], which wraps a
+/// [`ByteReader`] to remove the header byte and emulation prevention bytes on the fly. To read
+/// bytes which are already RBSP (such as those returned by [`decode_nal`]), use
+/// [`BitReader::from_rbsp`] to avoid that overhead.
+#[derive(Clone)]
 pub struct BitReader<R: std::io::BufRead + Clone> {
     reader: bitstream_io::read::BitReader<R, bitstream_io::BigEndian>,
 }
+impl<'a> BitReader<&'a [u8]> {
+    /// Reads from bytes which are already in RBSP form.
+    ///
+    /// No emulation prevention handling is performed; a `0x00 0x00 0x03` sequence is read as-is.
+    /// This is equivalent to `BitReader::new(rbsp)`.
+    ///
+    /// ```
+    /// # use h264_reader::rbsp::{decode_nal, BitRead, BitReader};
+    /// let rbsp = decode_nal(&b"\x68\x12\x34\x00\x00\x03\x00\x86"[..]).unwrap();
+    /// let mut r = BitReader::from_rbsp(&rbsp);
+    /// assert_eq!(r.read_u32(32, "first 4 bytes").unwrap(), 0x1234_0000);
+    /// assert_eq!(r.read_u8(8, "escaped byte").unwrap(), 0x00);
+    /// ```
+    pub fn from_rbsp(rbsp: &'a [u8]) -> Self {
+        Self::new(rbsp)
+    }
+
+    /// Returns the number of whole bytes remaining, or `None` if the reader isn't byte-aligned.
+    ///
+    /// This is only available when reading from a slice; readers such as
+    /// [`crate::nal::Nal::rbsp_bits`] can't tell how much data remains without consuming it.
+    ///
+    /// ```
+    /// # use h264_reader::rbsp::{BitRead, BitReader};
+    /// let mut r = BitReader::from_rbsp(&[0x12, 0x34, 0x56]);
+    /// assert_eq!(r.remaining_bytes(), Some(3));
+    /// r.read_u8(4, "nibble").unwrap();
+    /// assert_eq!(r.remaining_bytes(), None);
+    /// r.read_u8(4, "nibble").unwrap();
+    /// assert_eq!(r.remaining_bytes(), Some(2));
+    /// ```
+    pub fn remaining_bytes(&mut self) -> Option<usize> {
+        self.reader().map(|r| r.len())
+    }
+}
 impl<R: std::io::BufRead + Clone> BitReader<R> {
+    /// Reads from the given RBSP bytes. The reader must not yield the NAL header byte or any
+    /// emulation prevention three bytes; see [`BitReader::from_rbsp`] and
+    /// [`crate::nal::Nal::rbsp_bits`].
     pub fn new(inner: R) -> Self {
         Self {
             reader: bitstream_io::read::BitReader::new(inner),
@@ -275,6 +514,41 @@ impl<R: std::io::BufRead + Clone> BitReader<R> {
         self.reader.reader()
     }
 
+    /// Counts the `cabac_zero_word`s (`0x0000`) following the RBSP trailing bits, without
+    /// consuming anything.
+    ///
+    /// The reader must be positioned at the `rbsp_stop_one_bit`, e.g. after reading the last
+    /// syntax element of the slice data. Returns [`BitReaderError::RemainingData`] if it isn't,
+    /// or if the trailing bits are followed by anything other than zero bytes. A trailing odd
+    /// zero byte isn't counted.
+    pub fn count_trailing_zero_words(&self) -> Result<usize, BitReaderError> {
+        let mut throwaway = self.reader.clone();
+        let mut bit = throwaway
+            .read_bit()
+            .map_err(|e| BitReaderError::ReaderErrorFor("rbsp_stop_one_bit", e))?;
+        if !bit {
+            return Err(BitReaderError::RemainingData);
+        }
+        while !throwaway.byte_aligned() {
+            bit = throwaway
+                .read_bit()
+                .map_err(|e| BitReaderError::ReaderErrorFor("rbsp_alignment_zero_bit", e))?;
+            if bit {
+                return Err(BitReaderError::RemainingData);
+            }
+        }
+        let mut zero_bytes = 0;
+        loop {
+            match throwaway.read::<u8>(8) {
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(BitReaderError::ReaderErrorFor("cabac_zero_word", e)),
+                Ok(0) => zero_bytes += 1,
+                Ok(_) => return Err(BitReaderError::RemainingData),
+            }
+        }
+        Ok(zero_bytes / 2)
+    }
+
     /// Unwraps internal reader and disposes of BitReader.
     ///
     /// # Warning
@@ -335,6 +609,21 @@ impl<R: std::io::BufRead + Clone> BitRead for BitReader<R> {
             .map_err(|e| BitReaderError::ReaderErrorFor(name, e))
     }
 
+    fn read_bytes(&mut self, buf: &mut [u8], name: &'static str) -> Result<(), BitReaderError> {
+        self.reader
+            .read_bytes(buf)
+            .map_err(|e| BitReaderError::ReaderErrorFor(name, e))
+    }
+
+    fn is_byte_aligned(&self) -> bool {
+        self.reader.byte_aligned()
+    }
+
+    fn byte_align(&mut self) -> Result<(), BitReaderError> {
+        self.reader.byte_align();
+        Ok(())
+    }
+
     fn has_more_rbsp_data(&mut self, name: &'static str) -> Result<bool, BitReaderError> {
         let mut throwaway = self.reader.clone();
         let r = (move || {
@@ -349,6 +638,35 @@ impl<R: std::io::BufRead + Clone> BitRead for BitReader<R> {
         }
     }
 
+    fn more_data_in_payload(&mut self, name: &'static str) -> Result<bool, BitReaderError> {
+        let mut throwaway = self.reader.clone();
+        if throwaway.byte_aligned() {
+            return match throwaway.read_bit() {
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+                Err(e) => Err(BitReaderError::ReaderErrorFor(name, e)),
+                Ok(_) => Ok(true),
+            };
+        }
+        // Check for `payload_bit_equal_to_one`, then `payload_bit_equal_to_zero` up to the byte
+        // boundary, then the end of the payload.
+        let r = (move || {
+            if !throwaway.read_bit()? {
+                return Ok(true);
+            }
+            while !throwaway.byte_aligned() {
+                if throwaway.read_bit()? {
+                    return Ok(true);
+                }
+            }
+            match throwaway.read_bit() {
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+                Err(e) => Err(e),
+                Ok(_) => Ok(true),
+            }
+        })();
+        r.map_err(|e| BitReaderError::ReaderErrorFor(name, e))
+    }
+
     fn finish_rbsp(mut self) -> Result<(), BitReaderError> {
         // The next bit is expected to be the final one bit.
         if !self
@@ -389,6 +707,118 @@ fn golomb_to_signed(val: u32) -> i32 {
     ((val >> 1) as i32 + (val & 0x1) as i32) * sign
 }
 
+/// Writes H.264 bitstream syntax elements; the counterpart of [`BitRead`].
+pub trait BitWrite {
+    fn write_ue(&mut self, value: u32) -> std::io::Result<()>;
+    fn write_se(&mut self, value: i32) -> std::io::Result<()>;
+    fn write_bool(&mut self, value: bool) -> std::io::Result<()>;
+    fn write_u8(&mut self, bit_count: u32, value: u8) -> std::io::Result<()>;
+    fn write_u16(&mut self, bit_count: u32, value: u16) -> std::io::Result<()>;
+    fn write_u32(&mut self, bit_count: u32, value: u32) -> std::io::Result<()>;
+
+    /// Returns true if positioned at a byte boundary.
+    fn is_byte_aligned(&self) -> bool;
+
+    /// Writes the `rbsp_stop_one_bit` followed by zero bits up to the next byte boundary.
+    fn write_rbsp_trailing_bits(&mut self) -> std::io::Result<()>;
+}
+
+/// Writes H.264 bitstream syntax elements in RBSP representation (no NAL header byte or
+/// emulation prevention three bytes).
+///
+/// ```
+/// # use h264_reader::rbsp::{BitRead, BitReader, BitWrite, BitWriter};
+/// let mut w = BitWriter::new(Vec::new());
+/// w.write_ue(7).unwrap();
+/// w.write_se(-2).unwrap();
+/// w.write_rbsp_trailing_bits().unwrap();
+/// let rbsp = w.into_writer();
+/// let mut r = BitReader::from_rbsp(&rbsp);
+/// assert_eq!(r.read_ue("a").unwrap(), 7);
+/// assert_eq!(r.read_se("b").unwrap(), -2);
+/// r.finish_rbsp().unwrap();
+/// ```
+pub struct BitWriter<W: std::io::Write> {
+    writer: bitstream_io::write::BitWriter<W, bitstream_io::BigEndian>,
+}
+impl<W: std::io::Write> BitWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            writer: bitstream_io::write::BitWriter::new(inner),
+        }
+    }
+
+    /// Borrows the underlying writer if byte-aligned.
+    pub fn writer(&mut self) -> Option<&mut W> {
+        self.writer.writer()
+    }
+
+    /// Unwraps internal writer and disposes of BitWriter.
+    ///
+    /// # Warning
+    ///
+    /// Any unwritten partial bits are discarded.
+    pub fn into_writer(self) -> W {
+        self.writer.into_writer()
+    }
+}
+
+impl<W: std::io::Write> BitWrite for BitWriter<W> {
+    fn write_ue(&mut self, value: u32) -> std::io::Result<()> {
+        // As with read_ue, values up to u32::MAX - 1 (a 31-bit prefix of leading zeros) are
+        // supported; u32::MAX would need a 32-bit prefix, which read_ue rejects.
+        if value == u32::MAX {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "ue(v) value out of range",
+            ));
+        }
+        let code_num = u64::from(value) + 1;
+        let leading_zero_bits = 63 - code_num.leading_zeros();
+        self.writer.write_unary1(leading_zero_bits)?;
+        if leading_zero_bits > 0 {
+            self.writer
+                .write(leading_zero_bits, code_num - (1 << leading_zero_bits))?;
+        }
+        Ok(())
+    }
+
+    fn write_se(&mut self, value: i32) -> std::io::Result<()> {
+        let value = i64::from(value);
+        let code_num = if value > 0 { 2 * value - 1 } else { -2 * value };
+        // i32::MIN maps to 2^32, which can't be represented.
+        let code_num = u32::try_from(code_num).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "se(v) value out of range")
+        })?;
+        self.write_ue(code_num)
+    }
+
+    fn write_bool(&mut self, value: bool) -> std::io::Result<()> {
+        self.writer.write_bit(value)
+    }
+
+    fn write_u8(&mut self, bit_count: u32, value: u8) -> std::io::Result<()> {
+        self.writer.write(bit_count, value)
+    }
+
+    fn write_u16(&mut self, bit_count: u32, value: u16) -> std::io::Result<()> {
+        self.writer.write(bit_count, value)
+    }
+
+    fn write_u32(&mut self, bit_count: u32, value: u32) -> std::io::Result<()> {
+        self.writer.write(bit_count, value)
+    }
+
+    fn is_byte_aligned(&self) -> bool {
+        self.writer.byte_aligned()
+    }
+
+    fn write_rbsp_trailing_bits(&mut self) -> std::io::Result<()> {
+        self.writer.write_bit(true)?;
+        self.writer.byte_align()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -421,6 +851,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn byte_reader_trailing_zeros() {
+        for (data, expected) in [
+            (&hex!("65 88 00 00")[..], &hex!("88 00 00")[..]),
+            (&hex!("65 88 00")[..], &hex!("88 00")[..]),
+            (&hex!("65 88 00 00 03")[..], &hex!("88 00 00")[..]),
+            (
+                &hex!("65 88 00 00 03 00 00")[..],
+                &hex!("88 00 00 00 00")[..],
+            ),
+        ] {
+            for i in 1..data.len() {
+                let (head, tail) = data.split_at(i);
+                let mut r = ByteReader::new(head.chain(tail));
+                let mut rbsp = Vec::new();
+                r.read_to_end(&mut rbsp).unwrap();
+                assert_eq!(rbsp, expected, "{:02x} split_at({})", data.as_hex(), i);
+            }
+        }
+    }
+
+    #[test]
+    fn byte_reader_position_tracking() {
+        let data = hex!(
+            "67 64 00 0A AC 72 84 44 26 84 00 00 03
+            00 04 00 00 03 00 CA 3C 48 96 11 80"
+        );
+        for i in 1..data.len() - 1 {
+            let (head, tail) = data.split_at(i);
+            let mut r = ByteReader::with_position_tracking(head.chain(tail));
+            let mut rbsp = Vec::new();
+            r.read_to_end(&mut rbsp).unwrap();
+            assert_eq!(
+                r.removed_positions(),
+                Some(&[12, 17][..]),
+                "split_at({})",
+                i
+            );
+            for (rbsp_offset, &b) in rbsp.iter().enumerate() {
+                let nal_offset = r.nal_offset(rbsp_offset).unwrap();
+                assert_eq!(data[nal_offset], b, "rbsp_offset {}", rbsp_offset);
+            }
+            assert_eq!(r.nal_offset(11), Some(13));
+            assert_eq!(r.nal_offset(15), Some(18));
+        }
+        let r = ByteReader::new(&data[..]);
+        assert_eq!(r.removed_positions(), None);
+        assert_eq!(r.nal_offset(0), None);
+    }
+
     #[test]
     fn bitreader_has_more_data() {
         // Should work when the end bit is byte-aligned.
@@ -442,6 +922,93 @@ mod tests {
             .unwrap());
     }
 
+    #[test]
+    fn bitreader_read_bytes() {
+        let mut reader = BitReader::new(&[0x12, 0x34, 0x56, 0x78, 0x9a][..]);
+        let mut buf = [0; 2];
+        reader.read_bytes(&mut buf, "aligned").unwrap();
+        assert_eq!(buf, [0x12, 0x34]);
+        assert_eq!(reader.read_u8(4, "nibble").unwrap(), 0x5);
+        reader.read_bytes(&mut buf, "unaligned").unwrap();
+        assert_eq!(buf, [0x67, 0x89]);
+        assert!(matches!(
+            reader.read_bytes(&mut buf, "eof"),
+            Err(BitReaderError::ReaderErrorFor("eof", _))
+        ));
+    }
+
+    #[test]
+    fn bitreader_byte_align() {
+        let mut reader = BitReader::new(&[0b1010_1111, 0x12][..]);
+        assert!(reader.is_byte_aligned());
+        reader.byte_align().unwrap();
+        assert_eq!(reader.read_u8(3, "bits").unwrap(), 0b101);
+        assert!(!reader.is_byte_aligned());
+        reader.byte_align().unwrap();
+        assert!(reader.is_byte_aligned());
+        assert_eq!(reader.read_u8(8, "byte").unwrap(), 0x12);
+    }
+
+    #[test]
+    fn bitreader_more_data_in_payload() {
+        // Byte-aligned: any remaining byte is data, even if it looks like trailing bits.
+        let mut reader = BitReader::new(&[0x80][..]);
+        assert!(reader.more_data_in_payload("data").unwrap());
+        reader.read_u8(8, "data").unwrap();
+        assert!(!reader.more_data_in_payload("end").unwrap());
+
+        // Unaligned, followed by the trailing bits.
+        let mut reader = BitReader::new(&[0b1011_1000][..]);
+        reader.read_u8(4, "data").unwrap();
+        assert!(!reader.more_data_in_payload("trailing").unwrap());
+
+        // Unaligned, followed by more data.
+        let mut reader = BitReader::new(&[0b1011_1010][..]);
+        reader.read_u8(4, "data").unwrap();
+        assert!(reader.more_data_in_payload("data").unwrap());
+        let mut reader = BitReader::new(&[0b1011_1000, 0x00][..]);
+        reader.read_u8(4, "data").unwrap();
+        assert!(reader.more_data_in_payload("data").unwrap());
+    }
+
+    #[test]
+    fn bitreader_count_trailing_zero_words() {
+        let mut reader = BitReader::new(&[0x12, 0x80, 0x00, 0x00, 0x00, 0x00][..]);
+        assert!(matches!(
+            reader.count_trailing_zero_words(),
+            Err(BitReaderError::RemainingData)
+        ));
+        assert_eq!(reader.read_u8(8, "u8 1").unwrap(), 0x12);
+        assert_eq!(reader.count_trailing_zero_words().unwrap(), 2);
+        // The count doesn't consume anything.
+        reader.finish_rbsp().unwrap();
+
+        // Unaligned stop bit, no cabac-zero-words.
+        let mut reader = BitReader::new(&[0x18][..]);
+        assert_eq!(reader.read_u8(4, "u8 2").unwrap(), 0x1);
+        assert_eq!(reader.count_trailing_zero_words().unwrap(), 0);
+
+        // Non-zero data after the trailing bits.
+        let reader = BitReader::new(&[0x80, 0x00, 0x00, 0x01][..]);
+        assert!(matches!(
+            reader.count_trailing_zero_words(),
+            Err(BitReaderError::RemainingData)
+        ));
+    }
+
+    #[test]
+    fn peek_bits() {
+        let mut r = BitReader::new(&[0b0010_1000][..]);
+        // The prefix of ue(v) 4.
+        assert_eq!(r.peek_bits(3, "prefix").unwrap(), 0b001);
+        assert_eq!(r.read_ue("value").unwrap(), 4);
+        assert_eq!(r.peek_bits(3, "rest").unwrap(), 0b000);
+        assert!(matches!(
+            r.peek_bits(4, "too many"),
+            Err(BitReaderError::ReaderErrorFor("too many", _))
+        ));
+    }
+
     #[test]
     fn read_ue_overflow() {
         let mut reader = BitReader::new(&[0, 0, 0, 0, 255, 255, 255, 255, 255][..]);
@@ -450,4 +1017,30 @@ mod tests {
             Err(BitReaderError::ExpGolombTooLarge("test"))
         ));
     }
+
+    #[test]
+    fn bit_writer() {
+        let mut w = BitWriter::new(Vec::new());
+        for v in [0, 1, 2, 254, u32::MAX - 1] {
+            w.write_ue(v).unwrap();
+        }
+        assert!(w.write_ue(u32::MAX).is_err());
+        for v in [0, 1, -1, i32::MAX, i32::MIN + 1] {
+            w.write_se(v).unwrap();
+        }
+        assert!(w.write_se(i32::MIN).is_err());
+        w.write_u8(3, 5).unwrap();
+        w.write_rbsp_trailing_bits().unwrap();
+        assert!(w.is_byte_aligned());
+        let rbsp = w.into_writer();
+        let mut r = BitReader::from_rbsp(&rbsp);
+        for v in [0, 1, 2, 254, u32::MAX - 1] {
+            assert_eq!(r.read_ue("ue").unwrap(), v);
+        }
+        for v in [0, 1, -1, i32::MAX, i32::MIN + 1] {
+            assert_eq!(r.read_se("se").unwrap(), v);
+        }
+        assert_eq!(r.read_u8(3, "u8").unwrap(), 5);
+        r.finish_rbsp().unwrap();
+    }
 }
@@ -0,0 +1,269 @@
+//! A depacketizer for the RTP payload format for H264 defined in
+//! [RFC 6184](https://www.rfc-editor.org/rfc/rfc6184), as used when H264 data is carried in RTP
+//! (for example via RTSP or WebRTC).
+//!
+//! Supports single NAL unit packets, `STAP-A` aggregation packets and `FU-A` fragmentation units,
+//! which together cover the _non-interleaved_ packetization mode.
+
+#[cfg(feature = "log")]
+use log::*;
+
+use crate::push::{AccumulatedNalHandler, NalAccumulator, NalFragmentHandler};
+
+const STAP_A: u8 = 24;
+const FU_A: u8 = 28;
+
+/// Push parser for RTP payloads which delegates to a [NalFragmentHandler], most commonly a
+/// [NalAccumulator]:
+///
+/// ```
+/// use h264_reader::rtp::RtpDepacketizer;
+/// use h264_reader::nal::{Nal, RefNal, UnitType};
+/// use h264_reader::push::NalInterest;
+///
+/// let mut calls = Vec::new();
+/// let mut depacketizer = RtpDepacketizer::accumulate(|nal: RefNal<'_>| {
+///     let nal_unit_type = nal.header().unwrap().nal_unit_type();
+///     calls.push((nal_unit_type, nal.is_complete()));
+///     NalInterest::Buffer
+/// });
+///
+/// // A STAP-A packet aggregating a SeqParameterSet and a PicParameterSet.
+/// depacketizer.push(&b"\x78\x00\x04\x67\x64\x00\x0A\x00\x02\x68\xE8"[..]);
+///
+/// // An IDR slice, fragmented over two FU-A packets.
+/// depacketizer.push(&b"\x7c\x85\x88\x81"[..]);
+/// depacketizer.push(&b"\x7c\x45\x00\x05"[..]);
+///
+/// assert_eq!(calls, &[
+///     (UnitType::SeqParameterSet, true),
+///     (UnitType::PicParameterSet, true),
+///     (UnitType::SliceLayerWithoutPartitioningIdr, false),
+///     (UnitType::SliceLayerWithoutPartitioningIdr, true),
+/// ]);
+/// ```
+///
+/// RTP headers must already have been removed, and packets must be supplied in sequence number
+/// order. When packet loss is detected, call [`RtpDepacketizer::reset`].
+///
+/// When a malformed or unsupported packet is encountered, the `RtpDepacketizer` logs an error
+/// and ignores the packet. If a fragmented NAL is interrupted, the fragments received so far are
+/// delivered to the handler as a complete (truncated) NAL.
+pub struct RtpDepacketizer<H: NalFragmentHandler> {
+    /// True if the start of an `FU-A` fragmented NAL has been pushed but not its end.
+    in_fragmented_unit: bool,
+    inner: H,
+}
+impl<H: AccumulatedNalHandler> RtpDepacketizer<NalAccumulator<H>> {
+    /// Constructs an `RtpDepacketizer` with a `NalAccumulator`.
+    pub fn accumulate(inner: H) -> Self {
+        Self::for_fragment_handler(NalAccumulator::new(inner))
+    }
+
+    /// Gets a reference to the underlying [AccumulatedNalHandler].
+    pub fn nal_handler_ref(&self) -> &H {
+        self.inner.handler()
+    }
+
+    /// Gets a mutable reference to the underlying [AccumulatedNalHandler].
+    pub fn nal_handler_mut(&mut self) -> &mut H {
+        self.inner.handler_mut()
+    }
+
+    /// Unwraps the `RtpDepacketizer<H>`, returning the inner [AccumulatedNalHandler].
+    pub fn into_nal_handler(self) -> H {
+        self.inner.into_handler()
+    }
+}
+impl<H: NalFragmentHandler> RtpDepacketizer<H> {
+    /// Constructs an `RtpDepacketizer` with a custom [`NalFragmentHandler`].
+    pub fn for_fragment_handler(inner: H) -> Self {
+        RtpDepacketizer {
+            in_fragmented_unit: false,
+            inner,
+        }
+    }
+
+    /// Gets a reference to the underlying [NalFragmentHandler].
+    pub fn fragment_handler_ref(&self) -> &H {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying [NalFragmentHandler].
+    pub fn fragment_handler_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
+    /// Unwraps the `RtpDepacketizer<H>`, returning the inner [NalFragmentHandler].
+    pub fn into_fragment_handler(self) -> H {
+        self.inner
+    }
+
+    /// Pushes the payload of a single RTP packet (i.e. without the RTP header).
+    pub fn push(&mut self, payload: &[u8]) {
+        let Some(&header) = payload.first() else {
+            error!("RtpDepacketizer: empty payload");
+            return;
+        };
+        match header & 0b0001_1111 {
+            1..=23 => {
+                self.end_fragmented_unit();
+                self.inner.nal_fragment(&[payload], true);
+            }
+            STAP_A => {
+                self.end_fragmented_unit();
+                self.push_stap_a(&payload[1..]);
+            }
+            FU_A => self.push_fu_a(header, &payload[1..]),
+            t => {
+                error!("RtpDepacketizer: unsupported packet type {}", t);
+            }
+        }
+    }
+
+    /// To be invoked when packet loss is detected, or when no further packets will be pushed.
+    ///
+    /// If a fragmented NAL is in progress, the fragments received so far are delivered as a
+    /// complete NAL.
+    pub fn reset(&mut self) {
+        self.end_fragmented_unit();
+    }
+
+    fn end_fragmented_unit(&mut self) {
+        if self.in_fragmented_unit {
+            warn!("RtpDepacketizer: fragmented NAL unit was not finished");
+            self.inner.nal_fragment(&[], true);
+            self.in_fragmented_unit = false;
+        }
+    }
+
+    fn push_stap_a(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if data.len() < 2 {
+                error!("RtpDepacketizer: STAP-A has {} trailing bytes", data.len());
+                return;
+            }
+            let len = usize::from(u16::from(data[0]) << 8 | u16::from(data[1]));
+            data = &data[2..];
+            if len == 0 || len > data.len() {
+                error!(
+                    "RtpDepacketizer: STAP-A NAL unit size {} invalid with {} bytes remaining",
+                    len,
+                    data.len()
+                );
+                return;
+            }
+            let (nal, rest) = data.split_at(len);
+            self.inner.nal_fragment(&[nal], true);
+            data = rest;
+        }
+    }
+
+    fn push_fu_a(&mut self, indicator: u8, data: &[u8]) {
+        let Some((&fu_header, data)) = data.split_first() else {
+            error!("RtpDepacketizer: FU-A is missing its header");
+            return;
+        };
+        let start = fu_header & 0b1000_0000 != 0;
+        let end = fu_header & 0b0100_0000 != 0;
+        if start {
+            self.end_fragmented_unit();
+            let nal_header = (indicator & 0b1110_0000) | (fu_header & 0b0001_1111);
+            if data.is_empty() {
+                self.inner.nal_fragment(&[&[nal_header][..]], end);
+            } else {
+                self.inner.nal_fragment(&[&[nal_header][..], data], end);
+            }
+        } else if !self.in_fragmented_unit {
+            error!("RtpDepacketizer: FU-A continuation without a start fragment");
+            return;
+        } else if !data.is_empty() {
+            self.inner.nal_fragment(&[data], end);
+        } else if end {
+            self.inner.nal_fragment(&[], true);
+        }
+        self.in_fragmented_unit = !end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockFragmentHandler {
+        nals: Vec<Vec<u8>>,
+        current: Vec<u8>,
+    }
+    impl NalFragmentHandler for MockFragmentHandler {
+        fn nal_fragment(&mut self, bufs: &[&[u8]], end: bool) {
+            assert!(!bufs.is_empty() || end);
+            for buf in bufs {
+                assert!(!buf.is_empty());
+                self.current.extend_from_slice(buf);
+            }
+            if end {
+                self.nals.push(std::mem::take(&mut self.current));
+            }
+        }
+    }
+
+    #[test]
+    fn single_nal() {
+        let mut d = RtpDepacketizer::for_fragment_handler(MockFragmentHandler::default());
+        d.push(&[0x67, 1, 2, 3]);
+        d.push(&[0x68, 4]);
+        assert_eq!(
+            d.into_fragment_handler().nals,
+            vec![vec![0x67, 1, 2, 3], vec![0x68, 4]]
+        );
+    }
+
+    #[test]
+    fn stap_a() {
+        let mut d = RtpDepacketizer::for_fragment_handler(MockFragmentHandler::default());
+        d.push(&[0x78, 0, 2, 0x67, 1, 0, 3, 0x68, 2, 3]);
+        assert_eq!(
+            d.into_fragment_handler().nals,
+            vec![vec![0x67, 1], vec![0x68, 2, 3]]
+        );
+    }
+
+    #[test]
+    fn stap_a_truncated() {
+        let mut d = RtpDepacketizer::for_fragment_handler(MockFragmentHandler::default());
+        // The second NAL claims 5 bytes but only 2 are present; the first is still delivered.
+        d.push(&[0x78, 0, 2, 0x67, 1, 0, 5, 0x68, 2]);
+        assert_eq!(d.into_fragment_handler().nals, vec![vec![0x67, 1]]);
+    }
+
+    #[test]
+    fn fu_a() {
+        let mut d = RtpDepacketizer::for_fragment_handler(MockFragmentHandler::default());
+        d.push(&[0x7c, 0x85, 1, 2]);
+        d.push(&[0x7c, 0x05, 3]);
+        assert!(d.fragment_handler_ref().nals.is_empty());
+        d.push(&[0x7c, 0x45, 4]);
+        assert_eq!(d.into_fragment_handler().nals, vec![vec![0x65, 1, 2, 3, 4]]);
+    }
+
+    #[test]
+    fn fu_a_interrupted() {
+        let mut d = RtpDepacketizer::for_fragment_handler(MockFragmentHandler::default());
+        // Continuation without a start is ignored.
+        d.push(&[0x7c, 0x05, 9]);
+        d.push(&[0x7c, 0x85, 1]);
+        // A single NAL unit packet ends the unfinished fragmented NAL.
+        d.push(&[0x68, 2]);
+        // As does a reset.
+        d.push(&[0x7c, 0x85, 3]);
+        d.reset();
+        // Unsupported packet types and empty payloads are ignored.
+        d.push(&[0x79, 0, 0]);
+        d.push(&[]);
+        assert_eq!(
+            d.into_fragment_handler().nals,
+            vec![vec![0x65, 1], vec![0x68, 2], vec![0x65, 3]]
+        );
+    }
+}
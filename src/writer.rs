@@ -0,0 +1,182 @@
+//! Writing of NAL units with either of the common framings: the start codes of
+//! _ITU-T Recommendation H.264 - Annex B_ (see [`crate::annexb`]), or the length prefixes used in
+//! the _ISO Base Media File Format_ (see [`crate::avcc`]).
+//!
+//! Together with the readers, this allows transmuxing between the two framings.
+
+use std::io::Write;
+
+pub(crate) const START_CODE: [u8; 4] = [0x00, 0x00, 0x00, 0x01];
+
+/// Writes NAL units to an underlying [`Write`].
+///
+/// The NAL bytes are written as given, so must already include the NAL header and any
+/// emulation prevention bytes.
+///
+/// ```
+/// use h264_reader::writer::NalWriter;
+///
+/// let mut w = NalWriter::new(Vec::new());
+/// w.write_annexb(&b"\x09\xf0"[..]).unwrap();
+/// w.write_avcc(&b"\x09\xf0"[..], 2).unwrap();
+/// assert_eq!(w.into_inner(), b"\x00\x00\x00\x01\x09\xf0\x00\x02\x09\xf0");
+/// ```
+pub struct NalWriter<W: Write> {
+    inner: W,
+}
+impl<W: Write> NalWriter<W> {
+    pub fn new(inner: W) -> Self {
+        NalWriter { inner }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps the `NalWriter<W>`, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes a NAL preceded by a four-byte Annex B start code (`00 00 00 01`).
+    ///
+    /// A NAL ending in a `0x00` byte can't be represented in Annex B framing, since the byte
+    /// would be taken as part of the following start code; this returns an error of kind
+    /// [`std::io::ErrorKind::InvalidInput`] in that case, or if `nal` is empty.
+    pub fn write_annexb(&mut self, nal: &[u8]) -> std::io::Result<()> {
+        match nal.last() {
+            None => return Err(invalid_input("empty NAL")),
+            Some(0x00) => return Err(invalid_input("NAL ends with a zero byte")),
+            Some(_) => {}
+        }
+        self.inner.write_all(&START_CODE)?;
+        self.inner.write_all(nal)
+    }
+
+    /// Writes a NAL preceded by its length as a big-endian integer of `length_size` bytes.
+    ///
+    /// `length_size` must be 1, 2 or 4, as given by
+    /// [`crate::avcc::AvcDecoderConfigurationRecord::length_size_minus_one`] plus one. Returns an
+    /// error of kind [`std::io::ErrorKind::InvalidInput`] if it isn't, or if the NAL's length
+    /// can't be represented in `length_size` bytes.
+    pub fn write_avcc(&mut self, nal: &[u8], length_size: u8) -> std::io::Result<()> {
+        if !matches!(length_size, 1 | 2 | 4) {
+            return Err(invalid_input("length_size must be 1, 2 or 4"));
+        }
+        let len = nal.len() as u64;
+        if len >= 1 << (8 * u32::from(length_size)) {
+            return Err(invalid_input("NAL too long for length_size"));
+        }
+        let prefix = len.to_be_bytes();
+        self.inner
+            .write_all(&prefix[prefix.len() - usize::from(length_size)..])?;
+        self.inner.write_all(nal)
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn invalid_input(msg: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annexb::AnnexBReader;
+    use crate::nal::{Nal, RefNal};
+    use crate::push::NalInterest;
+    use hex_literal::hex;
+    use std::io::Read;
+
+    const NALS: [&[u8]; 3] = [
+        &hex!("67 64 00 0A AC 72 84 44 26 84 00 00 03 00 04 00 00 03 00 CA 3C 48 96 11 80"),
+        &hex!("68 E8 43 8F 13 21 30"),
+        &hex!("65 88 81 00 2a 80"),
+    ];
+
+    fn read_annexb(data: &[u8]) -> Vec<Vec<u8>> {
+        let mut nals = Vec::new();
+        let mut reader = AnnexBReader::accumulate(|nal: RefNal<'_>| {
+            if nal.is_complete() {
+                let mut buf = Vec::new();
+                nal.reader().read_to_end(&mut buf).unwrap();
+                nals.push(buf);
+            }
+            NalInterest::Buffer
+        });
+        reader.push(data);
+        reader.reset();
+        drop(reader);
+        nals
+    }
+
+    fn read_avcc(mut data: &[u8], length_size: usize) -> Vec<Vec<u8>> {
+        let mut nals = Vec::new();
+        while !data.is_empty() {
+            let len = data[..length_size]
+                .iter()
+                .fold(0usize, |len, &b| len << 8 | usize::from(b));
+            nals.push(data[length_size..length_size + len].to_vec());
+            data = &data[length_size + len..];
+        }
+        nals
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut w = NalWriter::new(Vec::new());
+        for nal in NALS {
+            w.write_annexb(nal).unwrap();
+        }
+        let annexb = w.into_inner();
+        let nals = read_annexb(&annexb);
+        assert_eq!(nals, NALS);
+
+        // Transmux to AVC framing, and back again.
+        for length_size in [1, 2, 4] {
+            let mut w = NalWriter::new(Vec::new());
+            for nal in &nals {
+                w.write_avcc(nal, length_size).unwrap();
+            }
+            let avcc = w.into_inner();
+            let avcc_nals = read_avcc(&avcc, usize::from(length_size));
+            assert_eq!(avcc_nals, NALS);
+
+            let mut w = NalWriter::new(Vec::new());
+            for nal in &avcc_nals {
+                w.write_annexb(nal).unwrap();
+            }
+            assert_eq!(w.into_inner(), annexb);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut w = NalWriter::new(Vec::new());
+        let kind = |r: std::io::Result<()>| r.unwrap_err().kind();
+        assert_eq!(kind(w.write_annexb(&[])), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            kind(w.write_annexb(&[0x65, 0x00])),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            kind(w.write_avcc(&[0x65], 3)),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            kind(w.write_avcc(&[0x65; 256], 1)),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert!(w.into_inner().is_empty());
+    }
+}